@@ -0,0 +1,10 @@
+fn main() {
+    println!("cargo:rustc-check-cfg=cfg(asan_runtime)");
+    println!("cargo:rerun-if-env-changed=CARGO_CFG_SANITIZE");
+
+    // The AddressSanitizer interface is only linked into builds with -Zsanitizer=address
+    let sanitizers = std::env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    if std::env::var_os("CARGO_FEATURE_ASAN").is_some() && sanitizers.split(',').any(|sanitizer| sanitizer == "address") {
+        println!("cargo:rustc-cfg=asan_runtime");
+    }
+}
@@ -0,0 +1,250 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Error, ForeignItemFn, GenericArgument, Ident, PathArguments, ReturnType, Type, TypeBareFn, Visibility};
+
+use crate::wrap::{Parameter, Role};
+
+/// The callback parameter of a wrapped function, an `extern fn` pointer or an `Option` of one
+pub(crate) struct Callback {
+    function: TypeBareFn,
+    optional: bool,
+    arguments: Vec<Argument>
+}
+
+/// What an argument of a callback is used for
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Argument {
+    /// Handed to the future as it is
+    Value,
+    /// The pointer of a passed parameter, marked with `#[pass]`
+    Pass,
+    /// The userdata handed to the function, marked with `#[userdata]`
+    Userdata
+}
+
+/// Parse the type of a `#[callback]` parameter, removing the `#[pass]` and `#[userdata]` attributes of its arguments
+pub(crate) fn parse(ty: &mut Type) -> syn::Result<Callback> {
+    let span = quote! { #ty };
+    let (function, optional) = match bare_fn(ty) {
+        Some(function) => function,
+        None => return Err(Error::new_spanned(span, "a #[callback] parameter has to be an `extern fn` pointer or an `Option` of one"))
+    };
+    if function.variadic.is_some() {
+        return Err(Error::new_spanned(span, "variadic callbacks cannot be wrapped"));
+    }
+    if let ReturnType::Type(..) = function.output {
+        return Err(Error::new_spanned(span, "the callback cannot return a value, the wrapper has nothing to return to the caller of the callback"));
+    }
+
+    let mut arguments = Vec::new();
+    for input in function.inputs.iter_mut() {
+        let mut argument = Argument::Value;
+        let mut attrs = Vec::new();
+        for attr in input.attrs.drain(..) {
+            let marked = if attr.path().is_ident("pass") {
+                Argument::Pass
+            }
+            else if attr.path().is_ident("userdata") {
+                Argument::Userdata
+            }
+            else {
+                attrs.push(attr);
+                continue;
+            };
+            attr.meta.require_path_only()?;
+            if argument != Argument::Value {
+                return Err(Error::new_spanned(attr, "a callback argument can only have one of #[pass] and #[userdata]"));
+            }
+            argument = marked;
+        }
+        input.attrs = attrs;
+        arguments.push(argument);
+    }
+
+    Ok(Callback {
+        function: function.clone(),
+        optional,
+        arguments
+    })
+}
+
+/// Find the function pointer in `ty`, and whether it is wrapped in an `Option`
+fn bare_fn(ty: &mut Type) -> Option<(&mut TypeBareFn, bool)> {
+    match ty {
+        Type::BareFn(function) => Some((function, false)),
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last_mut()?;
+            if segment.ident != "Option" {
+                return None;
+            }
+            let arguments = match &mut segment.arguments {
+                PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => arguments,
+                _ => return None
+            };
+            match arguments.args.first_mut()? {
+                GenericArgument::Type(Type::BareFn(function)) => Some((function, true)),
+                _ => None
+            }
+        }
+        _ => None
+    }
+}
+
+/// Generate the wrapper of `function` that completes a future from its callback
+pub(crate) fn wrapper(function: &ForeignItemFn, parameters: &[Parameter]) -> syn::Result<TokenStream> {
+    let mut callbacks = parameters.iter().filter_map(|parameter| match &parameter.role {
+        Role::Callback(callback) => Some(callback),
+        _ => None
+    });
+    let callback = match (callbacks.next(), callbacks.next()) {
+        (Some(callback), None) => callback,
+        _ => return Err(Error::new_spanned(&function.sig, "a function with #[userdata] has to have exactly one #[callback] parameter"))
+    };
+    if parameters.iter().filter(|parameter| matches!(parameter.role, Role::Userdata)).count() != 1 {
+        return Err(Error::new_spanned(&function.sig, "a function with a #[callback] has to have exactly one #[userdata] parameter"));
+    }
+    if callback.arguments.iter().filter(|argument| **argument == Argument::Userdata).count() != 1 {
+        return Err(Error::new_spanned(&callback.function, "the callback has to have exactly one #[userdata] argument"));
+    }
+
+    let passed = parameters.iter()
+        .filter(|parameter| parameter.passable().is_some())
+        .collect::<Vec<_>>();
+    if callback.arguments.iter().filter(|argument| **argument == Argument::Pass).count() != passed.len() {
+        return Err(Error::new_spanned(&callback.function, "the callback has to have one #[pass] argument for every #[pass] parameter, in the same order"));
+    }
+
+    let name = &function.sig.ident;
+    let docs = function.attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+    let vis = match &function.vis {
+        Visibility::Inherited => quote! { pub(super) },
+        vis => quote! { #vis }
+    };
+
+    // The items and locals of the wrapper must not shadow the types and parameters of the function
+    let output_type = Ident::new("__PassableCallbackOutput", Span::call_site());
+    let state_type = Ident::new("__PassableCallbackState", Span::call_site());
+    let trampoline = Ident::new("__passable_callback_trampoline", Span::call_site());
+    let state = Ident::new("state", Span::mixed_site());
+    let future = Ident::new("future", Span::mixed_site());
+    let completer = Ident::new("completer", Span::mixed_site());
+    let output = Ident::new("output", Span::mixed_site());
+
+    let inputs = parameters.iter().filter_map(|parameter| {
+        let name = &parameter.name;
+        match &parameter.role {
+            Role::Value => {
+                let ty = &parameter.ty;
+                Some(quote! { #name: #ty })
+            },
+            Role::Pass(passable) => Some(quote! { #name: #passable }),
+            Role::Callback(_) | Role::Userdata => None
+        }
+    });
+
+    let passed_names = passed.iter().map(|parameter| &parameter.name).collect::<Vec<_>>();
+    let passed_types = passed.iter().map(|parameter| parameter.passable()).collect::<Vec<_>>();
+    let passed_strings = passed.iter().map(|parameter| parameter.name.to_string()).collect::<Vec<_>>();
+    let guards = passed.iter().map(|parameter| format_ident!("{}_guard", parameter.name, span = Span::mixed_site())).collect::<Vec<_>>();
+    let pointers = passed.iter().map(|parameter| format_ident!("{}_ptr", parameter.name, span = Span::mixed_site())).collect::<Vec<_>>();
+
+    let callback_names = (0..callback.arguments.len())
+        .map(|index| format_ident!("arg{}", index, span = Span::mixed_site()))
+        .collect::<Vec<_>>();
+    let callback_types = callback.function.inputs.iter().map(|input| &input.ty);
+    let callback_pointers = callback_names.iter()
+        .zip(&callback.arguments)
+        .filter(|(_, argument)| **argument == Argument::Pass)
+        .map(|(name, _)| name);
+    let userdata = callback_names.iter()
+        .zip(&callback.arguments)
+        .find(|(_, argument)| **argument == Argument::Userdata)
+        .map(|(name, _)| name)
+        .expect("the callback has been checked to have a userdata argument");
+
+    // The future completes with the reconstituted parameters followed by the remaining arguments of the callback
+    let mut output_types = passed_types.iter().map(|ty| quote! { #ty }).collect::<Vec<_>>();
+    let mut output_values = guards.iter()
+        .zip(&passed_strings)
+        .map(|(guard, string)| quote! {
+            #guard
+                .map(::passable_guard::PassableContainer::into_inner)
+                .map_err(|error| ::passable_guard::CallError::new(#string, error))?
+        })
+        .collect::<Vec<_>>();
+    for ((name, input), argument) in callback_names.iter().zip(&callback.function.inputs).zip(&callback.arguments) {
+        if *argument == Argument::Value {
+            let ty = &input.ty;
+            output_types.push(quote! { #ty });
+            output_values.push(quote! { #name });
+        }
+    }
+
+    let unsafety = &callback.function.unsafety;
+    let abi = &callback.function.abi;
+    let callback_type = &callback.function;
+    let callback_argument = if callback.optional {
+        quote! { ::std::option::Option::Some(#trampoline as #callback_type) }
+    }
+    else {
+        quote! { #trampoline as #callback_type }
+    };
+
+    let arguments = parameters.iter().map(|parameter| {
+        let name = &parameter.name;
+        match &parameter.role {
+            Role::Value => quote! { #name },
+            Role::Pass(_) => {
+                let pointer = format_ident!("{}_ptr", name, span = Span::mixed_site());
+                quote! { #pointer as _ }
+            },
+            Role::Callback(_) => callback_argument.clone(),
+            Role::Userdata => quote! { #state as _ }
+        }
+    });
+
+    let output_result = quote! { ::std::result::Result<(#(#output_types),*), ::passable_guard::CallError> };
+    let call = quote! { unsafe { super::#name(#(#arguments),*) } };
+    let (returns, returned) = match &function.sig.output {
+        ReturnType::Default => (
+            quote! { ::passable_guard::CallbackFuture<#output_result> },
+            quote! { #call; #future }
+        ),
+        ReturnType::Type(_, ty) => (
+            quote! { (#ty, ::passable_guard::CallbackFuture<#output_result>) },
+            quote! { (#call, #future) }
+        )
+    };
+
+    Ok(quote! {
+        #(#docs)*
+        ///
+        /// Passes the `#[pass]` parameters for the call and returns a future that completes with them reconstituted
+        /// and the remaining arguments of the callback once the callback fires
+        #vis fn #name(#(#inputs),*) -> #returns {
+            type #output_type = #output_result;
+            type #state_type = ((#(::passable_guard::PassableGuard<#passed_types>,)*), ::passable_guard::Completer<#output_type>);
+
+            #[allow(unused_unsafe)]
+            #unsafety #abi fn #trampoline(#(#callback_names: #callback_types),*) {
+                let #state = unsafe { ::std::boxed::Box::from_raw(#userdata as *mut #state_type) };
+                let ((#(#guards,)*), #completer) = *#state;
+                // Reconstitute every parameter before checking the results, so the ones that succeeded are dropped on failure
+                #(
+                    let #guards = unsafe { #guards.reconstitute(#callback_pointers as _) };
+                )*
+                let #output = (|| -> #output_type {
+                    ::std::result::Result::Ok((#(#output_values),*))
+                })();
+                #completer.complete(#output);
+            }
+
+            let (#future, #completer) = ::passable_guard::CallbackFuture::new();
+            #(
+                let (#guards, #pointers) = ::passable_guard::PassableContainer::new(#passed_names).pass();
+            )*
+            let #state: *mut #state_type = ::std::boxed::Box::into_raw(::std::boxed::Box::new(((#(#guards,)*), #completer)));
+            #returned
+        }
+    })
+}
@@ -0,0 +1,45 @@
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{FnArg, Ident, ItemFn, Pat};
+
+pub(crate) fn expand(mut function: ItemFn) -> syn::Result<TokenStream> {
+    let mut passed = Vec::new();
+    for input in function.sig.inputs.iter_mut() {
+        let input = match input {
+            FnArg::Typed(input) => input,
+            FnArg::Receiver(_) => continue
+        };
+
+        let before = input.attrs.len();
+        input.attrs.retain(|attr| !attr.path().is_ident("pass"));
+        if input.attrs.len() == before {
+            continue;
+        }
+
+        match &mut *input.pat {
+            // The parameter holds the Passable, only the pointer it is shadowed with can be mutable
+            Pat::Ident(pat) => passed.push((pat.ident.clone(), pat.mutability.take())),
+            pat => return Err(syn::Error::new_spanned(pat, "#[pass] parameters have to be bound to a name"))
+        }
+    }
+
+    if passed.is_empty() {
+        return Err(syn::Error::new_spanned(&function.sig, "#[guarded] functions need at least one #[pass] parameter"));
+    }
+
+    // Not nameable by the body, so it cannot be moved or finished early
+    let scope = Ident::new("scope", Span::mixed_site());
+    let stmts = &function.block.stmts;
+    let (passed, mutability): (Vec<_>, Vec<_>) = passed.into_iter().unzip();
+    let block = quote! {{
+        // Reconstitutes the passed parameters when the function returns, on every path
+        let mut #scope = unsafe { ::passable_guard::PassScope::new() };
+        #(
+            let #mutability #passed = #scope.pass(::passable_guard::PassableContainer::new(#passed));
+        )*
+        #(#stmts)*
+    }};
+    function.block = syn::parse2(block)?;
+
+    Ok(quote! { #function })
+}
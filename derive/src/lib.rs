@@ -0,0 +1,209 @@
+//! # Passable Guard Derive
+//!
+//! The derive macro behind the `derive` feature of [passable_guard](https://docs.rs/passable_guard),
+//! use it through `passable_guard::Passable`
+
+mod callback;
+mod guarded;
+mod passable;
+mod wrap;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, Error, ItemFn, ItemForeignMod};
+
+/// Implement `Passable` for a struct by passing it as a `#[repr(C)]` struct of raw pointers
+///
+/// Fields marked with `#[passable]` are passed themselves and appear as a raw pointer to their `Pointee`,
+/// all other fields are moved over as they are, so they should be FFI-safe.
+/// The struct is passed as a pointer to a boxed `{Name}Raw` struct, which has the same fields in the same order,
+/// and is reconstituted by reconstituting every passed field again.
+///
+/// If a field cannot be reconstituted, the fields that could be are dropped and a `{Name}ReconstituteError`
+/// naming the first failed field is returned. The name of the raw struct can be changed with `#[passable(raw = "...")]`
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{Passable, PassableContainer};
+///
+/// #[derive(Passable)]
+/// #[repr(C)]
+/// pub struct Request {
+///     #[passable]
+///     pub url: CString,
+///     #[passable]
+///     pub body: Vec<u8>,
+///     pub timeout_ms: u32
+/// }
+///
+/// let request = Request {
+///     url: CString::new("https://example.com").unwrap(),
+///     body: vec![1, 2, 3],
+///     timeout_ms: 500
+/// };
+/// let (guard, ptr) = PassableContainer::new(request).pass();
+///
+/// // Hand `ptr` to C as a `RequestRaw *`, whose fields are an `uint8_t *`, an `uint8_t *` and an `uint32_t`
+/// assert_eq!(unsafe { (*ptr).timeout_ms }, 500);
+///
+/// let request = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(request.body, vec![1, 2, 3]);
+/// ```
+///
+/// ### Notes
+/// Only structs with named fields and without generics are supported.
+/// At most 12 fields can be `#[passable]`, since their metadata is kept in a tuple,
+/// and the reconstitution errors of the fields have to implement `Debug`
+#[proc_macro_derive(Passable, attributes(passable))]
+pub fn derive_passable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    passable::expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Generate safe wrappers for the functions of an `extern "C"` block that take pointers to [Passable]s
+///
+/// A parameter marked with `#[pass(Type)]` becomes a parameter taking an owned `Type` in the wrapper,
+/// which is passed for the call and reconstituted afterwards.
+/// The wrappers return the result of the call followed by the reconstituted parameters,
+/// or a `CallError` if one of them could not be reconstituted, which requires the errors of the passed types to be `Send` and `Sync`.
+/// They are generated in a module next to the block, `guarded` unless named with `#[wrap(name)]`,
+/// which can be given a visibility like `#[wrap(pub name)]`.
+/// Functions without `#[pass]` parameters are not wrapped.
+/// The block has to be at module level, since the generated module imports the types of the parameters from its parent
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use std::os::raw::c_char;
+///
+/// #[passable_guard::wrap]
+/// extern "C" {
+///     /// Get the length of a NULL-terminated string
+///     fn strlen(#[pass(CString)] string: *const c_char) -> usize;
+/// }
+///
+/// fn main() {
+///     let (len, string) = guarded::strlen(CString::new("name").unwrap()).unwrap();
+///     assert_eq!(len, 4);
+///     assert_eq!(string.to_str().unwrap(), "name");
+/// }
+/// ```
+///
+/// A function that reports back through a callback, like `start_op(buffer, callback, userdata)`,
+/// is wrapped into a function returning a `CallbackFuture` when its callback parameter is marked with `#[callback]`
+/// and its userdata parameter with `#[userdata]`.
+/// In the `extern fn` type of the callback, the userdata argument is marked with `#[userdata]`
+/// and the pointers of the passed parameters with `#[pass]`, in the same order as the parameters.
+/// The guards and the completer of the future are moved into the userdata,
+/// a generated trampoline reconstitutes the parameters when the callback fires and completes the future
+/// with them followed by the remaining arguments of the callback.
+/// If the function returns a value, the wrapper returns it together with the future
+///
+/// ``` rust
+/// use std::ffi::c_void;
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll, Waker};
+///
+/// #[passable_guard::wrap]
+/// extern "C" {
+///     /// Fill a buffer with sevens and report the status through `callback`
+///     fn start_fill(
+///         #[pass(Vec<u8>)] buffer: *mut u8,
+///         len: usize,
+///         #[callback] callback: extern "C" fn(#[userdata] userdata: *mut c_void, #[pass] buffer: *mut u8, status: i32),
+///         #[userdata] userdata: *mut c_void
+///     );
+/// }
+///
+/// # mod c {
+/// #     use std::ffi::c_void;
+/// #     #[no_mangle]
+/// #     extern "C" fn start_fill(buffer: *mut u8, len: usize, callback: extern "C" fn(*mut c_void, *mut u8, i32), userdata: *mut c_void) {
+/// #         unsafe { std::ptr::write_bytes(buffer, 7, len) };
+/// #         callback(userdata, buffer, 0);
+/// #     }
+/// # }
+/// fn main() {
+///     let mut future = pin!(guarded::start_fill(vec![0; 4], 4));
+///
+///     // Usually awaited on an executor, the callback could fire on any thread
+///     let mut cx = Context::from_waker(Waker::noop());
+///     let (buffer, status) = match future.as_mut().poll(&mut cx) {
+///         Poll::Ready(result) => result.unwrap(),
+///         Poll::Pending => unreachable!("the example completes the callback right away")
+///     };
+///     assert_eq!(buffer, vec![7; 4]);
+///     assert_eq!(status, 0);
+/// }
+/// ```
+///
+/// ### Safety
+/// Applying the attribute asserts that the functions are safe to call with any values of the remaining parameters
+/// and do not use the passed pointers after they return.
+/// For a function with a `#[callback]`, the pointers may be used until the callback fires,
+/// which has to happen exactly once and with the userdata the function was given
+///
+/// ### Notes
+/// If the callback never fires, because starting the operation failed, the future never completes
+/// and the passed parameters are leaked. A pointer that does not match completes the future with a `CallError`,
+/// its guard applies its policy inside the callback, where a panic aborts
+///
+/// [Passable]: https://docs.rs/passable_guard/latest/passable_guard/trait.Passable.html
+#[proc_macro_attribute]
+pub fn wrap(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as wrap::Args);
+    let block = parse_macro_input!(input as ItemForeignMod);
+    wrap::expand(args, block)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Pass the parameters of a function marked with `#[pass]` on entry and reconstitute them on every exit path
+///
+/// Inside the function a `#[pass]` parameter is the raw pointer of the [Passable] the caller handed in.
+/// The parameters are owned by a `PassScope` that reconstitutes and drops them when the function returns,
+/// also on early returns, `?` and panics, so no branch can forget one
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use std::os::raw::c_char;
+///
+/// extern "C" {
+///     fn strlen(string: *const c_char) -> usize;
+/// }
+///
+/// #[passable_guard::guarded]
+/// fn name_len(#[pass] name: CString) -> Result<usize, &'static str> {
+///     let len = unsafe { strlen(name as *const c_char) };
+///     if len == 0 {
+///         return Err("the name is empty");
+///     }
+///     Ok(len)
+/// }
+///
+/// assert_eq!(name_len(CString::new("name").unwrap()), Ok(4));
+/// assert_eq!(name_len(CString::default()), Err("the name is empty"));
+/// ```
+///
+/// ### Safety
+/// Applying the attribute asserts that the pointers are not used by the FFI after the function returns.
+/// The same restrictions as for `PassScope::new` apply
+///
+/// ### Notes
+/// A [Passable] that cannot be reconstituted is reported to the registered `Reporter`s of `passable_guard`,
+/// or printed to stderr without a registered reporter or without its `diagnostics` feature
+///
+/// [Passable]: https://docs.rs/passable_guard/latest/passable_guard/trait.Passable.html
+#[proc_macro_attribute]
+pub fn guarded(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return Error::new_spanned(proc_macro2::TokenStream::from(args), "#[guarded] takes no arguments")
+            .into_compile_error()
+            .into();
+    }
+    let function = parse_macro_input!(input as ItemFn);
+    guarded::expand(function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,214 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, LitStr};
+
+struct Field {
+    name: Ident,
+    ty: syn::Type,
+    vis: syn::Visibility,
+    attrs: Vec<Attribute>,
+    passable: bool
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "Passable cannot be derived for generic structs"));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "Passable can only be derived for structs with named fields"))
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "Passable can only be derived for structs"))
+    };
+
+    let fields = fields.iter()
+        .map(|field| {
+            let mut passable = false;
+            for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("passable")) {
+                attr.meta.require_path_only()?;
+                passable = true;
+            }
+            Ok(Field {
+                name: field.ident.clone().expect("The fields are named"),
+                ty: field.ty.clone(),
+                vis: field.vis.clone(),
+                attrs: field.attrs.iter()
+                    .filter(|attr| attr.path().is_ident("doc"))
+                    .cloned()
+                    .collect(),
+                passable
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let passed = fields.iter()
+        .filter(|field| field.passable)
+        .collect::<Vec<_>>();
+    if passed.len() > 12 {
+        return Err(Error::new(Span::call_site(), "at most 12 fields can be #[passable]"));
+    }
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let raw = raw_name(&input)?;
+    let error = format_ident!("{}ReconstituteError", name);
+
+    let raw_fields = fields.iter().map(|field| {
+        let Field {name, ty, vis, attrs, passable} = field;
+        if *passable {
+            quote! { #(#attrs)* #vis #name: *mut <#ty as ::passable_guard::Passable>::Pointee }
+        }
+        else {
+            quote! { #(#attrs)* #vis #name: #ty }
+        }
+    });
+    let raw_doc = format!("The `#[repr(C)]` layout a [{}] is passed as", name);
+
+    let field_names = fields.iter().map(|field| &field.name).collect::<Vec<_>>();
+    let passed_names = passed.iter().map(|field| &field.name).collect::<Vec<_>>();
+    let passed_types = passed.iter().map(|field| &field.ty).collect::<Vec<_>>();
+    let metadata_names = passed.iter()
+        .map(|field| format_ident!("{}_metadata", field.name))
+        .collect::<Vec<_>>();
+
+    let (error_type, error_definition) = if passed.is_empty() {
+        (quote! { ::std::convert::Infallible }, quote! {})
+    }
+    else {
+        let variants = passed.iter().map(|field| variant_name(&field.name)).collect::<Vec<_>>();
+        let error_doc = format!("An Error that can occur while reconstituting a [{}]", name);
+        let variant_docs = passed.iter().map(|field| format!("The field `{}` could not be reconstituted", field.name));
+        let field_strings = passed.iter().map(|field| field.name.to_string()).collect::<Vec<_>>();
+
+        let definition = quote! {
+            #[doc = #error_doc]
+            #vis enum #error {
+                #(
+                    #[doc = #variant_docs]
+                    #variants {
+                        error: <#passed_types as ::passable_guard::Passable>::ReconstituteError
+                    },
+                )*
+            }
+
+            impl ::std::fmt::Debug for #error {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match self {
+                        #(
+                            #error::#variants {error} => f.debug_struct(stringify!(#variants))
+                                .field("error", error)
+                                .finish(),
+                        )*
+                    }
+                }
+            }
+
+            impl ::std::fmt::Display for #error {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    match self {
+                        #(
+                            #error::#variants {..} => write!(f, "Could not reconstitute the field `{}`", #field_strings),
+                        )*
+                    }
+                }
+            }
+        };
+
+        (quote! { #error }, definition)
+    };
+
+    let reconstitute_fields = fields.iter().map(|field| {
+        let field_name = &field.name;
+        if field.passable {
+            let variant = variant_name(field_name);
+            quote! { #field_name: #field_name.map_err(|error| #error::#variant {error})? }
+        }
+        else {
+            quote! { #field_name }
+        }
+    });
+
+    Ok(quote! {
+        #[doc = #raw_doc]
+        #[repr(C)]
+        #vis struct #raw {
+            #(#raw_fields,)*
+        }
+
+        #error_definition
+
+        impl ::passable_guard::Passable for #name {
+            type Pointee = #raw;
+            type ReconstituteError = #error_type;
+            type Metadata = (#(<#passed_types as ::passable_guard::Passable>::Metadata,)*);
+
+            fn pass(self) -> (*mut #raw, Self::Metadata) {
+                let #name {#(#field_names),*} = self;
+                #(
+                    let (#passed_names, #metadata_names) = ::passable_guard::Passable::pass(#passed_names);
+                )*
+                let raw = ::std::boxed::Box::new(#raw {#(#field_names),*});
+                (::std::boxed::Box::into_raw(raw), (#(#metadata_names,)*))
+            }
+
+            // A field whose error is `Infallible` makes the code after its `?` unreachable
+            #[allow(unreachable_code)]
+            unsafe fn reconstitute(ptr: *mut #raw, metadata: Self::Metadata) -> ::std::result::Result<Self, Self::ReconstituteError> {
+                let #raw {#(#field_names),*} = *::std::boxed::Box::from_raw(ptr);
+                let (#(#metadata_names,)*) = metadata;
+                // Reconstitute every field before checking the results, so the ones that succeeded are dropped on failure
+                #(
+                    let #passed_names = <#passed_types as ::passable_guard::Passable>::reconstitute(#passed_names, #metadata_names);
+                )*
+                ::std::result::Result::Ok(#name {
+                    #(#reconstitute_fields,)*
+                })
+            }
+
+            fn passed_size(_metadata: &Self::Metadata) -> ::std::option::Option<usize> {
+                ::std::option::Option::Some(::std::mem::size_of::<#raw>())
+            }
+
+            fn passed_extent(metadata: &Self::Metadata) -> ::std::option::Option<usize> {
+                Self::passed_size(metadata)
+            }
+        }
+    })
+}
+
+/// Get the name of the raw struct, `{Name}Raw` unless set with `#[passable(raw = "...")]`
+fn raw_name(input: &DeriveInput) -> syn::Result<Ident> {
+    let mut raw = format_ident!("{}Raw", input.ident);
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("passable")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("raw") {
+                let name: LitStr = meta.value()?.parse()?;
+                raw = name.parse()?;
+                Ok(())
+            }
+            else {
+                Err(meta.error("unknown passable attribute, expected `raw`"))
+            }
+        })?;
+    }
+    Ok(raw)
+}
+
+/// Convert a snake case field name into the camel case name of its error variant
+fn variant_name(field: &Ident) -> Ident {
+    let field = field.to_string();
+    let name = field.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new()
+            }
+        })
+        .collect::<String>();
+    Ident::new(&name, Span::call_site())
+}
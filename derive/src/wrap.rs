@@ -0,0 +1,200 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{FnArg, ForeignItem, ForeignItemFn, Ident, ItemForeignMod, Pat, ReturnType, Type, Visibility};
+
+use crate::callback::{self, Callback};
+
+/// The arguments of the attribute, the visibility and name of the generated module
+pub(crate) struct Args {
+    vis: Visibility,
+    module: Ident
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let module = if input.is_empty() {
+            Ident::new("guarded", Span::call_site())
+        }
+        else {
+            input.parse()?
+        };
+        Ok(Self {vis, module})
+    }
+}
+
+/// A parameter of a wrapped function
+pub(crate) struct Parameter {
+    pub(crate) name: Ident,
+    pub(crate) ty: Type,
+    pub(crate) role: Role
+}
+
+/// What a parameter of a wrapped function is used for
+pub(crate) enum Role {
+    /// Moved over as it is
+    Value,
+    /// Passed from the `Passable` given in `#[pass(...)]`
+    Pass(Type),
+    /// The callback completing the operation, marked with `#[callback]`
+    Callback(Callback),
+    /// The userdata handed to the callback, marked with `#[userdata]`
+    Userdata
+}
+
+impl Parameter {
+    /// Get the `Passable` the parameter is passed from, if it is marked with `#[pass(...)]`
+    pub(crate) fn passable(&self) -> Option<&Type> {
+        match &self.role {
+            Role::Pass(passable) => Some(passable),
+            _ => None
+        }
+    }
+}
+
+pub(crate) fn expand(args: Args, mut block: ItemForeignMod) -> syn::Result<TokenStream> {
+    let mut wrappers = Vec::new();
+    for item in block.items.iter_mut() {
+        if let ForeignItem::Fn(function) = item {
+            let parameters = parameters(function)?;
+            if parameters.iter().any(|parameter| matches!(parameter.role, Role::Callback(_) | Role::Userdata)) {
+                wrappers.push(callback::wrapper(function, &parameters)?);
+            }
+            else if parameters.iter().any(|parameter| parameter.passable().is_some()) {
+                wrappers.push(wrapper(function, &parameters));
+            }
+        }
+    }
+
+    let Args {vis, module} = args;
+    Ok(quote! {
+        #block
+
+        #vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#wrappers)*
+        }
+    })
+}
+
+/// Collect the parameters of `function`, removing the `#[pass(...)]`, `#[callback]` and `#[userdata]` attributes the extern block cannot keep
+fn parameters(function: &mut ForeignItemFn) -> syn::Result<Vec<Parameter>> {
+    let mut parameters = Vec::new();
+    for (index, input) in function.sig.inputs.iter_mut().enumerate() {
+        let input = match input {
+            FnArg::Typed(input) => input,
+            FnArg::Receiver(receiver) => return Err(syn::Error::new_spanned(receiver, "extern functions cannot take self"))
+        };
+
+        let mut role = Role::Value;
+        let mut attrs = Vec::new();
+        for attr in input.attrs.drain(..) {
+            let marked = if attr.path().is_ident("pass") {
+                Role::Pass(attr.parse_args::<Type>()?)
+            }
+            else if attr.path().is_ident("callback") {
+                attr.meta.require_path_only()?;
+                Role::Callback(callback::parse(&mut input.ty)?)
+            }
+            else if attr.path().is_ident("userdata") {
+                attr.meta.require_path_only()?;
+                Role::Userdata
+            }
+            else {
+                attrs.push(attr);
+                continue;
+            };
+            if !matches!(role, Role::Value) {
+                return Err(syn::Error::new_spanned(attr, "a parameter can only have one of #[pass], #[callback] and #[userdata]"));
+            }
+            role = marked;
+        }
+        input.attrs = attrs;
+
+        let name = match &*input.pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            _ => format_ident!("arg{}", index)
+        };
+        if !matches!(role, Role::Value) && function.sig.variadic.is_some() {
+            return Err(syn::Error::new_spanned(&function.sig, "variadic functions cannot be wrapped"));
+        }
+        parameters.push(Parameter {
+            name,
+            ty: (*input.ty).clone(),
+            role
+        });
+    }
+    Ok(parameters)
+}
+
+/// Generate the safe wrapper of `function`
+fn wrapper(function: &ForeignItemFn, parameters: &[Parameter]) -> TokenStream {
+    let name = &function.sig.ident;
+    let docs = function.attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+    let vis = match &function.vis {
+        Visibility::Inherited => quote! { pub(super) },
+        vis => quote! { #vis }
+    };
+    let output = match &function.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty }
+    };
+
+    let inputs = parameters.iter().map(|parameter| {
+        let name = &parameter.name;
+        match parameter.passable() {
+            Some(passable) => quote! { #name: #passable },
+            None => {
+                let ty = &parameter.ty;
+                quote! { #name: #ty }
+            }
+        }
+    });
+
+    let passed = parameters.iter()
+        .filter(|parameter| parameter.passable().is_some())
+        .collect::<Vec<_>>();
+    let passed_names = passed.iter().map(|parameter| &parameter.name).collect::<Vec<_>>();
+    let passed_types = passed.iter().map(|parameter| parameter.passable()).collect::<Vec<_>>();
+    let passed_strings = passed.iter().map(|parameter| parameter.name.to_string()).collect::<Vec<_>>();
+    let guards = passed.iter().map(|parameter| format_ident!("{}_guard", parameter.name)).collect::<Vec<_>>();
+    let pointers = passed.iter().map(|parameter| format_ident!("{}_ptr", parameter.name)).collect::<Vec<_>>();
+
+    let arguments = parameters.iter().map(|parameter| {
+        let name = &parameter.name;
+        if parameter.passable().is_some() {
+            let pointer = format_ident!("{}_ptr", name);
+            quote! { #pointer as _ }
+        }
+        else {
+            quote! { #name }
+        }
+    });
+
+    quote! {
+        #(#docs)*
+        ///
+        /// Passes the `#[pass]` parameters for the call and returns them reconstituted after the result
+        #vis fn #name(#(#inputs),*) -> ::std::result::Result<(#output, #(#passed_types),*), ::passable_guard::CallError> {
+            #(
+                let (#guards, #pointers) = ::passable_guard::PassableContainer::new(#passed_names).pass();
+            )*
+            let result = unsafe { super::#name(#(#arguments),*) };
+            // Reconstitute every parameter before checking the results, so the ones that succeeded are dropped on failure
+            #(
+                let #passed_names = unsafe { #guards.reconstitute(#pointers) };
+            )*
+            ::std::result::Result::Ok((
+                result,
+                #(
+                    #passed_names
+                        .map(::passable_guard::PassableContainer::into_inner)
+                        .map_err(|error| ::passable_guard::CallError::new(#passed_strings, error))?
+                ),*
+            ))
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! AddressSanitizer manual poisoning
+//!
+//! A buffer is only poisoned while nothing may use it: after the FFI has handed it back through
+//! [mark_returned](crate::PassableGuard::mark_returned), until the `Returned` token is reconstituted.
+//!
+//! Poisoning a buffer for as long as it is passed is left out on purpose. The FFI may use it in that time,
+//! and every instrumented access would be reported, from test doubles written in Rust
+//! to the checksum and shadow checks of this crate reading the buffer while it is lent.
+//! Guards dropped while still armed leak their buffer so the FFI can keep using it,
+//! and a failed reconstitution may have handed the allocation to the error, like `FromUtf8Error` does,
+//! so neither is poisoned either.
+//!
+//! The interface of the sanitizer runtime is only called in builds with `-Zsanitizer=address`,
+//! which the build script detects. In every other build, including under Miri, poisoning is a no-op.
+
+#[cfg(asan_runtime)]
+use std::os::raw::c_void;
+
+use crate::Passable;
+
+#[cfg(asan_runtime)]
+extern "C" {
+    fn __asan_poison_memory_region(addr: *const c_void, size: usize);
+    fn __asan_unpoison_memory_region(addr: *const c_void, size: usize);
+}
+
+/// Mark the passed buffer as unaddressable, so ASan reports every later access to it, if its [extent](Passable::passed_extent) is known
+pub(crate) fn poison<PAS: Passable>(ptr: *mut PAS::Pointee, metadata: &PAS::Metadata) {
+    #[cfg(asan_runtime)]
+    if let Some(size) = PAS::passed_extent(metadata) {
+        unsafe { __asan_poison_memory_region(ptr as *const c_void, size) };
+    }
+    #[cfg(not(asan_runtime))]
+    let _ = (ptr, metadata);
+}
+
+/// Make a buffer marked by [poison] addressable again, before it is reconstituted
+pub(crate) fn unpoison<PAS: Passable>(ptr: *mut PAS::Pointee, metadata: &PAS::Metadata) {
+    #[cfg(asan_runtime)]
+    if let Some(size) = PAS::passed_extent(metadata) {
+        unsafe { __asan_unpoison_memory_region(ptr as *const c_void, size) };
+    }
+    #[cfg(not(asan_runtime))]
+    let _ = (ptr, metadata);
+}
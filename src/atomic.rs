@@ -0,0 +1,55 @@
+//! A 64 bit counter for every target
+//!
+//! Targets without 64 bit atomics, like 32 bit MIPS and PowerPC,
+//! get a lock based counter with the same interface as [AtomicU64](std::sync::atomic::AtomicU64)
+
+#[cfg(target_has_atomic = "64")]
+pub(crate) use std::sync::atomic::AtomicU64;
+
+#[cfg(not(target_has_atomic = "64"))]
+pub(crate) use fallback::AtomicU64;
+
+#[cfg(not(target_has_atomic = "64"))]
+mod fallback {
+    use std::sync::atomic::Ordering;
+    use std::sync::{Mutex, PoisonError};
+
+    #[derive(Debug, Default)]
+    pub(crate) struct AtomicU64 {
+        value: Mutex<u64>
+    }
+
+    impl AtomicU64 {
+        pub(crate) const fn new(value: u64) -> Self {
+            Self {
+                value: Mutex::new(value)
+            }
+        }
+
+        fn update<F: FnOnce(u64) -> u64>(&self, f: F) -> u64 {
+            let mut value = self.value.lock().unwrap_or_else(PoisonError::into_inner);
+            let previous = *value;
+            *value = f(previous);
+            previous
+        }
+
+        #[cfg(feature = "diagnostics")]
+        pub(crate) fn load(&self, _ordering: Ordering) -> u64 {
+            *self.value.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        #[cfg(feature = "diagnostics")]
+        pub(crate) fn store(&self, value: u64, _ordering: Ordering) {
+            self.update(|_| value);
+        }
+
+        pub(crate) fn fetch_add(&self, value: u64, _ordering: Ordering) -> u64 {
+            self.update(|previous| previous.wrapping_add(value))
+        }
+
+        #[cfg(feature = "diagnostics")]
+        pub(crate) fn fetch_sub(&self, value: u64, _ordering: Ordering) -> u64 {
+            self.update(|previous| previous.wrapping_sub(value))
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use tokio::task::JoinHandle;
+
+use crate::scoped::{reconstitute_scoped, WithContainer};
+use crate::{Passable, PassableContainer, ReportPolicy};
+
+/// Move the [PassableContainer] onto the blocking pool of tokio, call `f` with the raw pointer there and reconstitute the container afterwards
+///
+/// The [JoinHandle] resolves to the result of `f` together with the recovered container, like [PassableContainer::pass_with]
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{guarded_blocking_call, PassableContainer};
+///
+/// extern "C" {
+///     fn strlen(string: *const std::os::raw::c_char) -> usize;
+/// }
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let name = PassableContainer::new(CString::new("name").unwrap());
+///
+/// let (len, name) = unsafe { guarded_blocking_call(name, |ptr| strlen(ptr as *const _)) }.await.unwrap().unwrap();
+/// assert_eq!(len, 4);
+/// assert_eq!(name.to_str().unwrap(), "name");
+/// # });
+/// ```
+///
+/// ### Errors
+/// The [JoinHandle] will resolve to a `JoinError` if `f` panicked or the runtime shut down before the call started,
+/// and to a reconstitution Error if the memory was modified by the FFI
+///
+/// ### Panic
+/// Panics if called outside of a tokio runtime, like `tokio::task::spawn_blocking`.
+///
+/// The call never panics because of its guard: If the runtime shuts down before the call started,
+/// the container is dropped without ever being passed.
+/// A call that has started always runs to completion and is reconstituted on its blocking thread,
+/// even if the runtime shuts down or the [JoinHandle] is dropped in the meantime, the result is then discarded.
+/// If `f` panics, the memory is leaked and reported according to [ReportPolicy]
+///
+/// ### Safety
+/// The same restrictions as for [PassableContainer::pass_with] apply
+pub unsafe fn guarded_blocking_call<PAS, R, F>(container: PassableContainer<PAS>, f: F) -> JoinHandle<Result<WithContainer<R, PAS>, PAS::ReconstituteError>>
+    where PAS: Passable + Send + 'static,
+          PAS::ReconstituteError: Send,
+          R: Send + 'static,
+          F: FnOnce(*mut PAS::Pointee) -> R + Send + 'static
+{
+    tokio::task::spawn_blocking(move || {
+        let (guard, ptr) = container.pass_with_policy::<ReportPolicy>();
+
+        let result = f(ptr);
+
+        reconstitute_scoped(guard, ptr).map(|container| (result, container))
+    })
+}
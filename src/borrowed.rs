@@ -0,0 +1,100 @@
+use std::ops::Deref;
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A guard for a [Passable] that has been lent to a FFI by [PassableContainer::pass_mut]
+///
+/// The Container stays borrowed until the guard is reconstituted, which puts the [Passable] back into it.
+/// The guard dereferences to the underlying [PassableGuard], so it can be checked and repassed.
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::PassableContainer;
+///
+/// struct Session {
+///     name: PassableContainer<CString>
+/// }
+///
+/// let mut session = Session {
+///     name: PassableContainer::new(CString::new("name").unwrap())
+/// };
+///
+/// for _ in 0..3 {
+///     let (guard, ptr) = session.name.pass_mut();
+///     // FFI call with ptr
+///     unsafe { guard.reconstitute(ptr) }.unwrap();
+/// }
+///
+/// assert_eq!(session.name.as_inner().to_str().unwrap(), "name");
+/// ```
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted, it will panic just like a [PassableGuard].
+/// The Container is left with the default value of the [Passable] in that case
+#[derive(Debug)]
+pub struct BorrowedGuard<'a, PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    container: &'a mut PassableContainer<PAS>
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// so a container can be lent to a FFI from a scoped thread
+unsafe impl<'a, PAS: Passable + Send> Send for BorrowedGuard<'a, PAS> {}
+
+impl<'a, PAS: Passable> BorrowedGuard<'a, PAS> {
+    /// Reconstitute a raw pointer and put the [Passable] back into the borrowed Container
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute].
+    /// The Container is left with the default value of the [Passable] in that case
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its policy
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<&'a mut PassableContainer<PAS>, ReconstituteError<PAS>> {
+        let BorrowedGuard {guard, container} = self;
+
+        container.value = guard.reconstitute_into(ptr)?;
+        Ok(container)
+    }
+}
+
+impl<'a, PAS: Passable> Deref for BorrowedGuard<'a, PAS> {
+    type Target = PassableGuard<PAS>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.guard
+    }
+}
+
+impl<PAS: Passable + Default> PassableContainer<PAS> {
+    /// Lend the [Passable] inside this Container to a FFI without consuming the Container
+    ///
+    /// The [Passable] is replaced by its default value until the returned guard is reconstituted.
+    /// Since the guard borrows the Container, it can be used on a scoped thread with a Container from the stack of the caller
+    ///
+    /// ``` rust
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let mut buffer = [0u8; 64];
+    /// let mut container = PassableContainer::new(&mut buffer[..]);
+    ///
+    /// std::thread::scope(|threads| {
+    ///     threads.spawn(|| {
+    ///         let (guard, ptr) = container.pass_mut();
+    ///         // FFI call writing to ptr on the worker thread
+    ///         unsafe { ptr.write(1) };
+    ///         unsafe { guard.reconstitute(ptr) }.unwrap();
+    ///     });
+    /// });
+    ///
+    /// assert_eq!(container.as_inner()[0], 1);
+    /// ```
+    #[track_caller]
+    pub fn pass_mut(&mut self) -> (BorrowedGuard<'_, PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = PassableContainer::with_options(std::mem::take(&mut self.value), self.options).pass();
+        (BorrowedGuard {guard, container: self}, ptr)
+    }
+}
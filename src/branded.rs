@@ -0,0 +1,121 @@
+//! Lifetime-branded guards
+//!
+//! A branded guard is tied to the pointer it produced by an invariant lifetime `'id`.
+//! Every call to [PassableContainer::pass_branded] creates a new, unique brand,
+//! so handing a pointer from one pass to the guard of another pass is a compile error
+//! instead of a runtime [ReconstituteError::PointerMismatch].
+//!
+//! ``` compile_fail
+//! use std::ffi::CString;
+//! use passable_guard::PassableContainer;
+//!
+//! let first = PassableContainer::new(CString::new("first").unwrap());
+//! let second = PassableContainer::new(CString::new("second").unwrap());
+//!
+//! first.pass_branded(|first_guard, first_ptr| {
+//!     second.pass_branded(|second_guard, second_ptr| {
+//!         unsafe {
+//!             // The brands of the two passes do not match, so this does not compile
+//!             first_guard.reconstitute(second_ptr).unwrap();
+//!             second_guard.reconstitute(first_ptr).unwrap();
+//!         }
+//!     })
+//! });
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// Invariant lifetime marker used to brand a guard and its pointer
+type Brand<'id> = PhantomData<fn(&'id ()) -> &'id ()>;
+
+/// A raw pointer created by [PassableContainer::pass_branded]
+///
+/// It can only be given back to the [BrandedGuard] of the same pass
+#[derive(Debug)]
+pub struct BrandedPtr<'id, PTR> {
+    ptr: *mut PTR,
+    _brand: Brand<'id>
+}
+
+impl<'id, PTR> BrandedPtr<'id, PTR> {
+    /// Get the raw pointer to pass it over a FFI boundary
+    pub fn as_ptr(&self) -> *mut PTR {
+        self.ptr
+    }
+}
+
+impl<'id, PTR> Clone for BrandedPtr<'id, PTR> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'id, PTR> Copy for BrandedPtr<'id, PTR> {}
+
+/// A [PassableGuard] that can only be reconstituted with the [BrandedPtr] of its own pass
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted, it will panic just like a [PassableGuard]
+#[derive(Debug)]
+pub struct BrandedGuard<'id, PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    _brand: Brand<'id>
+}
+
+impl<'id, PAS: Passable> BrandedGuard<'id, PAS> {
+    /// Reconstitute the [BrandedPtr] of this pass back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: BrandedPtr<'id, PAS::Pointee>) -> Result<PassableContainer<PAS>, PAS::ReconstituteError> {
+        match self.guard.reconstitute(ptr.ptr) {
+            Ok(container) => Ok(container),
+            Err(ReconstituteError::ReconstituteError {error}) => Err(error),
+            Err(ReconstituteError::PointerMismatch {..}) => unreachable!("Branded pointers always match their guard"),
+            Err(ReconstituteError::ContentModified {..}) => unreachable!("Only a ChecksumGuard verifies the content"),
+            Err(ReconstituteError::InvalidParts {..}) => unreachable!("Only raw parts are validated"),
+            #[cfg(feature = "diagnostics")]
+            Err(ReconstituteError::PointerSwapped {..}) => unreachable!("Branded pointers always match their guard")
+        }
+    }
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a branded pointer and guard and hand them to `f`
+    ///
+    /// Each call creates a new brand, so the guard cannot be reconstituted with a pointer from a different pass.
+    /// Both the guard and the pointer are confined to `f`, so the guard has to be reconstituted before `f` returns.
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let container = PassableContainer::new(CString::new("name").unwrap());
+    ///
+    /// let name = container.pass_branded(|guard, ptr| {
+    ///     // Give ptr.as_ptr() to the FFI here
+    ///     unsafe { guard.reconstitute(ptr) }.unwrap().into_inner()
+    /// });
+    ///
+    /// assert_eq!(name.to_str().unwrap(), "name");
+    /// ```
+    pub fn pass_branded<R, F>(self, f: F) -> R
+        where F: for<'id> FnOnce(BrandedGuard<'id, PAS>, BrandedPtr<'id, PAS::Pointee>) -> R
+    {
+        let (guard, ptr) = self.pass();
+        let guard = BrandedGuard {
+            guard,
+            _brand: PhantomData
+        };
+        let ptr = BrandedPtr {
+            ptr,
+            _brand: PhantomData
+        };
+        f(guard, ptr)
+    }
+}
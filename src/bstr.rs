@@ -0,0 +1,147 @@
+use std::convert::{Infallible, TryFrom};
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+
+use crate::{Passable, PassableLen};
+
+#[link(name = "oleaut32")]
+extern "system" {
+    fn SysAllocStringLen(string: *const u16, len: u32) -> *mut u16;
+    fn SysFreeString(string: *mut u16);
+    fn SysStringLen(string: *mut u16) -> u32;
+}
+
+/// An owned OLE Automation string, allocated with `SysAllocStringLen` and freed with `SysFreeString`
+///
+/// The length is read from the length prefix of the BSTR, so it may contain NUL characters.
+/// A null BSTR is treated as the empty string, as OLE Automation does
+///
+/// ``` rust
+/// use passable_guard::{Bstr, PassableContainer};
+///
+/// let (guard, ptr) = PassableContainer::new(Bstr::new("name")).pass();
+///
+/// // Hand `ptr` to a COM method taking a BSTR
+///
+/// let name = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(name.to_string_lossy(), "name");
+/// ```
+pub struct Bstr {
+    ptr: *mut u16
+}
+
+impl Bstr {
+    /// Allocate a BSTR containing `string`
+    ///
+    /// ### Panic
+    /// Panics if the BSTR could not be allocated
+    pub fn new(string: &str) -> Self {
+        let wide = string.encode_utf16().collect::<Vec<_>>();
+        Self::from_wide(&wide)
+    }
+
+    /// Allocate a BSTR containing the UTF-16 code units `wide`
+    ///
+    /// ### Panic
+    /// Panics if the BSTR could not be allocated
+    pub fn from_wide(wide: &[u16]) -> Self {
+        let len = u32::try_from(wide.len()).expect("String is too long for a BSTR");
+        let ptr = unsafe { SysAllocStringLen(wide.as_ptr(), len) };
+        assert!(!ptr.is_null(), "Could not allocate a BSTR");
+        Self {
+            ptr
+        }
+    }
+
+    /// Take ownership of a BSTR, for example one returned by a COM method
+    ///
+    /// ### Safety
+    /// `ptr` has to be null or a BSTR allocated by `SysAllocString` or one of its variants that is not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut u16) -> Self {
+        Self {
+            ptr
+        }
+    }
+
+    /// Give up ownership of the BSTR, it has to be freed with `SysFreeString`
+    pub fn into_raw(self) -> *mut u16 {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Get the pointer to the first character of the BSTR
+    pub fn as_ptr(&self) -> *mut u16 {
+        self.ptr
+    }
+
+    /// Get the number of UTF-16 code units in the BSTR, as stored in its length prefix
+    pub fn len(&self) -> usize {
+        unsafe { SysStringLen(self.ptr) as usize }
+    }
+
+    /// Check whether the BSTR is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the UTF-16 code units of the BSTR, without the terminating NUL
+    pub fn as_wide(&self) -> &[u16] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len()) }
+    }
+
+    /// Convert the BSTR to a [String], replacing invalid UTF-16 with the replacement character
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(self.as_wide())
+    }
+}
+
+impl Drop for Bstr {
+    fn drop(&mut self) {
+        unsafe { SysFreeString(self.ptr) }
+    }
+}
+
+impl Debug for Bstr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl Clone for Bstr {
+    fn clone(&self) -> Self {
+        Self::from_wide(self.as_wide())
+    }
+}
+
+impl Passable for Bstr {
+    type Pointee = u16;
+    type ReconstituteError = Infallible;
+    /// The number of UTF-16 code units in the passed BSTR
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut u16, Self::Metadata) {
+        let len = self.len();
+        (self.into_raw(), len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut u16, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        // The length is read from the prefix when it is needed, the callee might have shortened the string in place
+        Ok(Bstr::from_raw(ptr))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata * std::mem::size_of::<u16>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for Bstr {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+/// A buffer in the `{len: i64, data: *mut u8}` layout of the `ByteBuffer` of ffi-support
+///
+/// Created by [PassableContainer::pass_byte_buffer], so the C side of an ffi-support codebase keeps its signatures
+/// while the buffer it hands back to the destructor is checked by a guard
+///
+/// ``` rust
+/// use passable_guard::{ByteBuffer, PassableContainer};
+///
+/// let (guard, buffer) = PassableContainer::new(vec![1u8, 2, 3]).pass_byte_buffer();
+/// assert_eq!(buffer.len, 3);
+///
+/// // Hand `buffer` to C, which hands it back to the destructor when it is done
+///
+/// let bytes = unsafe { guard.reconstitute_byte_buffer(buffer) }.unwrap().into_inner();
+/// assert_eq!(bytes, vec![1, 2, 3]);
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteBuffer {
+    pub len: i64,
+    pub data: *mut u8
+}
+
+impl ByteBuffer {
+    /// Check whether this is the empty buffer returned in place of a buffer on failure
+    pub fn is_null(&self) -> bool {
+        self.data.is_null()
+    }
+}
+
+impl Default for ByteBuffer {
+    fn default() -> Self {
+        Self {
+            len: 0,
+            data: std::ptr::null_mut()
+        }
+    }
+}
+
+impl<PAS: PassableLen<Pointee = u8>> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a [ByteBuffer] to pass it over a FFI boundary
+    #[track_caller]
+    pub fn pass_byte_buffer(self) -> (PassableGuard<PAS>, ByteBuffer) {
+        let (guard, data, len) = self.pass_with_len();
+        // The length of an allocation never exceeds isize::MAX, so it always fits
+        let buffer = ByteBuffer {
+            len: len as i64,
+            data
+        };
+        (guard, buffer)
+    }
+}
+
+impl<PAS: Passable<Pointee = u8>, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Reconstitute a [ByteBuffer] back into a [PassableContainer]
+    ///
+    /// The length of the buffer is not used, the guard already knows it
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_byte_buffer(self, buffer: ByteBuffer) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.reconstitute(buffer.data)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{ContainerOptions, DropPolicy, Passable, PassableContainer};
+
+/// A builder for a [PassableContainer] with advanced configuration
+///
+/// Created by [PassableContainer::builder]
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{DropPolicy, PassableContainer};
+///
+/// let container = PassableContainer::builder(CString::new("name").unwrap())
+///     .label("names")
+///     .policy(DropPolicy::Leak)
+///     .build();
+///
+/// let (guard, _ptr) = container.pass();
+/// assert_eq!(guard.label(), Some("names"));
+///
+/// // Does not panic, the configured policy leaks the memory instead
+/// drop(guard);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "the builder does nothing until build is called"]
+pub struct ContainerBuilder<PAS: Passable> {
+    value: PAS,
+    options: ContainerOptions
+}
+
+impl<PAS: Passable> ContainerBuilder<PAS> {
+    /// Set a label that identifies the Container and its guards in diagnostics
+    pub fn label(mut self, label: &'static str) -> Self {
+        self.options.label = Some(label);
+        self
+    }
+
+    /// Set the [DropPolicy] that guards of the Container apply if they are dropped while still armed
+    ///
+    /// ### Notes
+    /// The policy is only applied by guards using the default [ConfiguredPolicy](crate::ConfiguredPolicy)
+    pub fn policy(mut self, policy: DropPolicy) -> Self {
+        self.options.policy = Some(policy);
+        self
+    }
+
+    /// Create the configured [PassableContainer]
+    pub fn build(self) -> PassableContainer<PAS> {
+        PassableContainer::with_options(self.value, self.options)
+    }
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Start building a [PassableContainer] with advanced configuration
+    pub fn builder(passable: PAS) -> ContainerBuilder<PAS> {
+        ContainerBuilder {
+            value: passable,
+            options: ContainerOptions::new()
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+/// An Error returned by [guarded_call](crate::guarded_call) or a generated FFI wrapper if one of its passed parameters could not be reconstituted
+///
+/// The other parameters have been reconstituted and dropped, the result of the FFI call has been discarded.
+/// It is [Send] and [Sync], so it can be propagated as a `Box<dyn Error + Send + Sync>`
+///
+/// ``` rust
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<passable_guard::CallError>();
+/// ```
+pub struct CallError {
+    parameter: &'static str,
+    error: Box<dyn Debug + Send + Sync>
+}
+
+impl CallError {
+    /// Create the Error for `parameter` from the Error its reconstitution failed with
+    pub fn new<E: Debug + Send + Sync + 'static>(parameter: &'static str, error: E) -> Self {
+        Self {
+            parameter,
+            error: Box::new(error)
+        }
+    }
+
+    /// Get the name of the parameter that could not be reconstituted
+    pub fn parameter(&self) -> &'static str {
+        self.parameter
+    }
+
+    /// Get the Error the reconstitution of the parameter failed with
+    pub fn error(&self) -> &dyn Debug {
+        &*self.error
+    }
+}
+
+impl Debug for CallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CallError")
+            .field("parameter", &self.parameter)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl Display for CallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "could not reconstitute the parameter `{}`: {:?}", self.parameter, self.error)
+    }
+}
+
+impl Error for CallError {}
+
+/// Pass every argument, call an FFI function with the raw pointers and reconstitute the arguments again
+///
+/// Every argument has to be a [PassableContainer](crate::PassableContainer).
+/// Evaluates to the result of the call followed by the reconstituted [Passable](crate::Passable)s,
+/// or a [CallError] naming the first argument that could not be reconstituted, whose error has to be [Send] and [Sync].
+/// It has to be used in an `unsafe` block, the same restrictions as for
+/// [PassableContainer::pass_with](crate::PassableContainer::pass_with) apply to the function
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use std::os::raw::c_char;
+/// use passable_guard::{guarded_call, PassableContainer};
+///
+/// extern "C" {
+///     fn strlen(string: *const c_char) -> usize;
+/// }
+///
+/// let name = PassableContainer::new(CString::new("name").unwrap());
+///
+/// let (len, name) = unsafe { guarded_call!(strlen(name)) }.unwrap();
+/// assert_eq!(len, 4);
+/// assert_eq!(name.to_str().unwrap(), "name");
+/// ```
+#[macro_export]
+macro_rules! guarded_call {
+    ($($function:ident)::+ ($($argument:expr),* $(,)?)) => {
+        $crate::__guarded_call!(@pass [$($function)::+] []; $($argument,)*)
+    };
+}
+
+/// Pass the arguments of `guarded_call!` one by one, every step of the recursion names its own guard and pointer
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __guarded_call {
+    (@pass [$($function:tt)*] [$(($guard:ident, $ptr:ident, $name:expr))*]; $argument:expr, $($rest:tt)*) => {{
+        let (guard, ptr) = $crate::PassableContainer::pass($argument);
+        $crate::__guarded_call!(@pass [$($function)*] [$(($guard, $ptr, $name))* (guard, ptr, stringify!($argument))]; $($rest)*)
+    }};
+    (@pass [$($function:tt)*] [$(($guard:ident, $ptr:ident, $name:expr))*];) => {
+        'call: {
+            let result = $($function)*($($ptr as _),*);
+            // Reconstitute every argument before checking the results, so the ones that succeeded are dropped on failure
+            $(
+                let $guard = $guard.reconstitute($ptr);
+            )*
+            $(
+                let $guard = match $guard {
+                    ::std::result::Result::Ok(container) => container.into_inner(),
+                    ::std::result::Result::Err(error) => break 'call ::std::result::Result::Err($crate::CallError::new($name, error))
+                };
+            )*
+            ::std::result::Result::Ok((result, $($guard),*))
+        }
+    };
+}
@@ -0,0 +1,285 @@
+//! Futures completed by C callbacks
+//!
+//! Many C APIs start an operation with a buffer and report back through a callback with a userdata pointer.
+//! A [CallbackFuture] is completed through its [Completer] once the callback fires,
+//! `#[wrap]` uses them to turn such functions into wrappers returning a future.
+//! A [GuardFuture] does the same for a single passed [Passable], which C completes through a function pointer and a context
+
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::os::raw::c_void;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use crate::tag;
+use crate::{CompletionGuard, Passable, PassableContainer, ReconstituteError, TypeTagError};
+
+/// The state shared by a [CallbackFuture] and its [Completer]
+struct Shared<T> {
+    value: Option<T>,
+    waker: Option<Waker>
+}
+
+/// A future that completes once its [Completer] is called
+///
+/// ``` rust
+/// use std::future::Future;
+/// use std::pin::pin;
+/// use std::task::{Context, Poll};
+/// use futures::task::noop_waker_ref;
+/// use passable_guard::CallbackFuture;
+///
+/// let (future, completer) = CallbackFuture::new();
+/// let mut future = pin!(future);
+/// let mut cx = Context::from_waker(noop_waker_ref());
+/// assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+///
+/// // Move `completer` into the userdata of a C callback, which calls it once the operation is done
+/// completer.complete(42);
+///
+/// assert_eq!(future.poll(&mut cx), Poll::Ready(42));
+/// ```
+///
+/// ### Notes
+/// If the [Completer] is dropped without being called, the future never completes
+pub struct CallbackFuture<T> {
+    shared: Arc<Mutex<Shared<T>>>
+}
+
+/// The completing side of a [CallbackFuture]
+pub struct Completer<T> {
+    shared: Arc<Mutex<Shared<T>>>
+}
+
+impl<T> CallbackFuture<T> {
+    /// Create a pending future and the [Completer] that completes it
+    pub fn new() -> (Self, Completer<T>) {
+        let shared = Arc::new(Mutex::new(Shared {
+            value: None,
+            waker: None
+        }));
+        let completer = Completer {
+            shared: shared.clone()
+        };
+        (Self {shared}, completer)
+    }
+}
+
+impl<T> Completer<T> {
+    /// Complete the [CallbackFuture] with `value`, waking the task polling it
+    ///
+    /// If the future has already been dropped, `value` is dropped
+    pub fn complete(self, value: T) {
+        let waker = {
+            let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+            shared.value = Some(value);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Future for CallbackFuture<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        match shared.value.take() {
+            Some(value) => Poll::Ready(value),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T> Debug for CallbackFuture<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let shared = self.shared.lock().unwrap_or_else(PoisonError::into_inner);
+        f.debug_struct("CallbackFuture")
+            .field("completed", &shared.value.is_some())
+            .finish()
+    }
+}
+
+impl<T> Debug for Completer<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Completer").finish()
+    }
+}
+
+/// The result a [GuardFuture] resolves to
+type Reconstituted<PAS> = Result<PassableContainer<PAS>, ReconstituteError<PAS>>;
+
+/// A future that resolves to the reconstituted [Passable] once C signals that it is done with the pointer
+///
+/// Created by [PassableContainer::pass_future] together with a [GuardCompletion],
+/// which is handed to C as a context pointer next to one of its completion functions
+///
+/// ``` rust
+/// use std::os::raw::c_void;
+/// use passable_guard::{GuardCompletion, PassableContainer};
+///
+/// extern "C" fn start_read(buffer: *mut u8, len: usize, done: unsafe extern "C" fn(*mut c_void, *mut c_void), context: *mut c_void) {
+///     // A real C library would call back later, from any thread
+///     unsafe { std::ptr::write_bytes(buffer, 7, len) };
+///     unsafe { done(context, buffer as *mut c_void) };
+/// }
+///
+/// async fn read() -> Vec<u8> {
+///     let (future, completion, ptr) = PassableContainer::new(vec![0u8; 16]).pass_future();
+///     start_read(ptr, 16, GuardCompletion::<Vec<u8>>::COMPLETE, completion.into_context());
+///     future.await.unwrap().into_inner()
+/// }
+/// # use std::future::Future;
+/// # let mut future = std::pin::pin!(read());
+/// # let std::task::Poll::Ready(buffer) = future.as_mut().poll(&mut std::task::Context::from_waker(futures::task::noop_waker_ref())) else { unreachable!() };
+/// # assert_eq!(buffer, vec![7; 16]);
+/// ```
+///
+/// ### Notes
+/// If the future is dropped before C completes it, the [Passable] is reconstituted and dropped once C does.
+/// If C never completes it, the [Passable] is leaked and the future never resolves
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct GuardFuture<PAS: Passable> {
+    future: CallbackFuture<Reconstituted<PAS>>
+}
+
+/// The companion of a [GuardFuture] that is completed from C
+///
+/// ### Panic
+/// If this is dropped without being handed to C or completed, its guard is dropped while still armed and will apply its policy,
+/// the [GuardFuture] never resolves in that case
+pub struct GuardCompletion<PAS: Passable> {
+    guard: CompletionGuard<PAS>,
+    completer: Completer<Reconstituted<PAS>>
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer to pass it to an asynchronous FFI call,
+    /// getting a [GuardFuture] that resolves once the [GuardCompletion] is completed from C
+    #[track_caller]
+    pub fn pass_future(self) -> (GuardFuture<PAS>, GuardCompletion<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass_completion();
+        let (future, completer) = CallbackFuture::new();
+        let completion = GuardCompletion {
+            guard,
+            completer
+        };
+        (GuardFuture {future}, completion, ptr)
+    }
+}
+
+impl<PAS: Passable> GuardCompletion<PAS> {
+    /// The completion function for C APIs that hand back the context and the pointer, `void (*)(void *context, void *ptr)`
+    pub const COMPLETE: unsafe extern "C" fn(*mut c_void, *mut c_void) = complete_with_ptr::<PAS>;
+    /// The completion function for C APIs that only hand back the context, `void (*)(void *context)`
+    pub const COMPLETE_CONTEXT: unsafe extern "C" fn(*mut c_void) = complete_context::<PAS>;
+
+    /// Convert the completion into the context pointer for [GuardCompletion::COMPLETE] or [GuardCompletion::COMPLETE_CONTEXT]
+    pub fn into_context(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)) as *mut c_void
+    }
+
+    /// Convert a context pointer created with [GuardCompletion::into_context] back into the completion
+    ///
+    /// ### Safety
+    /// `context` has to come from [GuardCompletion::into_context] of a completion for the same [Passable]
+    /// and must not have been converted back before
+    pub unsafe fn from_context(context: *mut c_void) -> Self {
+        *Box::from_raw(context as *mut Self)
+    }
+
+    /// Convert the completion into a context pointer with a hidden type tag, see [TypeTagError]
+    ///
+    /// The pointer has to be converted back with [GuardCompletion::from_tagged_context] exactly once,
+    /// so it is meant for completion functions written by hand instead of [GuardCompletion::COMPLETE]
+    pub fn into_tagged_context(self) -> *mut c_void {
+        tag::into_tagged(self)
+    }
+
+    /// Convert a context pointer created with [GuardCompletion::into_tagged_context] back into the completion,
+    /// checking that it was created for the same [Passable]
+    ///
+    /// ### Errors
+    /// Will return an Error and leave the context untouched if it was created for a different type
+    ///
+    /// ### Safety
+    /// `context` has to come from [GuardCompletion::into_tagged_context] or [CompletionGuard::into_tagged_context]
+    /// and must not have been converted back before
+    pub unsafe fn from_tagged_context(context: *mut c_void) -> Result<Self, TypeTagError> {
+        tag::from_tagged(context)
+    }
+
+    /// Reconstitute the [Passable] from the pointer handed back by C and resolve the [GuardFuture] with it
+    ///
+    /// ### Notes
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its policy,
+    /// the [GuardFuture] resolves to the Error.
+    /// A panic of the policy is caught, so this never unwinds into the C code calling the completion function
+    ///
+    /// ``` rust
+    /// use std::future::Future;
+    /// use passable_guard::{GuardCompletion, PassableContainer};
+    ///
+    /// let (mut future, completion, _ptr) = PassableContainer::new(vec![0u8; 16]).pass_future();
+    ///
+    /// // C hands back a different pointer
+    /// unsafe { GuardCompletion::<Vec<u8>>::COMPLETE(completion.into_context(), std::ptr::null_mut()) };
+    ///
+    /// let poll = std::pin::Pin::new(&mut future).poll(&mut std::task::Context::from_waker(futures::task::noop_waker_ref()));
+    /// assert!(matches!(poll, std::task::Poll::Ready(Err(_))));
+    /// ```
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute](crate::PassableGuard::reconstitute) apply
+    pub unsafe fn complete(self, ptr: *mut PAS::Pointee) {
+        let GuardCompletion {guard, completer} = self;
+        completer.complete(guard.into_guard().reconstitute_in_callback(ptr));
+    }
+
+    /// Reconstitute the [Passable] from the pointer the guard kept and resolve the [GuardFuture] with it
+    ///
+    /// ### Safety
+    /// The FFI must not use the pointer anymore, the same restrictions as for [PassableGuard::reconstitute](crate::PassableGuard::reconstitute) apply
+    pub unsafe fn complete_kept(self) {
+        let GuardCompletion {guard, completer} = self;
+        completer.complete(guard.complete());
+    }
+}
+
+unsafe extern "C" fn complete_with_ptr<PAS: Passable>(context: *mut c_void, ptr: *mut c_void) {
+    GuardCompletion::<PAS>::from_context(context).complete(ptr as *mut PAS::Pointee);
+}
+
+unsafe extern "C" fn complete_context<PAS: Passable>(context: *mut c_void) {
+    GuardCompletion::<PAS>::from_context(context).complete_kept();
+}
+
+impl<PAS: Passable> Future for GuardFuture<PAS> {
+    type Output = Reconstituted<PAS>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.future).poll(cx)
+    }
+}
+
+impl<PAS: Passable> Debug for GuardFuture<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GuardFuture")
+            .field("future", &self.future)
+            .finish()
+    }
+}
+
+impl<PAS: Passable> Debug for GuardCompletion<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GuardCompletion")
+            .field("guard", &self.guard)
+            .finish()
+    }
+}
@@ -0,0 +1,167 @@
+//! Guards that survive the cancellation of the future holding them
+//!
+//! Dropping a future is the routine way to cancel it, so a guard held across an `.await`
+//! is dropped whenever the surrounding task is cancelled, while the FFI may still be using the pointer.
+//! A [CancelGuard] then switches to its [CancelFallback] instead of panicking inside the executor
+//!
+//! ``` rust
+//! use passable_guard::{CancelFallback, PassableContainer};
+//!
+//! async fn read_into(buffer: Vec<u8>) -> Vec<u8> {
+//!     let (guard, ptr) = PassableContainer::new(buffer).pass_cancellable(CancelFallback::Defer);
+//!
+//!     // Await the FFI operation writing to `ptr` here, if this future is dropped the buffer is deferred
+//!
+//!     unsafe { guard.reconstitute(ptr) }.unwrap().into_inner()
+//! }
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::sync::{Mutex, PoisonError};
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError, ReportPolicy};
+
+/// What a [CancelGuard] does if it is dropped before it has been reconstituted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CancelFallback {
+    /// Leak the [Passable] and report it, like [ReportPolicy]
+    #[default]
+    Report,
+    /// Keep the [Passable] until [free_deferred] is called
+    Defer
+}
+
+/// A cancelled guard waiting for [free_deferred]
+trait Deferred: Send {
+    fn free(self: Box<Self>) -> bool;
+}
+
+struct Cancelled<PAS: Passable> {
+    guard: PassableGuard<PAS, ReportPolicy>
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// so the deferred guards can be freed from any thread
+unsafe impl<PAS: Passable + Send> Send for Cancelled<PAS> {}
+
+impl<PAS: Passable + Send> Deferred for Cancelled<PAS> {
+    fn free(self: Box<Self>) -> bool {
+        let ptr = self.guard.repass();
+        // free_deferred is only called once the FFI is done with every deferred pointer
+        unsafe { self.guard.reconstitute(ptr) }.is_ok()
+    }
+}
+
+static DEFERRED: Mutex<Vec<Box<dyn Deferred>>> = Mutex::new(Vec::new());
+
+/// A guard that can be held across `.await` points and falls back to a [CancelFallback] if its future is cancelled
+///
+/// ### Notes
+/// Unlike a [PassableGuard], this guard never panics when it is dropped while still armed,
+/// it applies its [CancelFallback] instead
+pub struct CancelGuard<PAS: Passable + Send + 'static> {
+    guard: Option<PassableGuard<PAS, ReportPolicy>>,
+    fallback: CancelFallback
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// so the future holding it can move between the threads of the executor
+unsafe impl<PAS: Passable + Send + 'static> Send for CancelGuard<PAS> {}
+
+impl<PAS: Passable + Send + 'static> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer to pass it over a FFI boundary from async code,
+    /// getting a [CancelGuard] that applies `fallback` if it is dropped before being reconstituted
+    #[track_caller]
+    pub fn pass_cancellable(self, fallback: CancelFallback) -> (CancelGuard<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass_with_policy();
+        let guard = CancelGuard {
+            guard: Some(guard),
+            fallback
+        };
+        (guard, ptr)
+    }
+}
+
+impl<PAS: Passable + Send + 'static> CancelGuard<PAS> {
+    /// Get the [CancelFallback] applied if this guard is dropped before being reconstituted
+    pub fn fallback(&self) -> CancelFallback {
+        self.fallback
+    }
+
+    /// Reconstitute the pointer back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the [Passable] is leaked and reported, regardless of the [CancelFallback]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(mut self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        let guard = self.guard.take().expect("CancelGuard is only emptied by reconstitute and drop");
+        guard.reconstitute(ptr)
+    }
+}
+
+impl<PAS: Passable + Send + 'static> Drop for CancelGuard<PAS> {
+    fn drop(&mut self) {
+        let guard = match self.guard.take() {
+            Some(guard) => guard,
+            None => return
+        };
+        // A running shutdown overrides the fallback of the guard
+        match crate::shutdown::fallback().unwrap_or(self.fallback) {
+            // The guard reports itself when it is dropped while still armed
+            CancelFallback::Report => drop(guard),
+            CancelFallback::Defer => DEFERRED
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(Box::new(Cancelled {guard}))
+        }
+    }
+}
+
+impl<PAS: Passable + Send + 'static> Debug for CancelGuard<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancelGuard")
+            .field("guard", &self.guard)
+            .field("fallback", &self.fallback)
+            .finish()
+    }
+}
+
+/// Reconstitute and drop every [Passable] deferred by a [CancelGuard] that was cancelled or dropped during a [shutdown](crate::begin_shutdown)
+///
+/// Returns the number of deferred [Passable]s that were freed,
+/// the ones that could not be reconstituted are leaked
+///
+/// ``` rust
+/// use passable_guard::{CancelFallback, PassableContainer};
+///
+/// let (guard, _ptr) = PassableContainer::new(vec![0u8; 64]).pass_cancellable(CancelFallback::Defer);
+///
+/// // The future holding the guard is cancelled while the FFI still uses the buffer
+/// drop(guard);
+/// assert_eq!(passable_guard::deferred_count(), 1);
+///
+/// // Once the FFI is done with every outstanding buffer
+/// assert_eq!(unsafe { passable_guard::free_deferred() }, 1);
+/// assert_eq!(passable_guard::deferred_count(), 0);
+/// ```
+///
+/// ### Safety
+/// The FFI must no longer use any of the pointers that were deferred, for example because the library has been shut down
+/// or has signalled that all outstanding operations are done
+pub unsafe fn free_deferred() -> usize {
+    let deferred = std::mem::take(&mut *DEFERRED.lock().unwrap_or_else(PoisonError::into_inner));
+    deferred.into_iter()
+        .map(Deferred::free)
+        .filter(|freed| *freed)
+        .count()
+}
+
+/// Get the number of deferred [Passable]s that have not been freed yet
+pub fn deferred_count() -> usize {
+    DEFERRED.lock().unwrap_or_else(PoisonError::into_inner).len()
+}
@@ -0,0 +1,159 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+
+use core_foundation_sys::base::{kCFAllocatorDefault, CFIndex, CFRelease, CFRetain, CFTypeRef};
+use core_foundation_sys::data::{CFDataCreate, CFDataGetBytePtr, CFDataGetLength, __CFData};
+use core_foundation_sys::string::{kCFStringEncodingUTF8, CFStringCreateWithBytes, CFStringGetLength, __CFString};
+
+use crate::Passable;
+
+/// A Core Foundation type that can be held by a [CfRetained]
+///
+/// ### Safety
+/// The type has to be the opaque pointee of a Core Foundation reference that can be retained and released
+pub unsafe trait CfType {}
+
+unsafe impl CfType for __CFString {}
+unsafe impl CfType for __CFData {}
+
+/// An owned reference to a Core Foundation object, holding one retain that is released when it is dropped
+///
+/// Passing it hands the pointer and the retain to the FFI, so the guard tracks the release obligation along with the pointer.
+/// [CFString](CfString) is toll-free bridged, so the pointer can also be handed to Objective-C as an `NSString *`
+///
+/// ``` rust
+/// use passable_guard::{CfString, PassableContainer};
+///
+/// let (guard, ptr) = PassableContainer::new(CfString::new("name")).pass();
+///
+/// // Hand `ptr` to an API taking a CFStringRef or an NSString
+///
+/// let name = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(name.len(), 4);
+/// ```
+pub struct CfRetained<T: CfType> {
+    ptr: *const T
+}
+
+/// An owned `CFStringRef`
+pub type CfString = CfRetained<__CFString>;
+/// An owned `CFDataRef`
+pub type CfData = CfRetained<__CFData>;
+
+impl<T: CfType> CfRetained<T> {
+    /// Take ownership of a reference obtained under the create rule, from a function with `Create` or `Copy` in its name
+    ///
+    /// ### Safety
+    /// `ptr` has to be a valid, non-null reference with a retain that is not owned by anything else
+    pub unsafe fn from_create_rule(ptr: *const T) -> Self {
+        Self {
+            ptr
+        }
+    }
+
+    /// Retain a reference obtained under the get rule, which is not owned by the caller
+    ///
+    /// ### Safety
+    /// `ptr` has to be a valid, non-null reference
+    pub unsafe fn from_get_rule(ptr: *const T) -> Self {
+        CFRetain(ptr as CFTypeRef);
+        Self {
+            ptr
+        }
+    }
+
+    /// Give up ownership of the retain, it has to be released with `CFRelease`
+    pub fn into_raw(self) -> *const T {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Get the reference without giving up ownership
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+}
+
+impl CfRetained<__CFString> {
+    /// Create a CFString containing `string`
+    ///
+    /// ### Panic
+    /// Panics if the string could not be created
+    pub fn new(string: &str) -> Self {
+        let ptr = unsafe {
+            CFStringCreateWithBytes(kCFAllocatorDefault, string.as_ptr(), string.len() as CFIndex, kCFStringEncodingUTF8, 0)
+        };
+        assert!(!ptr.is_null(), "Could not create a CFString");
+        Self {
+            ptr
+        }
+    }
+
+    /// Get the number of UTF-16 code units in the string
+    pub fn len(&self) -> usize {
+        unsafe { CFStringGetLength(self.ptr) as usize }
+    }
+
+    /// Check whether the string is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CfRetained<__CFData> {
+    /// Create a CFData containing a copy of `data`
+    ///
+    /// ### Panic
+    /// Panics if the data could not be created
+    pub fn from_slice(data: &[u8]) -> Self {
+        let ptr = unsafe { CFDataCreate(kCFAllocatorDefault, data.as_ptr(), data.len() as CFIndex) };
+        assert!(!ptr.is_null(), "Could not create a CFData");
+        Self {
+            ptr
+        }
+    }
+
+    /// Get the contents of the data
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe {
+            match CFDataGetLength(self.ptr) as usize {
+                0 => &[],
+                len => std::slice::from_raw_parts(CFDataGetBytePtr(self.ptr), len)
+            }
+        }
+    }
+}
+
+impl<T: CfType> Clone for CfRetained<T> {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_get_rule(self.ptr) }
+    }
+}
+
+impl<T: CfType> Drop for CfRetained<T> {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.ptr as CFTypeRef) }
+    }
+}
+
+impl<T: CfType> Debug for CfRetained<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("CfRetained")
+            .field(&self.ptr)
+            .finish()
+    }
+}
+
+impl<T: CfType> Passable for CfRetained<T> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        (self.into_raw() as *mut T, ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(CfRetained::from_create_rule(ptr))
+    }
+}
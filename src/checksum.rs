@@ -0,0 +1,96 @@
+//! Checksum verification
+//!
+//! A [ChecksumGuard] hashes the passed buffer and verifies the hash when it is reconstituted,
+//! catching FFIs that write to memory they promised to only read.
+//! Unlike a [ShadowGuard](crate::ShadowGuard) it only keeps the hash instead of a full copy of the buffer,
+//! so it is cheap enough to leave enabled, but it cannot tell which bytes were modified.
+//!
+//! ``` rust
+//! use passable_guard::{PassableContainer, ReconstituteError};
+//!
+//! let (guard, ptr) = unsafe { PassableContainer::new(vec![0u8; 8]).pass_checksummed() };
+//!
+//! // The FFI writes to the buffer anyway
+//! unsafe { *ptr.add(2) = 1 };
+//!
+//! let result = unsafe { guard.reconstitute(ptr) };
+//! assert!(matches!(result, Err(ReconstituteError::ContentModified {..})));
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+/// A guard that keeps a checksum of the passed buffer to detect modifications by the FFI
+///
+/// Created by [PassableContainer::pass_checksummed]
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted, it will panic just like a [PassableGuard]
+#[derive(Debug)]
+pub struct ChecksumGuard<PAS: PassableLen> {
+    guard: PassableGuard<PAS>,
+    checksum: u64
+}
+
+impl<PAS: PassableLen> ChecksumGuard<PAS> {
+    /// Get the checksum taken when the buffer was passed
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+
+    /// Reconstitute a raw pointer back into a [PassableContainer], verifying that the buffer has not been modified
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute],
+    /// or [ReconstituteError::ContentModified] if the checksum of the buffer does not match anymore.
+    /// In that case the [Passable] is still reconstituted, but dropped
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its policy
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.guard.check(ptr)?;
+
+        let actual = checksum::<PAS>(self.guard.ptr, &self.guard.metadata);
+        let container = self.guard.reconstitute(ptr)?;
+        if actual != self.checksum {
+            return Err(
+                ReconstituteError::ContentModified {
+                    expected: self.checksum,
+                    actual
+                }
+            );
+        }
+        Ok(container)
+    }
+
+    /// Discard the checksum and get the plain [PassableGuard]
+    pub fn into_guard(self) -> PassableGuard<PAS> {
+        self.guard
+    }
+}
+
+/// Hash the bytes of a passed buffer
+unsafe fn checksum<PAS: PassableLen>(ptr: *mut PAS::Pointee, metadata: &PAS::Metadata) -> u64 {
+    let size = PAS::passed_len(metadata) * std::mem::size_of::<<PAS as Passable>::Pointee>();
+    let mut hasher = DefaultHasher::new();
+    hasher.write(std::slice::from_raw_parts(ptr as *const u8, size));
+    hasher.finish()
+}
+
+impl<PAS: PassableLen> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer and a [ChecksumGuard] that remembers a checksum of the passed buffer
+    ///
+    /// ### Safety
+    /// The buffer is hashed bytewise, so the pointee type must not contain any padding bytes
+    #[track_caller]
+    pub unsafe fn pass_checksummed(self) -> (ChecksumGuard<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass();
+        let checksum = checksum::<PAS>(ptr, &guard.metadata);
+        (ChecksumGuard {guard, checksum}, ptr)
+    }
+}
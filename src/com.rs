@@ -0,0 +1,121 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+use std::os::raw::c_void;
+
+use crate::Passable;
+
+/// The `IUnknown` part of the vtable every COM interface starts with
+#[repr(C)]
+struct IUnknownVtbl {
+    query_interface: unsafe extern "system" fn(this: *mut c_void, iid: *const c_void, object: *mut *mut c_void) -> i32,
+    add_ref: unsafe extern "system" fn(this: *mut c_void) -> u32,
+    release: unsafe extern "system" fn(this: *mut c_void) -> u32
+}
+
+/// An owned reference to a COM interface, holding one reference count that is released when it is dropped
+///
+/// Passing it hands the interface pointer together with its reference count to native code,
+/// so a guard that is never reconstituted reports the missing `Release` like a leaked allocation.
+/// If the native code calls `Release` itself, disarm the guard with [mark_returned](crate::PassableGuard::mark_returned)
+/// and drop the returned token instead of reconstituting it.
+/// Pass a clone to give native code an additional reference.
+///
+/// ``` rust
+/// use passable_guard::{ComPtr, PassableContainer};
+///
+/// fn lend(interface: &ComPtr) {
+///     let (guard, ptr) = PassableContainer::new(interface.clone()).pass();
+///
+///     // Hand `ptr` to native code that keeps the interface for a while
+///
+///     drop(unsafe { guard.reconstitute(ptr) }.unwrap());
+/// }
+/// ```
+pub struct ComPtr {
+    ptr: *mut c_void
+}
+
+impl ComPtr {
+    /// Take ownership of a reference to a COM interface
+    ///
+    /// ### Safety
+    /// `ptr` has to be a valid, non-null COM interface pointer whose reference count is not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut c_void) -> Self {
+        Self {
+            ptr
+        }
+    }
+
+    /// Add a reference to a COM interface that is not owned by the caller
+    ///
+    /// ### Safety
+    /// `ptr` has to be a valid, non-null COM interface pointer
+    pub unsafe fn from_borrowed(ptr: *mut c_void) -> Self {
+        (Self::vtable(ptr).add_ref)(ptr);
+        Self {
+            ptr
+        }
+    }
+
+    /// Give up ownership of the reference count, it has to be released with `IUnknown::Release`
+    pub fn into_raw(self) -> *mut c_void {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Get the interface pointer without giving up ownership
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+
+    /// Query the object for the interface with the IID at `iid`, returning a new reference to it if it is supported
+    ///
+    /// ### Safety
+    /// `iid` has to point to a valid GUID
+    pub unsafe fn query_interface(&self, iid: *const c_void) -> Option<ComPtr> {
+        let mut object = std::ptr::null_mut();
+        let result = (Self::vtable(self.ptr).query_interface)(self.ptr, iid, &mut object);
+        if result < 0 || object.is_null() {
+            return None;
+        }
+        Some(ComPtr::from_raw(object))
+    }
+
+    unsafe fn vtable<'a>(ptr: *mut c_void) -> &'a IUnknownVtbl {
+        &**(ptr as *mut *const IUnknownVtbl)
+    }
+}
+
+impl Clone for ComPtr {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_borrowed(self.ptr) }
+    }
+}
+
+impl Drop for ComPtr {
+    fn drop(&mut self) {
+        unsafe { (Self::vtable(self.ptr).release)(self.ptr) };
+    }
+}
+
+impl Debug for ComPtr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ComPtr")
+            .field(&self.ptr)
+            .finish()
+    }
+}
+
+impl Passable for ComPtr {
+    type Pointee = c_void;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut c_void, Self::Metadata) {
+        (self.into_raw(), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut c_void, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(ComPtr::from_raw(ptr))
+    }
+}
@@ -0,0 +1,134 @@
+//! Guards that travel through the `void *user_data` of a C completion callback
+//!
+//! Asynchronous C APIs take a callback and a context pointer they hand back once the operation is done.
+//! A [CompletionGuard] is turned into that context pointer, so no guard has to stay behind on the Rust side,
+//! and the `extern "C"` trampoline of the callback turns it back into the guard to reconstitute the [Passable]:
+//!
+//! ``` rust
+//! use std::os::raw::c_void;
+//! use passable_guard::{CompletionGuard, PassableContainer};
+//!
+//! extern "C" fn on_complete(context: *mut c_void, buffer: *mut u8) {
+//!     let guard = unsafe { CompletionGuard::<Vec<u8>>::from_context(context) };
+//!     let buffer = unsafe { guard.reconstitute(buffer) }.unwrap().into_inner();
+//!     assert_eq!(buffer.len(), 64);
+//! }
+//!
+//! let (guard, ptr) = PassableContainer::new(vec![0u8; 64]).pass_completion();
+//! let context = guard.into_context();
+//!
+//! // Hand `ptr`, `on_complete` and `context` to C, which calls back on any thread once it is done
+//! on_complete(context, ptr);
+//! ```
+
+use std::fmt::{Debug, Formatter};
+use std::os::raw::c_void;
+
+use crate::tag;
+use crate::{ConfiguredPolicy, GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError, TypeTagError};
+
+/// A guard that can be converted to and from the context pointer of a C completion callback
+///
+/// ### Panic
+/// Like a [PassableGuard], this guard will apply its [GuardPolicy] if it is dropped while still armed.
+/// While it is converted into a context pointer it is not dropped,
+/// so if the callback never hands the context back, the guard and the [Passable] are leaked
+pub struct CompletionGuard<PAS: Passable, P: GuardPolicy = ConfiguredPolicy> {
+    guard: PassableGuard<PAS, P>
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// so the completion callback can reconstitute it on any thread
+unsafe impl<PAS: Passable + Send, P: GuardPolicy> Send for CompletionGuard<PAS, P> {}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer to pass it to an asynchronous FFI call,
+    /// getting a [CompletionGuard] to hand over as the context of its completion callback
+    #[track_caller]
+    pub fn pass_completion(self) -> (CompletionGuard<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass();
+        (CompletionGuard::new(guard), ptr)
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> CompletionGuard<PAS, P> {
+    /// Wrap a [PassableGuard] so it can be handed over as a context pointer
+    pub fn new(guard: PassableGuard<PAS, P>) -> Self {
+        Self {guard}
+    }
+
+    /// Convert the guard into a context pointer for a completion callback
+    ///
+    /// The pointer has to be converted back with [CompletionGuard::from_context] exactly once
+    pub fn into_context(self) -> *mut c_void {
+        Box::into_raw(Box::new(self)) as *mut c_void
+    }
+
+    /// Convert a context pointer created with [CompletionGuard::into_context] back into the guard
+    ///
+    /// ### Safety
+    /// `context` has to come from [CompletionGuard::into_context] of a guard with the same [Passable] and [GuardPolicy]
+    /// and must not have been converted back before
+    pub unsafe fn from_context(context: *mut c_void) -> Self {
+        *Box::from_raw(context as *mut Self)
+    }
+
+    /// Convert the guard into a context pointer with a hidden type tag, see [TypeTagError]
+    ///
+    /// The pointer has to be converted back with [CompletionGuard::from_tagged_context] exactly once
+    pub fn into_tagged_context(self) -> *mut c_void {
+        tag::into_tagged(self)
+    }
+
+    /// Convert a context pointer created with [CompletionGuard::into_tagged_context] back into the guard,
+    /// checking that it was created for the same [Passable] and [GuardPolicy]
+    ///
+    /// ### Errors
+    /// Will return an Error and leave the context untouched if it was created for a different type
+    ///
+    /// ### Safety
+    /// `context` has to come from [CompletionGuard::into_tagged_context] or [GuardCompletion::into_tagged_context](crate::GuardCompletion::into_tagged_context)
+    /// and must not have been converted back before
+    pub unsafe fn from_tagged_context(context: *mut c_void) -> Result<Self, TypeTagError> {
+        tag::from_tagged(context)
+    }
+
+    /// Reconstitute the [Passable] from the pointer handed to the completion callback
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.guard.reconstitute(ptr)
+    }
+
+    /// Reconstitute the [Passable] from the pointer the guard kept, for callbacks that do not hand the pointer back
+    ///
+    /// ### Errors
+    /// Will return an Error if the [Passable] cannot be reconstituted from its pointer
+    ///
+    /// ### Safety
+    /// The FFI must not use the pointer anymore, the same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn complete(self) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        let ptr = self.guard.repass();
+        self.guard.reconstitute(ptr)
+    }
+
+    /// Get the wrapped [PassableGuard] back
+    pub fn into_guard(self) -> PassableGuard<PAS, P> {
+        self.guard
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Debug for CompletionGuard<PAS, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CompletionGuard")
+            .field("guard", &self.guard)
+            .finish()
+    }
+}
@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Once;
+
+#[cfg(feature = "diagnostics")]
+use crate::registry;
+use crate::DropPolicy;
+
+static DEFAULT_POLICY: AtomicU8 = AtomicU8::new(0);
+static CAPTURE_BACKTRACES: AtomicBool = AtomicBool::new(false);
+static ENVIRONMENT: Once = Once::new();
+
+/// The policies that can be the global default, [DropPolicy::Free] is only sound for guards that chose it explicitly
+const POLICIES: [DropPolicy; 4] = [DropPolicy::Panic, DropPolicy::Leak, DropPolicy::Report, DropPolicy::Abort];
+
+/// The global runtime configuration of the crate
+///
+/// Applied with [configure]. Every setting can be overridden through an environment variable,
+/// so it can be tuned at deploy time without recompiling:
+///
+/// - `PASSABLE_GUARD_POLICY`: `panic`, `leak`, `report` or `abort`
+/// - `PASSABLE_GUARD_BACKTRACES`: `1` or `0`
+/// - `PASSABLE_GUARD_REGISTRY`: `1` or `0`
+/// - `PASSABLE_GUARD_SAMPLE_RATE`: a positive number
+///
+/// The environment is read when the first guard is created, even if [configure] is never called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// The [DropPolicy] applied by [ConfiguredPolicy](crate::ConfiguredPolicy) to guards without a policy of their own
+    ///
+    /// This can not be [DropPolicy::Free], since freeing is only sound for guards whose FFI is known to be done with the buffer
+    pub default_policy: DropPolicy,
+    /// Whether a backtrace is captured for every guard recorded in the [registry]
+    pub capture_backtraces: bool,
+    /// Whether newly created guards are recorded in the [registry]
+    pub registry_enabled: bool,
+    /// Only fully track one in every `sample_rate` guards, see [registry::set_sample_rate]
+    pub sample_rate: u64
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            default_policy: DropPolicy::Panic,
+            capture_backtraces: false,
+            registry_enabled: false,
+            sample_rate: 1
+        }
+    }
+}
+
+/// Apply the global runtime configuration, environment variables still take precedence
+///
+/// ``` rust
+/// use passable_guard::{Config, DropPolicy};
+///
+/// passable_guard::configure(Config {
+///     default_policy: DropPolicy::Report,
+///     registry_enabled: true,
+///     ..Config::default()
+/// });
+/// ```
+///
+/// ### Panic
+/// Panics if the default policy is [DropPolicy::Free], which has to be chosen for each guard instead
+pub fn configure(config: Config) {
+    assert_ne!(config.default_policy, DropPolicy::Free, "DropPolicy::Free can not be the default policy");
+    apply(config);
+    apply_environment();
+}
+
+fn apply(config: Config) {
+    set_default_policy(config.default_policy);
+    CAPTURE_BACKTRACES.store(config.capture_backtraces, Ordering::Relaxed);
+    #[cfg(feature = "diagnostics")]
+    {
+        if config.registry_enabled {
+            registry::enable();
+        }
+        else {
+            registry::disable();
+        }
+        registry::set_sample_rate(config.sample_rate);
+    }
+}
+
+fn apply_environment() {
+    if let Some(policy) = variable("PASSABLE_GUARD_POLICY").and_then(|policy| parse_policy(&policy)) {
+        set_default_policy(policy);
+    }
+    if let Some(capture) = flag("PASSABLE_GUARD_BACKTRACES") {
+        CAPTURE_BACKTRACES.store(capture, Ordering::Relaxed);
+    }
+    #[cfg(feature = "diagnostics")]
+    match flag("PASSABLE_GUARD_REGISTRY") {
+        Some(true) => registry::enable(),
+        Some(false) => registry::disable(),
+        None => {}
+    }
+    #[cfg(feature = "diagnostics")]
+    if let Some(rate) = variable("PASSABLE_GUARD_SAMPLE_RATE").and_then(|rate| rate.parse().ok()) {
+        registry::set_sample_rate(rate);
+    }
+}
+
+fn variable(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+fn flag(name: &str) -> Option<bool> {
+    match variable(name)?.trim() {
+        "1" | "true" | "on" => Some(true),
+        "0" | "false" | "off" => Some(false),
+        _ => None
+    }
+}
+
+fn parse_policy(policy: &str) -> Option<DropPolicy> {
+    match policy.trim().to_ascii_lowercase().as_str() {
+        "panic" => Some(DropPolicy::Panic),
+        "leak" => Some(DropPolicy::Leak),
+        "report" => Some(DropPolicy::Report),
+        "abort" => Some(DropPolicy::Abort),
+        _ => None
+    }
+}
+
+fn set_default_policy(policy: DropPolicy) {
+    let index = POLICIES.iter()
+        .position(|candidate| *candidate == policy)
+        .unwrap_or(0);
+    DEFAULT_POLICY.store(index as u8, Ordering::Relaxed);
+}
+
+/// Read the environment overrides once, before the first guard is created
+pub(crate) fn init() {
+    ENVIRONMENT.call_once(apply_environment);
+}
+
+pub(crate) fn default_policy() -> DropPolicy {
+    POLICIES[DEFAULT_POLICY.load(Ordering::Relaxed) as usize]
+}
+
+#[cfg(feature = "diagnostics")]
+pub(crate) fn capture_backtraces() -> bool {
+    CAPTURE_BACKTRACES.load(Ordering::Relaxed)
+}
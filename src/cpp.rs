@@ -0,0 +1,48 @@
+use std::convert::Infallible;
+
+use cxx::memory::UniquePtrTarget;
+use cxx::vector::VectorElement;
+use cxx::{CxxVector, ExternType, UniquePtr};
+
+use crate::{Passable, PassableContainer};
+
+/// An owning C++ pointer passed as the raw pointer of the `std::unique_ptr`,
+/// for C++ APIs the typed [cxx::bridge] cannot express, for example ones taking a `std::string *` or `std::vector<T> *`
+///
+/// The reconstituted [UniquePtr] deletes the object again.
+/// A `UniquePtr<CxxString>` returned by a bridge function is passed as a `std::string *` this way
+impl<T: UniquePtrTarget> Passable for UniquePtr<T> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        (self.into_raw(), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(UniquePtr::from_raw(ptr))
+    }
+}
+
+/// Create a [PassableContainer] for a new `std::vector<T>` containing `items`
+///
+/// ``` rust
+/// let (guard, ptr) = passable_guard::cxx_vector([1u8, 2, 3]).pass();
+///
+/// // Hand `ptr` to a C++ function taking a `std::vector<uint8_t> *`
+///
+/// let vector = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(vector.as_slice(), &[1, 2, 3]);
+/// ```
+pub fn cxx_vector<T, I>(items: I) -> PassableContainer<UniquePtr<CxxVector<T>>>
+where
+    T: VectorElement + ExternType<Kind = cxx::kind::Trivial>,
+    I: IntoIterator<Item = T>
+{
+    let mut vector = CxxVector::new();
+    for item in items {
+        vector.pin_mut().push(item);
+    }
+    PassableContainer::new(vector)
+}
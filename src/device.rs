@@ -0,0 +1,182 @@
+//! Guarded device memory
+//!
+//! A [DeviceBuffer] lent to a kernel-launching C API is guarded like host memory,
+//! and [FreePolicy](crate::FreePolicy) releases it with the deallocator of its [DeviceAllocator].
+//!
+//! The CUDA runtime is not linked by this crate, link `cudart` from the build script of the final binary,
+//! as with the other CUDA bindings.
+//!
+//! ``` rust
+//! use std::os::raw::c_void;
+//! use passable_guard::{Cuda, CudaError, DeviceBuffer, PassableContainer};
+//!
+//! extern "C" {
+//!     /// Launches a kernel on `len` floats in device memory and waits for it
+//!     fn scale_on_device(data: *mut c_void, len: usize);
+//! }
+//!
+//! fn scale(len: usize) -> Result<(), CudaError> {
+//!     let buffer = DeviceBuffer::<Cuda>::new(len * std::mem::size_of::<f32>())?;
+//!     let (guard, ptr) = PassableContainer::new(buffer).pass();
+//!
+//!     unsafe { scale_on_device(ptr, len) };
+//!
+//!     // Frees the device memory with cudaFree
+//!     drop(unsafe { guard.reconstitute(ptr) }.unwrap());
+//!     Ok(())
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::os::raw::c_void;
+
+use crate::Passable;
+
+extern "C" {
+    fn cudaMalloc(ptr: *mut *mut c_void, size: usize) -> i32;
+    fn cudaFree(ptr: *mut c_void) -> i32;
+}
+
+/// An allocator for device memory that a [DeviceBuffer] can be allocated with
+///
+/// ### Safety
+/// Memory returned by [allocate](DeviceAllocator::allocate) has to be releasable with [free](DeviceAllocator::free)
+pub unsafe trait DeviceAllocator {
+    /// The Error returned if device memory could not be allocated
+    type Error: Debug;
+
+    /// Allocate `size` bytes of device memory
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory could not be allocated
+    fn allocate(size: usize) -> Result<*mut c_void, Self::Error>;
+
+    /// Free memory returned by [allocate](DeviceAllocator::allocate)
+    ///
+    /// ### Safety
+    /// `ptr` has to be returned by [allocate](DeviceAllocator::allocate) and must not be used afterwards, also not by a running kernel
+    unsafe fn free(ptr: *mut c_void);
+}
+
+/// An Error code returned by the CUDA runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CudaError {
+    pub code: i32
+}
+
+impl Display for CudaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CUDA runtime error {}", self.code)
+    }
+}
+
+impl Error for CudaError {}
+
+/// Device memory from `cudaMalloc`, freed with `cudaFree`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Cuda;
+
+unsafe impl DeviceAllocator for Cuda {
+    type Error = CudaError;
+
+    fn allocate(size: usize) -> Result<*mut c_void, CudaError> {
+        let mut ptr = std::ptr::null_mut();
+        match unsafe { cudaMalloc(&mut ptr, size) } {
+            0 => Ok(ptr),
+            code => Err(CudaError {code})
+        }
+    }
+
+    unsafe fn free(ptr: *mut c_void) {
+        // Errors of earlier asynchronous launches can be reported here, there is nothing left to do about them
+        cudaFree(ptr);
+    }
+}
+
+/// A buffer of device memory allocated with `A`, which is also used to free it
+pub struct DeviceBuffer<A: DeviceAllocator> {
+    ptr: *mut c_void,
+    size: usize,
+    _allocator: PhantomData<A>
+}
+
+impl<A: DeviceAllocator> DeviceBuffer<A> {
+    /// Allocate `size` bytes of uninitialized device memory
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory could not be allocated
+    pub fn new(size: usize) -> Result<Self, A::Error> {
+        Ok(Self {
+            ptr: A::allocate(size)?,
+            size,
+            _allocator: PhantomData
+        })
+    }
+
+    /// Take ownership of `size` bytes of device memory
+    ///
+    /// ### Safety
+    /// `ptr` has to be allocated by `A` and not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut c_void, size: usize) -> Self {
+        Self {
+            ptr,
+            size,
+            _allocator: PhantomData
+        }
+    }
+
+    /// Give up ownership of the device memory, it has to be freed with `A`
+    pub fn into_raw(self) -> *mut c_void {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Get the device pointer without giving up ownership
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+
+    /// Get the size of the buffer in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<A: DeviceAllocator> Drop for DeviceBuffer<A> {
+    fn drop(&mut self) {
+        unsafe { A::free(self.ptr) }
+    }
+}
+
+impl<A: DeviceAllocator> Debug for DeviceBuffer<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeviceBuffer")
+            .field("allocator", &std::any::type_name::<A>())
+            .field("ptr", &self.ptr)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl<A: DeviceAllocator> Passable for DeviceBuffer<A> {
+    type Pointee = c_void;
+    type ReconstituteError = Infallible;
+    /// The size of the buffer in bytes
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut c_void, Self::Metadata) {
+        let size = self.size;
+        (self.into_raw(), size)
+    }
+
+    unsafe fn reconstitute(ptr: *mut c_void, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(DeviceBuffer::from_raw(ptr, metadata))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(*metadata)
+    }
+}
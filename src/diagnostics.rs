@@ -0,0 +1,224 @@
+//! Lifecycle diagnostics of guards, routed to the enabled integrations
+
+#[cfg(feature = "log")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::events::{self, GuardEvent, GuardInfo};
+use crate::{GuardPolicy, Passable, PassableGuard};
+
+/// The [log] levels the guard lifecycle diagnostics are logged at
+///
+/// Set with [set_log_levels](crate::set_log_levels)
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogLevels {
+    pub passed: log::Level,
+    pub reconstituted: log::Level,
+    pub reconstitute_failed: log::Level,
+    pub mismatched: log::Level,
+    pub leaked: log::Level,
+    /// The level of guards marked as returned, see [PassableGuard::mark_returned]
+    pub defused: log::Level
+}
+
+#[cfg(feature = "log")]
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            passed: log::Level::Debug,
+            reconstituted: log::Level::Debug,
+            reconstitute_failed: log::Level::Warn,
+            mismatched: log::Level::Warn,
+            leaked: log::Level::Error,
+            defused: log::Level::Debug
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+static LOG_LEVELS: [AtomicUsize; 6] = [
+    AtomicUsize::new(log::Level::Debug as usize),
+    AtomicUsize::new(log::Level::Debug as usize),
+    AtomicUsize::new(log::Level::Warn as usize),
+    AtomicUsize::new(log::Level::Warn as usize),
+    AtomicUsize::new(log::Level::Error as usize),
+    AtomicUsize::new(log::Level::Debug as usize)
+];
+
+/// Set the [log] levels the guard lifecycle diagnostics are logged at
+///
+/// ``` rust
+/// use passable_guard::LogLevels;
+///
+/// // Leaks under non-panicking policies are only ever logged, so make them stand out
+/// passable_guard::set_log_levels(LogLevels {
+///     passed: log::Level::Trace,
+///     reconstituted: log::Level::Trace,
+///     ..LogLevels::default()
+/// });
+/// ```
+#[cfg(feature = "log")]
+pub fn set_log_levels(levels: LogLevels) {
+    let levels = [levels.passed, levels.reconstituted, levels.reconstitute_failed, levels.mismatched, levels.leaked, levels.defused];
+    for (slot, level) in LOG_LEVELS.iter().zip(levels.iter()) {
+        slot.store(*level as usize, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "log")]
+fn log_level(index: usize) -> log::Level {
+    match LOG_LEVELS[index].load(Ordering::Relaxed) {
+        1 => log::Level::Error,
+        2 => log::Level::Warn,
+        3 => log::Level::Info,
+        4 => log::Level::Debug,
+        _ => log::Level::Trace
+    }
+}
+
+#[cfg(feature = "log")]
+fn log_event<PAS: Passable, P: GuardPolicy>(index: usize, guard: &PassableGuard<PAS, P>, message: &str) {
+    log::log!(
+        target: "passable_guard",
+        log_level(index),
+        "{} (#{} {}: {} at {:p})",
+        message,
+        guard.id,
+        guard.options.label.unwrap_or("unlabeled"),
+        std::any::type_name::<PAS>(),
+        guard.ptr
+    );
+}
+
+fn info<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) -> GuardInfo {
+    GuardInfo {
+        id: guard.id,
+        type_name: std::any::type_name::<PAS>(),
+        ptr: guard.ptr as *mut (),
+        label: guard.options.label,
+        held_for: guard.held_for()
+    }
+}
+
+pub(crate) fn passed<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) {
+    events::emit(GuardEvent::Passed {guard: info(guard)});
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        "passable passed"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(0, guard, "passable passed");
+
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("passable_guard_passes_total").increment(1);
+        metrics::gauge!("passable_guard_outstanding").increment(1.0);
+    }
+}
+
+pub(crate) fn reconstituted<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) {
+    events::emit(GuardEvent::Reconstituted {guard: info(guard)});
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        "passable reconstituted"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(1, guard, "passable reconstituted");
+}
+
+pub(crate) fn reconstitute_failed<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) {
+    events::emit(GuardEvent::ReconstituteFailed {guard: info(guard)});
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        "passable could not be reconstituted"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(2, guard, "passable could not be reconstituted");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("passable_guard_reconstitute_failures_total").increment(1);
+}
+
+pub(crate) fn mismatched<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>, reconstituted: *mut PAS::Pointee) {
+    events::emit(GuardEvent::Mismatched {guard: info(guard), reconstituted: reconstituted as *mut ()});
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        reconstituted = ?reconstituted,
+        "pointer mismatch"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(3, guard, "pointer mismatch");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("passable_guard_mismatches_total").increment(1);
+}
+
+pub(crate) fn defused<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) {
+    events::emit(GuardEvent::Defused {guard: info(guard)});
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        "passable marked as returned"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(5, guard, "passable marked as returned");
+}
+
+pub(crate) fn leaked<PAS: Passable, P: GuardPolicy>(guard: &PassableGuard<PAS, P>) {
+    events::emit(GuardEvent::Leaked {guard: info(guard)});
+
+    #[cfg(feature = "tracing")]
+    tracing::error!(
+        target: "passable_guard",
+        id = guard.id,
+        label = guard.options.label,
+        type_name = std::any::type_name::<PAS>(),
+        ptr = ?guard.ptr,
+        "passable guard dropped before being reconstituted"
+    );
+
+    #[cfg(feature = "log")]
+    log_event(4, guard, "passable guard dropped before being reconstituted");
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!("passable_guard_leaks_total").increment(1);
+}
+
+pub(crate) fn released() {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("passable_guard_outstanding").decrement(1.0);
+}
@@ -0,0 +1,71 @@
+use std::ops::Deref;
+
+use libloading::{Library, Symbol};
+
+use crate::{Passable, PassableContainer};
+
+/// A function looked up in a dynamically loaded library, which passes its arguments with the same scoped treatment as [PassableContainer::pass_with]
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use libloading::Library;
+/// use passable_guard::{GuardedSymbol, PassableContainer};
+///
+/// fn set_plugin_name(plugin: &Library, name: CString) -> Result<u8, Box<dyn std::error::Error>> {
+///     let set_name = unsafe { GuardedSymbol::<unsafe extern "C" fn(*mut u8) -> u8>::load(plugin, b"setName\0") }?;
+///
+///     let (result, _name) = unsafe {
+///         set_name.call_guarded(PassableContainer::new(name), |set_name, ptr| set_name(ptr))
+///     }?;
+///     Ok(result)
+/// }
+/// ```
+pub struct GuardedSymbol<'lib, F> {
+    symbol: Symbol<'lib, F>
+}
+
+impl<'lib, F> GuardedSymbol<'lib, F> {
+    /// Look up the symbol `name` in `library`
+    ///
+    /// ### Errors
+    /// Will return an Error if the library does not contain the symbol
+    ///
+    /// ### Safety
+    /// `F` has to match the actual type of the symbol, see [Library::get]
+    pub unsafe fn load(library: &'lib Library, name: &[u8]) -> Result<Self, libloading::Error> {
+        Ok(Self {
+            symbol: library.get(name)?
+        })
+    }
+
+    /// Pass `container`, call `call` with the symbol and the raw pointer and reconstitute the container afterwards
+    ///
+    /// Returns the result of `call` together with the recovered container
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the loaded function
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableContainer::pass_with] apply
+    pub unsafe fn call_guarded<PAS, R, C>(&self, container: PassableContainer<PAS>, call: C) -> Result<(R, PassableContainer<PAS>), PAS::ReconstituteError>
+        where PAS: Passable, C: FnOnce(&F, *mut PAS::Pointee) -> R
+    {
+        container.pass_with(|ptr| call(&self.symbol, ptr))
+    }
+}
+
+impl<'lib, F> From<Symbol<'lib, F>> for GuardedSymbol<'lib, F> {
+    fn from(symbol: Symbol<'lib, F>) -> Self {
+        Self {
+            symbol
+        }
+    }
+}
+
+impl<'lib, F> Deref for GuardedSymbol<'lib, F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.symbol
+    }
+}
@@ -0,0 +1,111 @@
+//! Lifecycle events of guards
+//!
+//! A hook set with [set_event_hook] is called for every [GuardEvent],
+//! so downstream tooling can follow the lifecycle of every guard.
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use std::sync::{Arc, Mutex};
+//! use passable_guard::{GuardEvent, PassableContainer};
+//!
+//! let events = Arc::new(Mutex::new(Vec::new()));
+//!
+//! let recorded = events.clone();
+//! passable_guard::set_event_hook(move |event| recorded.lock().unwrap().push(event));
+//!
+//! let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+//! unsafe { guard.reconstitute(ptr) }.unwrap();
+//!
+//! passable_guard::clear_event_hook();
+//!
+//! let events = events.lock().unwrap();
+//! assert!(matches!(events[0], GuardEvent::Passed {..}));
+//! assert!(matches!(events[1], GuardEvent::Reconstituted {..}));
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{PoisonError, RwLock};
+use std::time::Duration;
+
+type EventHook = Box<dyn Fn(GuardEvent) + Send + Sync>;
+
+static HOOK_SET: AtomicBool = AtomicBool::new(false);
+static HOOK: RwLock<Option<EventHook>> = RwLock::new(None);
+
+/// Describes the guard an event happened to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GuardInfo {
+    /// The id of the guard
+    pub id: u64,
+    /// The type name of the guarded [Passable](crate::Passable)
+    pub type_name: &'static str,
+    /// The passed pointer
+    pub ptr: *mut (),
+    /// The label of the guard, if any
+    pub label: Option<&'static str>,
+    /// How long the pointer had been passed for when the event happened, if the guard was sampled
+    pub held_for: Option<Duration>
+}
+
+impl Display for GuardInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.label {
+            Some(label) => write!(f, "#{} {}: {} at {:p}", self.id, label, self.type_name, self.ptr),
+            None => write!(f, "#{} {} at {:p}", self.id, self.type_name, self.ptr)
+        }
+    }
+}
+
+/// An event in the lifecycle of a guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GuardEvent {
+    /// A guard was created for a passed pointer
+    Passed{guard: GuardInfo},
+    /// The pointer was handed back and the [Passable](crate::Passable) was reconstituted
+    Reconstituted{guard: GuardInfo},
+    /// The pointer was handed back but the [Passable](crate::Passable) could not be reconstituted
+    ReconstituteFailed{guard: GuardInfo},
+    /// A different pointer than the passed one was handed back
+    Mismatched{guard: GuardInfo, reconstituted: *mut ()},
+    /// The guard was disarmed without reconstituting the [Passable](crate::Passable) yet
+    Defused{guard: GuardInfo},
+    /// The guard was dropped while still armed
+    Leaked{guard: GuardInfo},
+    /// The guard has been held longer than the threshold of the [watchdog](crate::enable_watchdog)
+    Stale{guard: GuardInfo}
+}
+
+// The pointers are only kept for diagnostics and are never dereferenced
+unsafe impl Send for GuardInfo {}
+unsafe impl Sync for GuardInfo {}
+unsafe impl Send for GuardEvent {}
+unsafe impl Sync for GuardEvent {}
+
+/// Set the hook that is called for every [GuardEvent], replacing the previous one
+///
+/// ### Notes
+/// The hook is called synchronously from the code that caused the event, including drop implementations,
+/// so it should be fast and must not panic
+pub fn set_event_hook<F: Fn(GuardEvent) + Send + Sync + 'static>(hook: F) {
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(hook));
+    HOOK_SET.store(true, Ordering::Release);
+}
+
+/// Remove the hook set with [set_event_hook]
+pub fn clear_event_hook() {
+    HOOK_SET.store(false, Ordering::Release);
+    *HOOK.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+pub(crate) fn emit(event: GuardEvent) {
+    if !HOOK_SET.load(Ordering::Acquire) {
+        return;
+    }
+
+    if let Some(hook) = HOOK.read().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        hook(event);
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{Passable, PassableContainer, PassableGuard};
+
+/// Extension methods to guard a [Passable] without wrapping it in a [PassableContainer] by hand
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::PassableExt;
+///
+/// let (guard, ptr) = CString::new("name").unwrap().pass_guarded();
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// ```
+pub trait PassableExt: Passable {
+    /// Wrap this [Passable] in a [PassableContainer]
+    fn guarded(self) -> PassableContainer<Self> {
+        PassableContainer::new(self)
+    }
+
+    /// Wrap this [Passable] in a [PassableContainer] and directly convert it into a pointer with its guard
+    #[track_caller]
+    fn pass_guarded(self) -> (PassableGuard<Self>, *mut Self::Pointee) {
+        self.guarded().pass()
+    }
+}
+
+impl<PAS: Passable> PassableExt for PAS {}
@@ -0,0 +1,95 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use crate::{GuardPolicy, LeakInfo, ConfiguredPolicy};
+
+/// A Container for a pointer received from a FFI, together with the function of the FFI that frees it
+///
+/// This is the mirror image of a [PassableContainer](crate::PassableContainer):
+/// the memory is owned by the FFI and has to be handed back to it.
+///
+/// ``` rust
+/// use passable_guard::ForeignContainer;
+///
+/// extern "C" {
+///     fn malloc(size: usize) -> *mut u8;
+///     fn free(ptr: *mut u8);
+/// }
+///
+/// let container = unsafe { ForeignContainer::<u8>::new(malloc(16), free) };
+///
+/// // Use container.as_ptr() here
+///
+/// unsafe { container.free() };
+/// ```
+///
+/// ### Panic
+/// If this container is dropped before the pointer has been freed or handed back to the FFI, it will apply its [GuardPolicy].
+/// [FreePolicy](crate::FreePolicy) calls the free function in that case
+pub struct ForeignContainer<T, P: GuardPolicy = ConfiguredPolicy> {
+    ptr: *mut T,
+    free: unsafe extern "C" fn(*mut T),
+    owned: bool,
+    _phantom: PhantomData<P>
+}
+
+impl<T, P: GuardPolicy> ForeignContainer<T, P> {
+    /// Creates a new [ForeignContainer] from a pointer received from a FFI and the function that frees it
+    ///
+    /// ### Safety
+    /// `free` must be the function the FFI expects to be called to free `ptr`
+    /// and `ptr` must not be freed by anyone else
+    pub const unsafe fn new(ptr: *mut T, free: unsafe extern "C" fn(*mut T)) -> Self {
+        Self {
+            ptr,
+            free,
+            owned: true,
+            _phantom: PhantomData
+        }
+    }
+
+    /// Get the contained pointer
+    pub fn as_ptr(&self) -> *mut T {
+        self.ptr
+    }
+
+    /// Free the contained pointer with the free function of the FFI
+    ///
+    /// ### Safety
+    /// The pointer must not be used anymore, neither by Rust nor by the FFI
+    pub unsafe fn free(mut self) {
+        self.owned = false;
+        (self.free)(self.ptr);
+    }
+
+    /// Hand ownership of the pointer back to the FFI without freeing it
+    ///
+    /// This is meant for FFIs that take back ownership of the pointer, for example by freeing it themselves later
+    pub fn into_foreign(mut self) -> *mut T {
+        self.owned = false;
+        self.ptr
+    }
+}
+
+impl<T, P: GuardPolicy> Debug for ForeignContainer<T, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForeignContainer")
+            .field("ptr", &self.ptr)
+            .field("owned", &self.owned)
+            .finish()
+    }
+}
+
+impl<T, P: GuardPolicy> Drop for ForeignContainer<T, P> {
+    /// This function will apply the [GuardPolicy] if the pointer has neither been freed nor handed back to the FFI
+    fn drop(&mut self) {
+        if self.owned {
+            let (ptr, free) = (self.ptr, self.free);
+            let leak = LeakInfo::new::<T, T>("Foreign Container dropped before being freed", ptr);
+            P::armed_drop(&leak, || {
+                // The container still owns the pointer, so it has not been freed yet
+                unsafe { free(ptr) };
+            });
+        }
+    }
+}
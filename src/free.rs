@@ -0,0 +1,86 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+use crate::{GuardPolicy, LeakInfo, ConfiguredPolicy};
+
+/// A guard that makes sure a specific cleanup call happens
+///
+/// This is the same discipline as for a [PassableGuard](crate::PassableGuard), but for arbitrary cleanup obligations
+///
+/// ``` rust
+/// use passable_guard::FreeGuard;
+///
+/// extern "C" {
+///     fn malloc(size: usize) -> *mut u8;
+///     fn free(ptr: *mut u8);
+/// }
+///
+/// let ptr = unsafe { malloc(16) };
+/// let guard = FreeGuard::new(ptr, || unsafe { free(ptr) });
+///
+/// // Use ptr here
+///
+/// guard.run();
+/// ```
+///
+/// ### Panic
+/// If this guard is dropped before it has been run or disarmed, it will apply its [GuardPolicy].
+/// [FreePolicy](crate::FreePolicy) runs the cleanup in that case
+#[must_use = "dropping a FreeGuard without running or disarming it applies its policy"]
+pub struct FreeGuard<F: FnOnce(), P: GuardPolicy = ConfiguredPolicy> {
+    cleanup: Option<F>,
+    leak: LeakInfo,
+    _phantom: PhantomData<P>
+}
+
+impl<F: FnOnce()> FreeGuard<F> {
+    /// Creates a new [FreeGuard] for `ptr` that has to be cleaned up by calling `cleanup`
+    pub fn new<T>(ptr: *mut T, cleanup: F) -> Self {
+        Self::with_policy(ptr, cleanup)
+    }
+}
+
+impl<F: FnOnce(), P: GuardPolicy> FreeGuard<F, P> {
+    /// Creates a new [FreeGuard] for `ptr` that has to be cleaned up by calling `cleanup`,
+    /// using the [GuardPolicy] `P`
+    pub fn with_policy<T>(ptr: *mut T, cleanup: F) -> Self {
+        Self {
+            cleanup: Some(cleanup),
+            leak: LeakInfo::new::<T, T>("Free Guard dropped before being run", ptr),
+            _phantom: Default::default()
+        }
+    }
+
+    /// Run the cleanup and disarm the guard
+    pub fn run(mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            cleanup();
+        }
+    }
+
+    /// Disarm the guard without running the cleanup
+    ///
+    /// This is meant for the case where the obligation has been fulfilled in a different way,
+    /// for example because the FFI took over the cleanup
+    pub fn disarm(mut self) {
+        self.cleanup = None;
+    }
+}
+
+impl<F: FnOnce(), P: GuardPolicy> Debug for FreeGuard<F, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FreeGuard")
+            .field("ptr", &self.leak.ptr)
+            .field("armed", &self.cleanup.is_some())
+            .finish()
+    }
+}
+
+impl<F: FnOnce(), P: GuardPolicy> Drop for FreeGuard<F, P> {
+    /// This function will apply the [GuardPolicy] if the guard has neither been run nor disarmed
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            P::armed_drop(&self.leak, cleanup);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::convert::Infallible;
+use std::ffi::{CStr, CString, NulError};
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_char;
+
+use glib_sys::{g_free, g_malloc0, g_strdup};
+
+use crate::{Passable, PassableLen};
+
+/// An owned NUL-terminated string allocated with `g_strdup`, freed with `g_free`
+///
+/// GLib and GTK APIs that take ownership of a string free it with `g_free`,
+/// which must not be given memory from the Rust allocator.
+/// Hand such an API the pointer from [PassableContainer::leak](crate::PassableContainer::leak)
+///
+/// ``` rust
+/// use passable_guard::{GlibString, PassableContainer};
+///
+/// let (guard, ptr) = PassableContainer::new(GlibString::new("title").unwrap()).pass();
+///
+/// // Hand `ptr` to a GLib API that borrows the string
+///
+/// let title = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(title.to_str(), Ok("title"));
+/// ```
+pub struct GlibString {
+    ptr: *mut c_char
+}
+
+impl GlibString {
+    /// Copy `string` into a string allocated by GLib
+    ///
+    /// ### Errors
+    /// Will return an Error if `string` contains a NUL byte
+    pub fn new(string: &str) -> Result<Self, NulError> {
+        let string = CString::new(string)?;
+        Ok(Self::from_c_str(&string))
+    }
+
+    /// Copy `string` into a string allocated by GLib
+    pub fn from_c_str(string: &CStr) -> Self {
+        Self {
+            ptr: unsafe { g_strdup(string.as_ptr()) }
+        }
+    }
+
+    /// Take ownership of a string allocated by GLib, for example one returned by a GLib API
+    ///
+    /// ### Safety
+    /// `ptr` has to be a NUL-terminated string allocated by GLib that is not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut c_char) -> Self {
+        Self {
+            ptr
+        }
+    }
+
+    /// Give up ownership of the string, it has to be freed with `g_free`
+    pub fn into_raw(self) -> *mut c_char {
+        ManuallyDrop::new(self).ptr
+    }
+
+    /// Get the string as a [CStr]
+    pub fn as_c_str(&self) -> &CStr {
+        unsafe { CStr::from_ptr(self.ptr) }
+    }
+}
+
+impl Deref for GlibString {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        self.as_c_str()
+    }
+}
+
+impl Drop for GlibString {
+    fn drop(&mut self) {
+        unsafe { g_free(self.ptr as *mut _) }
+    }
+}
+
+impl Debug for GlibString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self.as_c_str(), f)
+    }
+}
+
+impl Passable for GlibString {
+    type Pointee = c_char;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut c_char, Self::Metadata) {
+        (self.into_raw(), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut c_char, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(GlibString::from_raw(ptr))
+    }
+}
+
+/// A zeroed byte buffer allocated with `g_malloc0`, freed with `g_free`
+///
+/// ``` rust
+/// use passable_guard::{GlibBuffer, PassableContainer};
+///
+/// let (guard, ptr, len) = PassableContainer::new(GlibBuffer::from_slice(b"data")).pass_with_len();
+/// assert_eq!(len, 4);
+///
+/// let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(&*buffer, b"data");
+/// ```
+pub struct GlibBuffer {
+    ptr: *mut u8,
+    len: usize
+}
+
+impl GlibBuffer {
+    /// Allocate a zeroed buffer of `len` bytes
+    pub fn new(len: usize) -> Self {
+        // g_malloc0 returns null for 0 bytes, which g_free accepts
+        Self {
+            ptr: unsafe { g_malloc0(len) } as *mut u8,
+            len
+        }
+    }
+
+    /// Allocate a buffer containing a copy of `data`
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut buffer = Self::new(data.len());
+        buffer.copy_from_slice(data);
+        buffer
+    }
+
+    /// Take ownership of a buffer of `len` bytes allocated by GLib
+    ///
+    /// ### Safety
+    /// `ptr` has to be allocated by GLib, valid for `len` bytes and not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut u8, len: usize) -> Self {
+        Self {
+            ptr,
+            len
+        }
+    }
+
+    /// Give up ownership of the buffer, it has to be freed with `g_free`
+    pub fn into_raw(self) -> *mut u8 {
+        ManuallyDrop::new(self).ptr
+    }
+}
+
+impl Deref for GlibBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for GlibBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        if self.ptr.is_null() {
+            return &mut [];
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for GlibBuffer {
+    fn drop(&mut self) {
+        unsafe { g_free(self.ptr as *mut _) }
+    }
+}
+
+impl Debug for GlibBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GlibBuffer")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Passable for GlibBuffer {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    /// The length of the buffer in bytes
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        let len = self.len;
+        (self.into_raw(), len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(GlibBuffer::from_raw(ptr, metadata))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(*metadata)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for GlibBuffer {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}
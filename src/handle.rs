@@ -0,0 +1,231 @@
+//! A C API for guards, for FFIs where the foreign side decides when it is done with a buffer
+//!
+//! A guard is turned into an opaque [GuardHandle] that is handed to C together with the pointer.
+//! C signals completion by calling `passable_guard_release`, which checks the pointer and reconstitutes the [Passable]:
+//!
+//! ``` c
+//! typedef struct GuardHandle GuardHandle;
+//!
+//! /* 0: released, 1: null handle, 2: pointer mismatch, 3: reconstitution failed */
+//! int passable_guard_release(GuardHandle *handle, void *ptr);
+//! ```
+//!
+//! ``` rust
+//! use std::os::raw::c_void;
+//! use passable_guard::{GuardHandle, PassableContainer, ReleaseStatus};
+//!
+//! let (guard, ptr) = PassableContainer::new(vec![0u8; 64]).pass();
+//! let handle = GuardHandle::new(guard);
+//!
+//! // Hand `handle` and `ptr` to C, which calls this when it is done with the buffer
+//! let status = unsafe { passable_guard::passable_guard_release(handle, ptr as *mut c_void) };
+//! assert_eq!(status, ReleaseStatus::Released);
+//! ```
+//!
+//! C code that only carries a guard between Rust calls holds it as a [RawGuard] instead.
+
+use std::any::Any;
+use std::fmt::{Debug, Formatter};
+use std::os::raw::c_void;
+
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// The result of `passable_guard_release`, returned to C as an `int`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReleaseStatus {
+    /// The [Passable] was reconstituted and the handle is gone
+    Released = 0,
+    /// The handle was null
+    NullHandle = 1,
+    /// The pointer is not the one the guard was created for, the handle stays valid
+    PointerMismatch = 2,
+    /// The [Passable] could not be reconstituted, the handle is gone
+    ReconstituteFailed = 3
+}
+
+/// A type-erased guard behind a [GuardHandle]
+trait Release {
+    fn check(&self, ptr: *mut c_void) -> bool;
+
+    fn release(self: Box<Self>) -> ReleaseStatus;
+}
+
+struct Completion<PAS: Passable, F> {
+    guard: PassableGuard<PAS>,
+    complete: F
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// and the completion callback is Send, so the handle can be released on any thread
+unsafe impl<PAS: Passable + Send, F: Send> Send for Completion<PAS, F> {}
+
+impl<PAS, F> Release for Completion<PAS, F>
+where
+    PAS: Passable,
+    F: FnOnce(Result<PassableContainer<PAS>, ReconstituteError<PAS>>)
+{
+    fn check(&self, ptr: *mut c_void) -> bool {
+        self.guard.check(ptr as *mut PAS::Pointee).is_ok()
+    }
+
+    fn release(self: Box<Self>) -> ReleaseStatus {
+        let Completion {guard, complete} = *self;
+        // The pointer was checked, so reconstitute from the one the guard kept
+        let ptr = guard.repass();
+        let result = unsafe { guard.reconstitute(ptr) };
+        let status = match result {
+            Ok(_) => ReleaseStatus::Released,
+            Err(_) => ReleaseStatus::ReconstituteFailed
+        };
+        complete(result);
+        status
+    }
+}
+
+/// An opaque handle to a guard, released by C with `passable_guard_release`
+///
+/// A handle C never releases keeps its guard armed forever, it stays visible in the [registry](crate::registry)
+/// but its [GuardPolicy](crate::GuardPolicy) is never applied
+pub struct GuardHandle {
+    guard: Box<dyn Release + Send>
+}
+
+impl GuardHandle {
+    /// Turn `guard` into a handle for C, the reconstituted [Passable] is dropped when C releases it
+    pub fn new<PAS: Passable + Send + 'static>(guard: PassableGuard<PAS>) -> *mut GuardHandle {
+        Self::with_completion(guard, drop)
+    }
+
+    /// Turn `guard` into a handle for C, handing the reconstituted [Passable] to `complete` when C releases it
+    ///
+    /// ``` rust
+    /// use std::os::raw::c_void;
+    /// use std::sync::mpsc;
+    /// use passable_guard::{GuardHandle, PassableContainer};
+    ///
+    /// let (sender, receiver) = mpsc::channel();
+    /// let (guard, ptr) = PassableContainer::new(vec![0u8; 4]).pass();
+    /// let handle = GuardHandle::with_completion(guard, move |result| {
+    ///     let _ = sender.send(result.map(|container| container.into_inner()).ok());
+    /// });
+    ///
+    /// // C fills the buffer and releases it, possibly on another thread
+    /// unsafe {
+    ///     (ptr as *mut [u8; 4]).write([1, 2, 3, 4]);
+    ///     passable_guard::passable_guard_release(handle, ptr as *mut c_void);
+    /// }
+    ///
+    /// assert_eq!(receiver.recv().unwrap(), Some(vec![1, 2, 3, 4]));
+    /// ```
+    pub fn with_completion<PAS, F>(guard: PassableGuard<PAS>, complete: F) -> *mut GuardHandle
+    where
+        PAS: Passable + Send + 'static,
+        F: FnOnce(Result<PassableContainer<PAS>, ReconstituteError<PAS>>) + Send + 'static
+    {
+        let guard = Box::new(Completion {guard, complete});
+        Box::into_raw(Box::new(GuardHandle {guard}))
+    }
+}
+
+/// Release a [GuardHandle] from C, reconstituting the [Passable] behind it
+///
+/// If `ptr` is not the pointer the guard was created for, [ReleaseStatus::PointerMismatch] is returned
+/// and the handle stays valid, so it can be released again with the right pointer
+///
+/// ### Safety
+/// `handle` has to be null or a handle created by [GuardHandle::new] or [GuardHandle::with_completion]
+/// that has not been released yet, and the buffer must not be used by C afterwards
+///
+/// ### Panic
+/// Panics in the completion callback abort the process, since they cannot unwind into C
+#[no_mangle]
+pub unsafe extern "C" fn passable_guard_release(handle: *mut GuardHandle, ptr: *mut c_void) -> ReleaseStatus {
+    if handle.is_null() {
+        return ReleaseStatus::NullHandle;
+    }
+    if !(*handle).guard.check(ptr) {
+        return ReleaseStatus::PointerMismatch;
+    }
+    Box::from_raw(handle).guard.release()
+}
+
+/// A guard converted into an FFI-safe value, so C code can carry it between Rust calls
+///
+/// Unlike a [GuardHandle], C cannot release it, it has to be handed back to Rust and converted back with [RawGuard::into_guard].
+/// The layout is stable, cbindgen emits it as
+///
+/// ``` c
+/// typedef struct RawGuard {
+///   uint64_t id;
+///   void *guard;
+/// } RawGuard;
+/// ```
+///
+/// C may read `id`, which is the [id](PassableGuard::id) of the guard, but must treat `guard` as opaque
+///
+/// ``` rust
+/// use passable_guard::{PassableContainer, PassableGuard, RawGuard};
+///
+/// let (guard, ptr) = PassableContainer::new(vec![1u8, 2, 3]).pass();
+/// let raw = RawGuard::new(guard);
+///
+/// // C stores `raw` next to `ptr` and hands both back later
+///
+/// let guard: PassableGuard<Vec<u8>> = unsafe { raw.into_guard() }.unwrap();
+/// let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(buffer, vec![1, 2, 3]);
+/// ```
+///
+/// ### Notes
+/// A [RawGuard] that is never converted back keeps its guard armed forever, its [GuardPolicy] is never applied
+#[repr(C)]
+#[must_use = "the guard is only checked again once the RawGuard is converted back"]
+pub struct RawGuard {
+    id: u64,
+    guard: *mut c_void
+}
+
+impl RawGuard {
+    /// Convert `guard` into an FFI-safe value
+    pub fn new<PAS: Passable + 'static, P: GuardPolicy + 'static>(guard: PassableGuard<PAS, P>) -> Self {
+        let id = guard.id();
+        let guard: Box<dyn Any> = Box::new(guard);
+        Self {
+            id,
+            guard: Box::into_raw(Box::new(guard)) as *mut c_void
+        }
+    }
+
+    /// Get the id of the guard
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Convert this value back into the guard it was created from
+    ///
+    /// ### Errors
+    /// Will return this value again if the guard is not a [PassableGuard] of `PAS` and `P`, it can still be converted into the right type
+    ///
+    /// ### Safety
+    /// This value has to be created by [RawGuard::new] and not converted back before,
+    /// a copy C made of it must not be converted back as well
+    pub unsafe fn into_guard<PAS: Passable + 'static, P: GuardPolicy + 'static>(self) -> Result<PassableGuard<PAS, P>, Self> {
+        let guard = self.guard as *mut Box<dyn Any>;
+        if !(*guard).is::<PassableGuard<PAS, P>>() {
+            return Err(self);
+        }
+        let guard = Box::from_raw(guard)
+            .downcast::<PassableGuard<PAS, P>>()
+            .expect("The type of the guard was just checked");
+        Ok(*guard)
+    }
+}
+
+impl Debug for RawGuard {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawGuard")
+            .field("id", &self.id)
+            .finish()
+    }
+}
@@ -0,0 +1,212 @@
+//! Handing a guard and its pointer over to another thread
+//!
+//! The thread that passes a [PassableContainer](crate::PassableContainer) is often not the one that learns when the FFI is done with it.
+//! A [handoff] is a oneshot channel that moves the guard together with its pointer to the thread receiving the completion,
+//! so the obligation to reconstitute it moves with them
+//!
+//! ``` rust
+//! use passable_guard::{handoff, ConfiguredPolicy, PassableContainer};
+//!
+//! let (sender, receiver) = handoff::<Vec<u8>, ConfiguredPolicy>();
+//!
+//! let completion = std::thread::spawn(move || {
+//!     let (guard, ptr) = receiver.recv().unwrap();
+//!     // The FFI signals completion on this thread
+//!     unsafe { guard.reconstitute(ptr) }.unwrap().into_inner()
+//! });
+//!
+//! let (guard, ptr) = PassableContainer::new(vec![0u8; 64]).pass();
+//! // Start the FFI operation with `ptr` here
+//! sender.send(guard, ptr).unwrap();
+//!
+//! assert_eq!(completion.join().unwrap().len(), 64);
+//! ```
+//!
+//! The [Passable] does not have to be `'static`, so a buffer from the stack can be handed to a scoped thread.
+//! A guard that is dropped there without being reconstituted panics the thread, which fails the whole scope
+//!
+//! ``` rust
+//! use passable_guard::{handoff, ConfiguredPolicy, PassableContainer};
+//!
+//! let mut buffer = [0u8; 64];
+//!
+//! std::thread::scope(|threads| {
+//!     let (sender, receiver) = handoff::<&mut [u8], ConfiguredPolicy>();
+//!     threads.spawn(move || {
+//!         let (guard, ptr) = receiver.recv().unwrap();
+//!         unsafe { guard.reconstitute(ptr) }.unwrap();
+//!     });
+//!
+//!     let (guard, ptr) = PassableContainer::new(&mut buffer[..]).pass();
+//!     sender.send(guard, ptr).unwrap();
+//! });
+//! ```
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+
+use crate::{ConfiguredPolicy, GuardPolicy, LeakInfo, Passable, PassableGuard, ReportPolicy};
+
+/// A guard together with the pointer it was created for
+pub type Handoff<PAS, P> = (PassableGuard<PAS, P>, *mut <PAS as Passable>::Pointee);
+
+/// An Error returned by [HandoffReceiver::recv] if the [HandoffSender] was dropped without sending a guard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HandoffError;
+
+impl Display for HandoffError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the handoff sender was dropped without sending a guard")
+    }
+}
+
+impl Error for HandoffError {}
+
+struct State<PAS: Passable, P: GuardPolicy> {
+    handoff: Option<Handoff<PAS, P>>,
+    closed: bool
+}
+
+/// The state shared by both ends of a [handoff]
+struct Slot<PAS: Passable, P: GuardPolicy> {
+    state: Mutex<State<PAS, P>>,
+    sent: Condvar
+}
+
+impl<PAS: Passable, P: GuardPolicy> Slot<PAS, P> {
+    fn lock(&self) -> MutexGuard<'_, State<PAS, P>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Mark the slot as closed once one end is dropped, waking a receiver waiting for a guard that will never be sent
+    fn close(&self) {
+        self.lock().closed = true;
+        self.sent.notify_all();
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Drop for Slot<PAS, P> {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let Some((guard, ptr)) = state.handoff.take() {
+            let leak = LeakInfo::new::<PAS, PAS::Pointee>("Handoff dropped with a guard that was never received", ptr);
+            ReportPolicy::armed_drop(&leak, || {});
+            // The guard applies its own policy as well, the handoff does not change what happens to the Passable
+            drop(guard);
+        }
+    }
+}
+
+/// The sending end of a [handoff]
+pub struct HandoffSender<PAS: Passable, P: GuardPolicy = ConfiguredPolicy> {
+    slot: Arc<Slot<PAS, P>>
+}
+
+/// The receiving end of a [handoff]
+pub struct HandoffReceiver<PAS: Passable, P: GuardPolicy = ConfiguredPolicy> {
+    slot: Arc<Slot<PAS, P>>
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send,
+// so both ends can be moved to the threads that pass and reconstitute it
+unsafe impl<PAS: Passable + Send, P: GuardPolicy> Send for HandoffSender<PAS, P> {}
+unsafe impl<PAS: Passable + Send, P: GuardPolicy> Send for HandoffReceiver<PAS, P> {}
+
+/// Create a oneshot channel to hand a guard and its pointer over to another thread
+///
+/// ### Panic
+/// If the channel is dropped while it still holds a guard that was never received, it reports the guard,
+/// which is then dropped while still armed and applies its [GuardPolicy]
+pub fn handoff<PAS: Passable, P: GuardPolicy>() -> (HandoffSender<PAS, P>, HandoffReceiver<PAS, P>) {
+    let slot = Arc::new(Slot {
+        state: Mutex::new(State {
+            handoff: None,
+            closed: false
+        }),
+        sent: Condvar::new()
+    });
+    let sender = HandoffSender {
+        slot: slot.clone()
+    };
+    (sender, HandoffReceiver {slot})
+}
+
+impl<PAS: Passable, P: GuardPolicy> HandoffSender<PAS, P> {
+    /// Send the guard and its pointer to the [HandoffReceiver]
+    ///
+    /// ### Errors
+    /// Will return the guard and its pointer if the [HandoffReceiver] has already been dropped
+    pub fn send(self, guard: PassableGuard<PAS, P>, ptr: *mut PAS::Pointee) -> Result<(), Handoff<PAS, P>> {
+        let mut state = self.slot.lock();
+        if state.closed {
+            return Err((guard, ptr));
+        }
+        state.handoff = Some((guard, ptr));
+        drop(state);
+        self.slot.sent.notify_all();
+        Ok(())
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> HandoffReceiver<PAS, P> {
+    /// Block until the guard and its pointer have been sent
+    ///
+    /// ### Errors
+    /// Will return an Error if the [HandoffSender] was dropped without sending a guard
+    pub fn recv(self) -> Result<Handoff<PAS, P>, HandoffError> {
+        let mut state = self.slot.lock();
+        loop {
+            if let Some(handoff) = state.handoff.take() {
+                return Ok(handoff);
+            }
+            if state.closed {
+                return Err(HandoffError);
+            }
+            state = self.slot.sent.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Take the guard and its pointer if they have already been sent, without blocking
+    ///
+    /// ### Errors
+    /// Will return an Error if the [HandoffSender] was dropped without sending a guard
+    pub fn try_recv(&mut self) -> Result<Option<Handoff<PAS, P>>, HandoffError> {
+        let mut state = self.slot.lock();
+        match state.handoff.take() {
+            Some(handoff) => Ok(Some(handoff)),
+            None if state.closed => Err(HandoffError),
+            None => Ok(None)
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Drop for HandoffSender<PAS, P> {
+    fn drop(&mut self) {
+        self.slot.close();
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Drop for HandoffReceiver<PAS, P> {
+    fn drop(&mut self) {
+        self.slot.close();
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Debug for HandoffSender<PAS, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HandoffSender")
+            .field("closed", &self.slot.lock().closed)
+            .finish()
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Debug for HandoffReceiver<PAS, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = self.slot.lock();
+        f.debug_struct("HandoffReceiver")
+            .field("sent", &state.handoff.is_some())
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
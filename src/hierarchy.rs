@@ -0,0 +1,214 @@
+//! Parent/child guard hierarchies
+//!
+//! For composite data, a [ParentGuard] can hand out child guards for the parts of the data
+//! that are passed over the FFI boundary separately.
+//! The parent can only be reconstituted once every child has been reconstituted or explicitly transferred.
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use passable_guard::PassableContainer;
+//!
+//! let (parent, parent_ptr) = PassableContainer::new(CString::new("parent").unwrap()).pass_parent();
+//! let (child, child_ptr) = parent.pass_child(PassableContainer::new(CString::new("child").unwrap()));
+//!
+//! assert_eq!(parent.outstanding_children().len(), 1);
+//!
+//! unsafe {
+//!     child.reconstitute(child_ptr).unwrap();
+//!     parent.reconstitute(parent_ptr).unwrap();
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// Information about a child guard that has not been reconstituted or transferred yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChildInfo {
+    /// The pointer that was passed for the child
+    pub ptr: *mut (),
+    /// The type name of the child [Passable]
+    pub type_name: &'static str
+}
+
+impl Display for ChildInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {:p}", self.type_name, self.ptr)
+    }
+}
+
+#[derive(Debug)]
+struct ChildRecord {
+    info: ChildInfo,
+    outstanding: bool
+}
+
+type Children = Rc<RefCell<Vec<ChildRecord>>>;
+
+fn outstanding(children: &Children) -> Vec<ChildInfo> {
+    children.borrow()
+        .iter()
+        .filter(|record| record.outstanding)
+        .map(|record| record.info.clone())
+        .collect()
+}
+
+/// An Error that can occur while reconstituting a [ParentGuard]
+pub enum ParentReconstituteError<PAS: Passable> {
+    /// Some children are still outstanding, the still armed parent guard is handed back
+    ChildrenOutstanding{guard: ParentGuard<PAS>, children: Vec<ChildInfo>},
+    ReconstituteError{error: ReconstituteError<PAS>}
+}
+
+impl<PAS: Passable + Debug> Debug for ParentReconstituteError<PAS> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParentReconstituteError::ChildrenOutstanding {guard, children} => f.debug_struct("ChildrenOutstanding")
+                .field("guard", guard)
+                .field("children", children)
+                .finish(),
+            ParentReconstituteError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish()
+        }
+    }
+}
+
+/// A guard for composite data whose parts are passed with their own child guards
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted, it will apply its policy just like a [PassableGuard],
+/// listing all outstanding children
+#[derive(Debug)]
+pub struct ParentGuard<PAS: Passable> {
+    guard: Option<PassableGuard<PAS>>,
+    children: Children
+}
+
+impl<PAS: Passable> ParentGuard<PAS> {
+    /// Pass a part of the composite data with a child guard that belongs to this parent
+    #[track_caller]
+    pub fn pass_child<CPAS: Passable>(&self, child: PassableContainer<CPAS>) -> (ChildGuard<CPAS>, *mut CPAS::Pointee) {
+        let (guard, ptr) = child.pass();
+
+        let mut children = self.children.borrow_mut();
+        let index = children.len();
+        children.push(ChildRecord {
+            info: ChildInfo {
+                ptr: ptr as *mut (),
+                type_name: std::any::type_name::<CPAS>()
+            },
+            outstanding: true
+        });
+
+        let child = ChildGuard {
+            guard,
+            children: self.children.clone(),
+            index
+        };
+        (child, ptr)
+    }
+
+    /// Get all children that have not been reconstituted or transferred yet
+    pub fn outstanding_children(&self) -> Vec<ChildInfo> {
+        outstanding(&self.children)
+    }
+
+    /// Reconstitute a raw pointer back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error containing this guard if there are still outstanding children
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(mut self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ParentReconstituteError<PAS>> {
+        let children = self.outstanding_children();
+        if !children.is_empty() {
+            return Err(
+                ParentReconstituteError::ChildrenOutstanding {
+                    guard: self,
+                    children
+                }
+            );
+        }
+
+        self.guard.take()
+            .expect("Parent guard is only taken on reconstitution")
+            .reconstitute(ptr)
+            .map_err(
+                |error|
+                    ParentReconstituteError::ReconstituteError {error}
+            )
+    }
+}
+
+impl<PAS: Passable> Drop for ParentGuard<PAS> {
+    /// This function will apply the policy of the parent guard if it is still armed, listing all outstanding children
+    fn drop(&mut self) {
+        if let Some(guard) = self.guard.as_mut() {
+            if guard.is_armed() {
+                // Apply the policy ourselves, so the leak lists the children
+                guard.armed_drop(outstanding(&self.children));
+            }
+        }
+    }
+}
+
+/// A guard for a part of composite data that belongs to a [ParentGuard]
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted or transferred, it will panic just like a [PassableGuard]
+#[derive(Debug)]
+pub struct ChildGuard<PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    children: Children,
+    index: usize
+}
+
+impl<PAS: Passable> ChildGuard<PAS> {
+    fn resolve(&self) {
+        self.children.borrow_mut()[self.index].outstanding = false;
+    }
+
+    /// Reconstitute a raw pointer back into a [PassableContainer] and resolve this child in its parent
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute],
+    /// in which case the child stays outstanding in its parent
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        let result = self.guard.reconstitute(ptr);
+        if result.is_ok() {
+            self.children.borrow_mut()[self.index].outstanding = false;
+        }
+        result
+    }
+
+    /// Detach this child from its parent and get the plain [PassableGuard]
+    ///
+    /// The obligation to reconstitute the child is transferred to the returned guard,
+    /// so the parent no longer waits for it
+    pub fn transfer(self) -> PassableGuard<PAS> {
+        self.resolve();
+        self.guard
+    }
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer and a [ParentGuard] that can hand out child guards
+    #[track_caller]
+    pub fn pass_parent(self) -> (ParentGuard<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass();
+        let parent = ParentGuard {
+            guard: Some(guard),
+            children: Default::default()
+        };
+        (parent, ptr)
+    }
+}
@@ -0,0 +1,251 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::ffi::CString;
+use std::fmt::{Display, Formatter};
+use std::mem::ManuallyDrop;
+use std::os::raw::c_char;
+use std::string::FromUtf8Error;
+
+use crate::{Passable, PassableLen};
+
+/// An Error returned when reconstituting a [CString] whose NUL terminator is no longer at the end of the passed string
+///
+/// Like a [FromUtf8Error], it holds the passed bytes including the former terminator,
+/// which are freed with the error unless they are taken with [NulTerminatorError::into_bytes]
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{PassableContainer, ReconstituteError};
+///
+/// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+///
+/// // The FFI overwrites the terminator
+/// unsafe { *ptr.add(4) = b'!' };
+///
+/// match unsafe { guard.reconstitute(ptr) } {
+///     Err(ReconstituteError::ReconstituteError {error}) => assert_eq!(error.into_bytes(), b"name!"),
+///     _ => unreachable!()
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NulTerminatorError {
+    /// The length of the passed string without its terminator
+    pub len: usize,
+    /// The offset of the first NUL byte in the passed string, if there still is one
+    pub found: Option<usize>,
+    bytes: Box<[u8]>
+}
+
+impl NulTerminatorError {
+    /// Get the passed bytes, including the byte where the terminator used to be
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Take the passed bytes, including the byte where the terminator used to be
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes.into_vec()
+    }
+}
+
+impl Display for NulTerminatorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.found {
+            Some(found) => write!(f, "the NUL terminator of a string of length {} was found at offset {}", self.len, found),
+            None => write!(f, "the NUL terminator of a string of length {} was removed", self.len)
+        }
+    }
+}
+
+impl Error for NulTerminatorError {}
+
+impl Passable for CString {
+    type Pointee = u8;
+    type ReconstituteError = NulTerminatorError;
+    /// The length of the string without its terminator
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        let len = self.as_bytes().len();
+        (self.into_raw().cast::<u8>(), len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        // Only look at the passed bytes, searching for a removed terminator would read past the allocation
+        let found = std::slice::from_raw_parts(ptr, metadata + 1)
+            .iter()
+            .position(|byte| *byte == 0);
+        if found != Some(metadata) {
+            // The allocation of a CString holds exactly the string and its terminator
+            let bytes = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, metadata + 1));
+            return Err(NulTerminatorError {len: metadata, found, bytes});
+        }
+        Ok(CString::from_raw(ptr as *mut c_char))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata + 1)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for CString {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata + 1
+    }
+}
+
+/// The length and capacity of a passed [Vec] or [String]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VecParts {
+    pub len: usize,
+    pub capacity: usize
+}
+
+impl<T> Passable for Vec<T> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    type Metadata = VecParts;
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        let mut vec = ManuallyDrop::new(self);
+        let parts = VecParts {
+            len: vec.len(),
+            capacity: vec.capacity()
+        };
+        (vec.as_mut_ptr(), parts)
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(Vec::from_raw_parts(ptr, metadata.len, metadata.capacity))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.capacity * std::mem::size_of::<T>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<T> PassableLen for Vec<T> {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
+
+impl Passable for String {
+    type Pointee = u8;
+    /// The FFI wrote invalid utf-8 into the buffer, the bytes can be recovered from the error
+    type ReconstituteError = FromUtf8Error;
+    type Metadata = VecParts;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        self.into_bytes().pass()
+    }
+
+    unsafe fn reconstitute(ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        let bytes = Vec::from_raw_parts(ptr, metadata.len, metadata.capacity);
+        String::from_utf8(bytes)
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.capacity)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for String {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
+
+impl<T> Passable for Box<T> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        (Box::into_raw(self), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(Box::from_raw(ptr))
+    }
+
+    fn passed_size(_metadata: &Self::Metadata) -> Option<usize> {
+        Some(std::mem::size_of::<T>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<T> Passable for Box<[T]> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    /// The number of elements in the slice
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        let len = self.len();
+        (Box::into_raw(self) as *mut T, len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, metadata)))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata * std::mem::size_of::<T>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<T> PassableLen for Box<[T]> {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}
+
+/// A slice borrowed for as long as it is passed, for buffers that live on the stack of the caller
+impl<T> Passable for &mut [T] {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    /// The number of elements in the slice
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        (self.as_mut_ptr(), self.len())
+    }
+
+    unsafe fn reconstitute(ptr: *mut T, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(std::slice::from_raw_parts_mut(ptr, metadata))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata * std::mem::size_of::<T>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<T> PassableLen for &mut [T] {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}
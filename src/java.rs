@@ -0,0 +1,142 @@
+//! [Passable] implementations for the [jni](https://docs.rs/jni) crate
+//!
+//! They cover the usual ownership hazards of JNI code:
+//! - a [GlobalRef] kept by native code as a `jlong` handle, which pins the Java object until it is handed back
+//! - [AutoElements] whose pointer is handed to native code, which must not outlive the release of the elements
+//! - a local [JObject] handed to native code as a raw `jobject`, which must come back to be deleted
+//!
+//! ``` rust
+//! use jni::JNIEnv;
+//! use jni::objects::{GlobalRef, JByteArray, JObject, ReleaseMode};
+//! use jni::sys::jlong;
+//! use passable_guard::{PassableContainer, PassableGuard};
+//!
+//! // Keep a Java callback alive in native state, the guard panics if the handle is forgotten
+//! fn store_callback(env: &mut JNIEnv, callback: JObject) -> jni::errors::Result<(PassableGuard<GlobalRef>, jlong)> {
+//!     let global = env.new_global_ref(callback)?;
+//!     let (guard, ptr) = PassableContainer::new(global).pass();
+//!     Ok((guard, ptr as jlong))
+//! }
+//!
+//! fn checksum(ptr: *mut i8, len: usize) -> i64 {
+//!     unsafe { std::slice::from_raw_parts(ptr, len) }.iter().map(|byte| *byte as i64).sum()
+//! }
+//!
+//! // The elements can only be released once the native code handed their pointer back
+//! fn array_checksum(env: &mut JNIEnv, array: &JByteArray) -> jni::errors::Result<i64> {
+//!     let elements = unsafe { env.get_array_elements(array, ReleaseMode::NoCopyBack)? };
+//!     let (guard, ptr, len) = PassableContainer::new(elements).pass_with_len();
+//!
+//!     let sum = checksum(ptr, len);
+//!
+//!     drop(unsafe { guard.reconstitute(ptr) }.unwrap());
+//!     Ok(sum)
+//! }
+//! ```
+
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+
+use jni::objects::{AutoElements, GlobalRef, JObject, TypeArray};
+use jni::sys::_jobject;
+
+use crate::{Passable, PassableLen};
+
+/// A local reference passed as a raw `jobject`
+///
+/// The reconstituted reference should be deleted with `JNIEnv::delete_local_ref`
+/// if the native code outlives the current JNI frame
+impl<'local> Passable for JObject<'local> {
+    type Pointee = _jobject;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut _jobject, Self::Metadata) {
+        (self.into_raw(), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut _jobject, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(JObject::from_raw(ptr))
+    }
+}
+
+/// A global reference passed as a pointer to a heap allocated [GlobalRef], which fits into a `jlong` handle
+///
+/// The Java object stays pinned until the pointer is reconstituted and the [GlobalRef] is dropped
+impl Passable for GlobalRef {
+    type Pointee = GlobalRef;
+    type ReconstituteError = Infallible;
+    type Metadata = ();
+
+    fn pass(self) -> (*mut GlobalRef, Self::Metadata) {
+        (Box::into_raw(Box::new(self)), ())
+    }
+
+    unsafe fn reconstitute(ptr: *mut GlobalRef, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(*Box::from_raw(ptr))
+    }
+
+    fn passed_size(_metadata: &Self::Metadata) -> Option<usize> {
+        Some(std::mem::size_of::<GlobalRef>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+/// The passed [AutoElements] of a Java primitive array, kept until their pointer is reconstituted
+pub struct PassedElements<'local, 'other_local, 'array, T: TypeArray> {
+    elements: *mut AutoElements<'local, 'other_local, 'array, T>,
+    len: usize
+}
+
+impl<'local, 'other_local, 'array, T: TypeArray> Clone for PassedElements<'local, 'other_local, 'array, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'local, 'other_local, 'array, T: TypeArray> Copy for PassedElements<'local, 'other_local, 'array, T> {}
+
+impl<'local, 'other_local, 'array, T: TypeArray> Debug for PassedElements<'local, 'other_local, 'array, T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassedElements")
+            .field("elements", &self.elements)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// The elements of a Java primitive array passed as a pointer to the first element
+///
+/// The elements are released, according to their release mode, when the reconstituted [AutoElements] are dropped
+impl<'local, 'other_local, 'array, T: TypeArray> Passable for AutoElements<'local, 'other_local, 'array, T> {
+    type Pointee = T;
+    type ReconstituteError = Infallible;
+    type Metadata = PassedElements<'local, 'other_local, 'array, T>;
+
+    fn pass(self) -> (*mut T, Self::Metadata) {
+        let ptr = self.as_ptr();
+        let len = self.len();
+        (ptr, PassedElements {elements: Box::into_raw(Box::new(self)), len})
+    }
+
+    unsafe fn reconstitute(_ptr: *mut T, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(*Box::from_raw(metadata.elements))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.len * std::mem::size_of::<T>())
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<'local, 'other_local, 'array, T: TypeArray> PassableLen for AutoElements<'local, 'other_local, 'array, T> {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
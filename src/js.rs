@@ -0,0 +1,217 @@
+//! Passing guarded buffers to JavaScript through [wasm-bindgen](https://docs.rs/wasm-bindgen)
+//!
+//! [pass_to_js] hands JS the offset and length of a buffer in linear memory and keeps its guard,
+//! until JS calls back into an export that hands the offset to [release_from_js].
+//! [assert_js_released] then turns buffers JS forgot to release, which would otherwise silently stay on the wasm heap, into a panic.
+//!
+//! ``` rust
+//! use passable_guard::{JsBuffer, PassableContainer};
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[wasm_bindgen]
+//! pub fn create_buffer() -> JsBuffer {
+//!     // JS views it with `new Uint8Array(memory.buffer, buffer.ptr, buffer.len)`
+//!     passable_guard::pass_to_js(PassableContainer::new(vec![0u8; 64]))
+//! }
+//!
+//! #[wasm_bindgen]
+//! pub fn release_buffer(ptr: usize) {
+//!     let buffer = passable_guard::release_from_js::<Vec<u8>>(ptr).unwrap().into_inner();
+//!     assert_eq!(buffer.len(), 64);
+//! }
+//!
+//! let buffer = create_buffer();
+//! release_buffer(buffer.ptr());
+//! passable_guard::assert_js_released();
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+struct Passed {
+    guard: Box<dyn Any>,
+    id: u64,
+    type_name: &'static str,
+    label: Option<&'static str>
+}
+
+thread_local! {
+    // Several buffers can start at the same offset, like empty Vecs sharing a dangling pointer
+    static PASSED: RefCell<BTreeMap<usize, Vec<Passed>>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// A buffer passed to JS with [pass_to_js], as an offset into the linear memory of the module
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsBuffer {
+    ptr: usize,
+    len: usize
+}
+
+#[wasm_bindgen]
+impl JsBuffer {
+    /// The offset of the buffer in linear memory, which has to be handed back to [release_from_js]
+    #[wasm_bindgen(getter)]
+    pub fn ptr(&self) -> usize {
+        self.ptr
+    }
+
+    /// The number of elements in the buffer
+    #[wasm_bindgen(getter)]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the buffer contains no elements
+    #[wasm_bindgen(getter, js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// An Error that can occur while releasing a buffer with [release_from_js]
+pub enum JsReleaseError<PAS: Passable> {
+    /// No buffer passed to JS on this thread starts at the offset
+    UnknownPointer{ptr: usize},
+    /// The buffer at the offset was passed as a different [Passable], it stays passed
+    WrongType{ptr: usize, type_name: &'static str},
+    ReconstituteError{error: ReconstituteError<PAS>}
+}
+
+impl<PAS: Passable> Debug for JsReleaseError<PAS> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsReleaseError::UnknownPointer {ptr} => f.debug_struct("UnknownPointer")
+                .field("ptr", ptr)
+                .finish(),
+            JsReleaseError::WrongType {ptr, type_name} => f.debug_struct("WrongType")
+                .field("ptr", ptr)
+                .field("type_name", type_name)
+                .finish(),
+            JsReleaseError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish()
+        }
+    }
+}
+
+impl<PAS: Passable> Display for JsReleaseError<PAS> where PAS::ReconstituteError: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JsReleaseError::UnknownPointer {ptr} =>
+                write!(f, "no buffer was passed to JS at {:#x}", ptr),
+            JsReleaseError::WrongType {ptr, type_name} =>
+                write!(f, "the buffer at {:#x} was passed as {}, not as {}", ptr, type_name, std::any::type_name::<PAS>()),
+            JsReleaseError::ReconstituteError {error} =>
+                write!(f, "could not release the buffer: {}", error)
+        }
+    }
+}
+
+impl<PAS: Passable + 'static> Error for JsReleaseError<PAS> where PAS::ReconstituteError: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            JsReleaseError::ReconstituteError {error} => Some(error),
+            _ => None
+        }
+    }
+}
+
+/// Pass the buffer of `container` to JS, keeping its guard until JS releases it through [release_from_js]
+#[track_caller]
+pub fn pass_to_js<PAS: PassableLen + 'static>(container: PassableContainer<PAS>) -> JsBuffer {
+    let (guard, ptr, len) = container.pass_with_len();
+    // JS accesses the buffer through its offset, so its provenance has to be exposed
+    let ptr = ptr.expose_provenance();
+
+    let passed = Passed {
+        id: guard.id(),
+        type_name: std::any::type_name::<PAS>(),
+        label: guard.label(),
+        guard: Box::new(guard)
+    };
+    PASSED.with(|table| table.borrow_mut().entry(ptr).or_default().push(passed));
+
+    JsBuffer {
+        ptr,
+        len
+    }
+}
+
+/// Reconstitute the buffer JS handed back the offset of, which was passed with [pass_to_js] on the same thread
+///
+/// If several buffers of the same [Passable] were passed at the offset, like empty [Vec]s, the one passed first is released
+///
+/// ``` rust
+/// use passable_guard::PassableContainer;
+///
+/// // Empty Vecs share the same dangling pointer
+/// let first = passable_guard::pass_to_js(PassableContainer::new(Vec::<u8>::new()));
+/// let second = passable_guard::pass_to_js(PassableContainer::new(Vec::<u8>::new()));
+/// assert_eq!(passable_guard::outstanding_js_buffers(), 2);
+///
+/// passable_guard::release_from_js::<Vec<u8>>(first.ptr()).unwrap();
+/// passable_guard::release_from_js::<Vec<u8>>(second.ptr()).unwrap();
+/// passable_guard::assert_js_released();
+/// ```
+///
+/// ### Errors
+/// Will return an Error if no buffer was passed at the offset or it was passed as a different [Passable]
+/// Will return an Error if the memory was modified by JS
+pub fn release_from_js<PAS: Passable + 'static>(ptr: usize) -> Result<PassableContainer<PAS>, JsReleaseError<PAS>> {
+    let passed = PASSED.with(|table| {
+        let mut table = table.borrow_mut();
+        let entries = table.get_mut(&ptr).ok_or(JsReleaseError::UnknownPointer {ptr})?;
+        let index = entries.iter()
+            .position(|passed| passed.guard.is::<PassableGuard<PAS>>())
+            .ok_or(JsReleaseError::WrongType {ptr, type_name: entries[0].type_name})?;
+        let passed = entries.remove(index);
+        if entries.is_empty() {
+            table.remove(&ptr);
+        }
+        Ok(passed)
+    })?;
+
+    let guard = passed.guard
+        .downcast::<PassableGuard<PAS>>()
+        .expect("The type of the buffer was just checked");
+    // Reconstitute from the pointer the guard kept instead of the offset,
+    // an integer cast back into a pointer loses its provenance on targets like CHERI
+    let ptr = guard.repass();
+    unsafe { guard.reconstitute(ptr) }
+        .map_err(|error| JsReleaseError::ReconstituteError {error})
+}
+
+/// Get the number of buffers passed to JS on this thread that have not been released yet
+pub fn outstanding_js_buffers() -> usize {
+    PASSED.with(|table| table.borrow().values().map(Vec::len).sum())
+}
+
+/// Panic if any buffer passed to JS on this thread has not been released yet
+///
+/// ### Panic
+/// Panics with a list of the buffers JS still holds
+#[track_caller]
+pub fn assert_js_released() {
+    let outstanding = PASSED.with(|table| {
+        table.borrow()
+            .iter()
+            .flat_map(|(ptr, entries)| entries.iter().map(move |passed| (ptr, passed)))
+            .map(|(ptr, passed)| match passed.label {
+                Some(label) => format!("#{} {}: {} at {:#x}", passed.id, label, passed.type_name, ptr),
+                None => format!("#{} {} at {:#x}", passed.id, passed.type_name, ptr)
+            })
+            .collect::<Vec<_>>()
+    });
+
+    if !outstanding.is_empty() {
+        panic!("{} buffers passed to JS were never released: {}", outstanding.len(), outstanding.join(", "));
+    }
+}
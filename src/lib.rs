@@ -88,136 +88,1118 @@
 //!     // Without the Guard, we would have now subtly leaked the String Memory
 //! }
 //! ```
+//!
+//! ## WebAssembly
+//!
+//! On `wasm32` targets a pointer is an offset into the linear memory of the module,
+//! so it can be handed to a host function as a plain 32 bit `usize` while the guard keeps watching it.
+//! The registry, [stats] and [reporters](Reporter) work without threads.
+//! On `wasm32-unknown-unknown`, which has no clock, guards are not timed,
+//! `report_at_exit` never fires and the [watchdog](enable_watchdog) has to be driven with [check_watchdog].
+//!
+//! ``` rust
+//! use passable_guard::PassableContainer;
+//!
+//! // Stands in for a function imported from the host, which writes `len` bytes at `offset`
+//! fn host_fill(offset: usize, len: usize) {
+//!     unsafe { std::ptr::write_bytes(std::ptr::with_exposed_provenance_mut::<u8>(offset), 0xff, len) }
+//! }
+//!
+//! let (guard, ptr) = PassableContainer::new(vec![0u8; 16]).pass();
+//!
+//! // The host accesses the buffer through its offset, so its provenance has to be exposed
+//! host_fill(ptr.expose_provenance(), 16);
+//!
+//! let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+//! assert_eq!(buffer, vec![0xff; 16]);
+//! ```
+//!
+//! ## Constrained targets
+//!
+//! Pointers are never converted to integers to track them, so guards work with pointers wider than `usize` like on CHERI.
+//! Ids and counters fall back to a lock on targets without 64 bit atomics, like 32 bit MIPS and PowerPC.
+//! The crate requires `std`, so targets without it are not supported.
+//! For small targets, disable the default `diagnostics` feature: guards then only check pointers and apply their [GuardPolicy],
+//! without the registry, stats, events, reporters or watchdog, and without reading the clock.
+//! [ReportPolicy] prints its reports to stderr in that case.
+//!
+//! ``` toml
+//! [dependencies]
+//! passable_guard = { version = "1", default-features = false }
+//! ```
+//!
+//! ## Miri
+//!
+//! The crate runs under Miri, so FFI test doubles can be checked with the guards in the loop.
+//! Under Miri, a [GuardPageBuffer] is allocated without a guard page and ends at the end of its allocation instead,
+//! Valgrind client requests do nothing and `report_at_exit` never fires.
+//! Guards only compare addresses and reconstitute from the pointer they kept, so `-Zmiri-strict-provenance` holds
+//! unless addresses are handed to another language as integers.
+//! Policies like [ReportPolicy] leak on purpose, so run Miri with `-Zmiri-ignore-leaks`:
+//!
+//! ``` sh
+//! MIRIFLAGS="-Zmiri-ignore-leaks" cargo +nightly miri test
+//! ```
+//!
+//! ## AddressSanitizer
+//!
+//! With the `asan` feature, a buffer is poisoned from the moment the FFI hands it back through [PassableGuard::mark_returned]
+//! until the `Returned` token is reconstituted, and stays poisoned if the token is dropped instead.
+//! Any access through a pointer stashed by the FFI or by Rust in between is then reported by ASan right where it happens.
+//! The sanitizer is only called in builds with `-Zsanitizer=address`:
+//!
+//! ``` sh
+//! RUSTFLAGS="-Zsanitizer=address" cargo +nightly test --features asan --target x86_64-unknown-linux-gnu
+//! ```
+//!
+//! Buffers that are reconstituted and freed need no poisoning, ASan reports a use after free for them on its own.
+//! Lent and leaked buffers are not poisoned, since the FFI may still use them legitimately.
+//! Only the [extent](Passable::passed_extent) of a buffer is poisoned, types that do not know it are left alone.
+//!
+//! ## Features
+//!
+//! - `asan`: Poison buffers the FFI has returned but Rust has not reconstituted yet, see [AddressSanitizer](#addresssanitizer)
+//! - `c-api`: Export `passable_guard_release`, so C can signal when it is done with a buffer passed with a `GuardHandle`, and carry guards as a `RawGuard`
+//! - `core-foundation`: Owned CFString and CFData references on Apple targets, whose retain is tracked by the guard like the pointer
+//! - `cuda`: Guard device memory from `cudaMalloc` lent to kernel-launching APIs, freed with `cudaFree` by the free policy
+//! - `cxx`: Pass the [cxx](https://docs.rs/cxx) `UniquePtr`s of `std::string`, `std::vector` and other C++ objects as raw pointers
+//! - `diagnostics` (default): The registry, [stats], lifecycle events, reporters and the watchdog, required by `log`, `metrics` and `tracing`
+//! - `derive`: `#[derive(Passable)]` for structs of passable fields, passed as a `#[repr(C)]` struct of raw pointers,
+//!   `#[wrap]` to generate safe wrappers taking [Passable]s for the functions of an `extern "C"` block,
+//!   returning a [CallbackFuture] for functions that report back through a callback,
+//!   and `#[guarded]` to pass parameters of a function for its whole body
+//! - `futures`: A `BufferPipe` that is a `Sink` passing buffers to a FFI that consumes them asynchronously,
+//!   and a `Stream` of the consumed buffers, with a bounded number of buffers in flight
+//! - `glib-sys`: Strings and buffers allocated with `g_strdup` and `g_malloc0`, for GLib and GTK APIs that free them with `g_free`
+//! - `jni`: Implement [Passable] for global and local references and array elements of the [jni](https://docs.rs/jni) crate
+//! - `libloading`: Call functions of dynamically loaded libraries with scoped passing, see `GuardedSymbol`
+//! - `log`: Log the same diagnostics through the [log](https://docs.rs/log) macros, at levels set with `set_log_levels`
+//! - `memmap2`: Implement [Passable] for the memory maps of [memmap2](https://docs.rs/memmap2), which are unmapped instead of deallocated
+//! - `metrics`: Record counters and an outstanding guard gauge through the [metrics](https://docs.rs/metrics) facade
+//! - `napi`: Hand guarded buffers to Node as external `ArrayBuffer`s through [napi](https://docs.rs/napi), reconstituted by their finalizer
+//! - `pyo3`: Lend guarded buffers to Python through [PyO3](https://docs.rs/pyo3), for ctypes or the buffer protocol, see `lend_to_python`
+//! - `rustler`: Lend guarded buffers to the BEAM as resource binaries from [Rustler](https://docs.rs/rustler) NIFs, see `lend_to_beam`
+//! - `serde`: Implement `Serialize` for [Stats], [LeakInfo], [ChildInfo] and [registry::GuardRecord]
+//! - `tokio`: `guarded_blocking_call` to run a blocking FFI call with a passed container on the blocking pool of [tokio](https://docs.rs/tokio),
+//!   and the task of every guard in the [registry]
+//! - `tracing`: Emit [tracing](https://docs.rs/tracing) events when a guard is passed, reconstituted, mismatched or leaked
+//! - `valgrind`: Describe passed buffers with Memcheck client requests, so Valgrind reports name their label and pass site
+//! - `wasm-bindgen`: Pass guarded buffers to JS as offsets into linear memory and check that JS released them, see `pass_to_js`
 
+#[cfg(feature = "diagnostics")]
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
-use std::ffi::CString;
+use std::ops::Deref;
+#[cfg(any(feature = "diagnostics", feature = "valgrind"))]
+use std::panic::Location;
+#[cfg(feature = "diagnostics")]
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU64;
+
+#[cfg(feature = "asan")]
+mod asan;
+mod atomic;
+#[cfg(feature = "tokio")]
+mod blocking;
+mod borrowed;
+mod branded;
+#[cfg(windows)]
+mod bstr;
+mod buffer;
+mod builder;
+mod call;
+mod callback;
+mod cancel;
+#[cfg(all(feature = "core-foundation", target_vendor = "apple"))]
+mod cf;
+mod checksum;
+#[cfg(windows)]
+mod com;
+mod completion;
+mod config;
+#[cfg(feature = "cxx")]
+mod cpp;
+#[cfg(feature = "cuda")]
+mod device;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+#[cfg(feature = "libloading")]
+mod dynamic;
+#[cfg(feature = "diagnostics")]
+mod events;
+mod ext;
+mod foreign;
+mod free;
+#[cfg(feature = "glib-sys")]
+mod glib;
+#[cfg(feature = "c-api")]
+mod handle;
+mod handoff;
+mod hierarchy;
+mod impls;
+#[cfg(feature = "jni")]
+mod java;
+#[cfg(feature = "wasm-bindgen")]
+mod js;
+#[cfg(feature = "memmap2")]
+mod mmap;
+mod moved;
+mod newtype;
+#[cfg(feature = "rustler")]
+mod nif;
+#[cfg(feature = "napi")]
+mod node;
+mod out;
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple", target_os = "freebsd", windows))]
+mod page;
+mod parts;
+mod passed;
+mod poison;
+mod policy;
+#[cfg(feature = "pyo3")]
+mod python;
+mod queue;
+#[cfg(feature = "diagnostics")]
+pub mod registry;
+#[cfg(feature = "diagnostics")]
+mod reporter;
+mod retry;
+mod returned;
+mod scope;
+mod scoped;
+#[cfg(feature = "serde")]
+mod serialize;
+mod set;
+mod shadow;
+mod shared;
+mod shutdown;
+mod split;
+#[cfg(feature = "diagnostics")]
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+mod tag;
+#[cfg(feature = "valgrind")]
+mod valgrind;
+#[cfg(feature = "diagnostics")]
+mod watchdog;
+#[cfg(windows)]
+mod winalloc;
+
+#[cfg(feature = "tokio")]
+pub use blocking::guarded_blocking_call;
+pub use borrowed::BorrowedGuard;
+pub use branded::{BrandedGuard, BrandedPtr};
+#[cfg(windows)]
+pub use bstr::Bstr;
+pub use buffer::ByteBuffer;
+pub use builder::ContainerBuilder;
+pub use call::CallError;
+pub use callback::{CallbackFuture, Completer, GuardCompletion, GuardFuture};
+pub use cancel::{deferred_count, free_deferred, CancelFallback, CancelGuard};
+#[cfg(all(feature = "core-foundation", target_vendor = "apple"))]
+pub use cf::{CfData, CfRetained, CfString, CfType};
+pub use checksum::ChecksumGuard;
+#[cfg(windows)]
+pub use com::ComPtr;
+pub use completion::CompletionGuard;
+pub use config::{configure, Config};
+#[cfg(feature = "cxx")]
+pub use cpp::cxx_vector;
+#[cfg(feature = "cuda")]
+pub use device::{Cuda, CudaError, DeviceAllocator, DeviceBuffer};
+#[cfg(feature = "log")]
+pub use diagnostics::{set_log_levels, LogLevels};
+#[cfg(feature = "libloading")]
+pub use dynamic::GuardedSymbol;
+#[cfg(feature = "diagnostics")]
+pub use events::{clear_event_hook, set_event_hook, GuardEvent, GuardInfo};
+pub use ext::PassableExt;
+pub use foreign::ForeignContainer;
+pub use free::FreeGuard;
+#[cfg(feature = "glib-sys")]
+pub use glib::{GlibBuffer, GlibString};
+#[cfg(feature = "c-api")]
+pub use handle::{passable_guard_release, GuardHandle, RawGuard, ReleaseStatus};
+pub use handoff::{handoff, Handoff, HandoffError, HandoffReceiver, HandoffSender};
+pub use hierarchy::{ChildGuard, ChildInfo, ParentGuard, ParentReconstituteError};
+pub use impls::{NulTerminatorError, VecParts};
+#[cfg(feature = "jni")]
+pub use java::PassedElements;
+#[cfg(feature = "wasm-bindgen")]
+pub use js::{assert_js_released, outstanding_js_buffers, pass_to_js, release_from_js, JsBuffer, JsReleaseError};
+#[cfg(feature = "memmap2")]
+pub use mmap::PassedMap;
+pub use moved::Relocatable;
+#[cfg(feature = "rustler")]
+pub use nif::{lend_to_beam, BeamBuffer, PassedBinary};
+#[cfg(feature = "napi")]
+pub use node::pass_to_arraybuffer;
+pub use out::{AdoptError, OutPtr};
+#[cfg(any(target_os = "linux", target_os = "android", target_vendor = "apple", target_os = "freebsd", windows))]
+pub use page::GuardPageBuffer;
+pub use passed::PassedPtr;
+pub use poison::{Poisonable, Poisoned, POISON};
+pub use policy::{AbortPolicy, ConfiguredPolicy, DropPolicy, FreePolicy, GuardPolicy, LeakInfo, LeakPolicy, PanicPolicy, ReportPolicy};
+#[cfg(feature = "pyo3")]
+pub use python::{lend_to_python, PyLentBuffer, PyLoan, PyLoanError};
+pub use queue::ReturnQueue;
+#[cfg(feature = "diagnostics")]
+pub use registry::install_panic_hook;
+#[cfg(feature = "log")]
+pub use reporter::LogReporter;
+#[cfg(feature = "diagnostics")]
+pub use reporter::{add_reporter, clear_reporters, report_stats, Reporter, StderrReporter};
+pub use retry::RetryError;
+pub use returned::Returned;
+pub use scope::{PassScope, ScopeFailure};
+pub use scoped::{PassWithFuture, TryPassError};
+pub use set::{GuardSet, PassAll};
+pub use shadow::ShadowGuard;
+pub use shared::{SharedContainer, SharedError, SharedGuard};
+pub use shutdown::{begin_shutdown, is_shutting_down, ShutdownScope};
+pub use split::{PassParts, SplitGuard, Splittable};
+#[cfg(feature = "diagnostics")]
+pub use stats::{stats, Stats, HOLD_HISTOGRAM_BOUNDS};
+#[cfg(feature = "futures")]
+pub use stream::BufferPipe;
+pub use tag::TypeTagError;
+#[cfg(feature = "diagnostics")]
+pub use watchdog::{check_watchdog, enable_watchdog};
+#[cfg(feature = "derive")]
+pub use passable_guard_derive::{guarded, wrap, Passable};
+#[cfg(windows)]
+pub use winalloc::{CoTaskMem, Global, Local, WinBuffer, WindowsAllocator};
 
 /// An Error that can occur while reconstituting a [Passable] from a pointer
-#[derive(Debug, Clone)]
-pub enum ReconstituteError<PTR, PAS: Passable<PTR>> {
-    PointerMismatch{passed: *mut PTR, reconstituted: *mut PTR},
-    ReconstituteError{error: PAS::ReconstituteError}
+///
+/// It implements [Error] if the error of the [Passable] does, so it can be propagated with `?`
+///
+/// ``` rust
+/// use std::error::Error;
+/// use std::ffi::CString;
+/// use passable_guard::PassableContainer;
+///
+/// fn round_trip(name: CString) -> Result<CString, Box<dyn Error + Send + Sync>> {
+///     let (guard, ptr) = PassableContainer::new(name).pass();
+///     let container = unsafe { guard.reconstitute(ptr) }?;
+///     Ok(container.into_inner())
+/// }
+///
+/// round_trip(CString::new("name").unwrap()).unwrap();
+/// ```
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum ReconstituteError<PAS: Passable> {
+    PointerMismatch{passed: *mut PAS::Pointee, reconstituted: *mut PAS::Pointee},
+    ReconstituteError{error: PAS::ReconstituteError},
+    /// The checksum of the buffer taken when it was passed no longer matches, see [ChecksumGuard]
+    ContentModified{expected: u64, actual: u64},
+    /// The length and capacity handed back by the FFI do not fit the passed buffer, see [PassableGuard::reconstitute_raw_parts]
+    InvalidParts{len: usize, capacity: usize, passed_capacity: usize},
+    /// The pointer belongs to a different armed guard in the [registry], see [PointerSwap](registry::PointerSwap)
+    ///
+    /// The guard is dropped with this error and applies its policy, whose [LeakInfo] names the owning guard
+    #[cfg(feature = "diagnostics")]
+    PointerSwapped{passed: *mut PAS::Pointee, reconstituted: *mut PAS::Pointee, swap: Box<registry::PointerSwap>}
+}
+
+// The pointers are only kept for diagnostics and are never dereferenced
+unsafe impl<PAS: Passable> Send for ReconstituteError<PAS> where PAS::ReconstituteError: Send {}
+unsafe impl<PAS: Passable> Sync for ReconstituteError<PAS> where PAS::ReconstituteError: Sync {}
+
+impl<PAS: Passable> Debug for ReconstituteError<PAS> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReconstituteError::PointerMismatch {passed, reconstituted} => f.debug_struct("PointerMismatch")
+                .field("passed", passed)
+                .field("reconstituted", reconstituted)
+                .finish(),
+            ReconstituteError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish(),
+            ReconstituteError::ContentModified {expected, actual} => f.debug_struct("ContentModified")
+                .field("expected", expected)
+                .field("actual", actual)
+                .finish(),
+            ReconstituteError::InvalidParts {len, capacity, passed_capacity} => f.debug_struct("InvalidParts")
+                .field("len", len)
+                .field("capacity", capacity)
+                .field("passed_capacity", passed_capacity)
+                .finish(),
+            #[cfg(feature = "diagnostics")]
+            ReconstituteError::PointerSwapped {passed, reconstituted, swap} => f.debug_struct("PointerSwapped")
+                .field("passed", passed)
+                .field("reconstituted", reconstituted)
+                .field("swap", swap)
+                .finish()
+        }
+    }
+}
+
+impl<PAS: Passable> Display for ReconstituteError<PAS> where PAS::ReconstituteError: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReconstituteError::PointerMismatch {passed, reconstituted} =>
+                write!(f, "pointer mismatch, passed {:p} but reconstituted {:p}", passed, reconstituted),
+            ReconstituteError::ReconstituteError {error} =>
+                write!(f, "could not reconstitute the passable: {}", error),
+            ReconstituteError::ContentModified {expected, actual} =>
+                write!(f, "content modified, checksum was {:#018x} but is {:#018x}", expected, actual),
+            ReconstituteError::InvalidParts {len, capacity, passed_capacity} =>
+                write!(f, "invalid parts, length {} and capacity {} for a buffer passed with capacity {}", len, capacity, passed_capacity),
+            #[cfg(feature = "diagnostics")]
+            ReconstituteError::PointerSwapped {passed, reconstituted, swap} =>
+                write!(f, "pointer swapped, passed {:p} but reconstituted {:p}, {}", passed, reconstituted, swap)
+        }
+    }
+}
+
+impl<PAS: Passable> Error for ReconstituteError<PAS> where PAS::ReconstituteError: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReconstituteError::PointerMismatch {..} | ReconstituteError::ContentModified {..} | ReconstituteError::InvalidParts {..} => None,
+            #[cfg(feature = "diagnostics")]
+            ReconstituteError::PointerSwapped {..} => None,
+            ReconstituteError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+/// Options of a [PassableContainer] that are carried over to its guard and back
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ContainerOptions {
+    pub(crate) label: Option<&'static str>,
+    pub(crate) policy: Option<DropPolicy>
+}
+
+impl ContainerOptions {
+    pub(crate) const fn new() -> Self {
+        Self {
+            label: None,
+            policy: None
+        }
+    }
+
+    /// Describe a guard with these options for its [GuardPolicy]
+    pub(crate) fn leak_info<T: ?Sized, PTR>(&self, message: &'static str, ptr: *mut PTR) -> LeakInfo {
+        let mut leak = LeakInfo::new::<T, PTR>(message, ptr);
+        leak.label = self.label;
+        leak.policy = self.policy;
+        leak
+    }
 }
 
 /// A Container that allows for checked passing of a pointer over a FFI boundary
 #[derive(Debug, Clone)]
-pub struct PassableContainer<PTR, PAS: Passable<PTR>> {
+pub struct PassableContainer<PAS: Passable> {
     value: PAS,
-    _phantom: PhantomData<PTR>
+    options: ContainerOptions
 }
 
-impl<PTR, PAS: Passable<PTR>> PassableContainer<PTR, PAS> {
+impl<PAS: Passable> PassableContainer<PAS> {
     /// Creates a new [PassableContainer] from a [Passable]
-    pub fn new(passable: PAS) -> Self {
+    ///
+    /// This is a `const fn`, so a Container around a const-constructible [Passable] can live in a static
+    ///
+    /// ``` rust
+    /// use passable_guard::PassableContainer;
+    ///
+    /// static EMPTY: PassableContainer<Vec<u8>> = PassableContainer::new(Vec::new());
+    ///
+    /// assert!(EMPTY.as_inner().is_empty());
+    /// ```
+    pub const fn new(passable: PAS) -> Self {
+        Self::with_options(passable, ContainerOptions::new())
+    }
+
+    pub(crate) const fn with_options(passable: PAS, options: ContainerOptions) -> Self {
         Self {
             value: passable,
-            _phantom: Default::default()
+            options
         }
     }
 
+    /// Get the label of this Container, if any
+    pub const fn label(&self) -> Option<&'static str> {
+        self.options.label
+    }
+
     /// Get back the [Passable] from this Container
     pub fn into_inner(self) -> PAS {
         self.value
     }
 
+    /// Borrow the [Passable] inside this Container
+    pub const fn as_inner(&self) -> &PAS {
+        &self.value
+    }
+
+    /// Mutably borrow the [Passable] inside this Container
+    pub fn as_inner_mut(&mut self) -> &mut PAS {
+        &mut self.value
+    }
+
+    /// Replace the [Passable] inside this Container, keeping its label and policy, and get back the old one
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let mut container = PassableContainer::builder(CString::new("old").unwrap())
+    ///     .label("names")
+    ///     .build();
+    ///
+    /// let old = container.replace(CString::new("new").unwrap());
+    ///
+    /// assert_eq!(old.to_str().unwrap(), "old");
+    /// assert_eq!(container.as_inner().to_str().unwrap(), "new");
+    /// assert_eq!(container.label(), Some("names"));
+    /// ```
+    pub fn replace(&mut self, passable: PAS) -> PAS {
+        std::mem::replace(&mut self.value, passable)
+    }
+
+    /// Swap the [Passable]s of two Containers, each Container keeps its own label and policy
+    pub fn swap(&mut self, other: &mut PassableContainer<PAS>) {
+        std::mem::swap(&mut self.value, &mut other.value);
+    }
+
+    /// Convert the [Passable] inside this Container into a different [Passable]
+    pub fn map<NPAS: Passable, F>(self, f: F) -> PassableContainer<NPAS>
+        where F: FnOnce(PAS) -> NPAS
+    {
+        PassableContainer::with_options(f(self.value), self.options)
+    }
+
+    /// Convert the [Passable] inside this Container into a different [Passable] with a fallible conversion
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let name = PassableContainer::new(String::from("name"));
+    /// let name = name.try_map(CString::new).unwrap();
+    ///
+    /// let (guard, ptr) = name.pass();
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return the Error of the conversion
+    pub fn try_map<NPAS: Passable, E, F>(self, f: F) -> Result<PassableContainer<NPAS>, E>
+        where F: FnOnce(PAS) -> Result<NPAS, E>
+    {
+        let options = self.options;
+        f(self.value).map(|passable| PassableContainer::with_options(passable, options))
+    }
+
     /// Convert the [PassableContainer] into a pointer to pass it over a FFI boundary
-    pub fn pass(self) -> (PassableGuard<PTR, PAS>, *mut PTR) {
-        let ptr = self.value.pass();
-        let guard = PassableGuard {
-            ptr,
-            _phantom: Default::default()
-        };
-        (guard, ptr)
+    #[track_caller]
+    pub fn pass(self) -> (PassableGuard<PAS>, *mut PAS::Pointee) {
+        self.pass_with_policy()
+    }
+
+    /// Convert the [PassableContainer] into a pointer to pass it over a FFI boundary,
+    /// using the [GuardPolicy] `P` for the guard
+    #[track_caller]
+    pub fn pass_with_policy<P: GuardPolicy>(self) -> (PassableGuard<PAS, P>, *mut PAS::Pointee) {
+        let (ptr, metadata) = self.value.pass();
+        (PassableGuard::new(ptr, metadata, self.options), ptr)
+    }
+
+    /// Convert the [PassableContainer] into a const pointer to pass it to a read-only FFI function
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass_const();
+    ///
+    /// // Read-only FFI call with ptr
+    ///
+    /// unsafe { guard.reconstitute_const(ptr) }.unwrap();
+    /// ```
+    #[track_caller]
+    pub fn pass_const(self) -> (PassableGuard<PAS>, *const PAS::Pointee) {
+        let (guard, ptr) = self.pass();
+        (guard, ptr as *const PAS::Pointee)
+    }
+
+    /// Convert the [PassableContainer] into a pointer to pass it over a FFI boundary,
+    /// together with the number of elements in the passed buffer
+    ///
+    /// ``` rust
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr, len) = PassableContainer::new(vec![1u8, 2, 3]).pass_with_len();
+    /// assert_eq!(len, 3);
+    ///
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// ```
+    #[track_caller]
+    pub fn pass_with_len(self) -> (PassableGuard<PAS>, *mut PAS::Pointee, usize) where PAS: PassableLen {
+        let (guard, ptr) = self.pass();
+        let len = PAS::passed_len(&guard.metadata);
+        (guard, ptr, len)
     }
 
     /// Convert the [PassableContainer] into a pointer to pass if over a FFI Boundary
     ///
-    /// ### Unsafe
+    /// ### Safety
     /// Since this does not create a [PassableGuard] to accompany the pointer, it is unsafe
-    pub unsafe fn pass_unguarded(self) -> *mut PTR {
-        self.value.pass()
+    #[deprecated(note = "use `PassableContainer::leak` to intentionally give the allocation to the FFI")]
+    pub unsafe fn pass_unguarded(self) -> *mut PAS::Pointee {
+        self.leak()
+    }
+
+    /// Permanently give the allocation of the [Passable] to the FFI, similar to [Box::leak]
+    ///
+    /// No guard is created, so the memory is never freed by Rust.
+    /// This is the intended way to hand over ownership to a FFI that frees the memory itself or keeps it forever
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let ptr = PassableContainer::new(CString::new("forever").unwrap()).leak();
+    /// assert!(!ptr.is_null());
+    /// ```
+    pub fn leak(self) -> *mut PAS::Pointee {
+        self.value.pass().0
+    }
+}
+
+impl<PAS: Passable> From<PAS> for PassableContainer<PAS> {
+    /// Creates a new [PassableContainer] from a [Passable], so helper functions can take `impl Into<PassableContainer<_>>`
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let container: PassableContainer<_> = CString::new("name").unwrap().into();
+    /// let (guard, ptr) = container.pass();
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// ```
+    fn from(passable: PAS) -> Self {
+        Self::new(passable)
+    }
+}
+
+impl<PAS: Passable> AsRef<PAS> for PassableContainer<PAS> {
+    fn as_ref(&self) -> &PAS {
+        &self.value
+    }
+}
+
+impl<PAS: Passable> AsMut<PAS> for PassableContainer<PAS> {
+    fn as_mut(&mut self) -> &mut PAS {
+        &mut self.value
     }
 }
 
+impl<PAS: Passable> Deref for PassableContainer<PAS> {
+    type Target = PAS;
+
+    /// Allows inspecting the [Passable] before passing it, for example with `container.as_bytes()` for a [CString](std::ffi::CString)
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+/// The state of a [PassableGuard]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardState {
+    /// The pointer has been passed and not yet been handed back, dropping the guard will panic
+    Armed,
+    /// The pointer has been handed back, dropping the guard is fine
+    Disarmed,
+    /// The pointer has been handed back, but the [Passable] could not be reconstituted from it
+    Poisoned
+}
+
 /// A guard for a [PassableContainer] that has been converted into a pointer to be passed over a FFI boundary
 ///
 /// ### Panic
-/// If this guard is dropped before it has been reconstituted with the original pointer, it will panic
-#[derive(Debug, Clone)]
-pub struct PassableGuard<PTR, PAS: Passable<PTR>> {
-    ptr: *mut PTR,
-    _phantom: PhantomData<PAS>
+/// If this guard is dropped while it is still [GuardState::Armed], it will panic.
+/// A different [DropPolicy] can be configured with [PassableContainer::builder]
+/// and a compile-time [GuardPolicy] can be chosen with [PassableContainer::pass_with_policy]
+pub struct PassableGuard<PAS: Passable, P: GuardPolicy = ConfiguredPolicy> {
+    // Not a NonNull, Passables like an empty GlibBuffer or a null BSTR pass null pointers
+    ptr: *mut PAS::Pointee,
+    metadata: PAS::Metadata,
+    options: ContainerOptions,
+    id: u64,
+    state: GuardState,
+    #[cfg(feature = "diagnostics")]
+    registered: bool,
+    passed_at: Option<Instant>,
+    #[cfg(feature = "valgrind")]
+    valgrind_block: Option<u64>,
+    /// The id of the guard whose pointer this guard was handed, or [NOT_SWAPPED]
+    #[cfg(feature = "diagnostics")]
+    swapped_with: AtomicU64,
+    _phantom: PhantomData<(PAS, P)>
 }
 
-impl<PTR, PAS: Passable<PTR>> PassableGuard<PTR, PAS> {
+/// Marks a guard that was never handed the pointer of another guard
+#[cfg(feature = "diagnostics")]
+const NOT_SWAPPED: u64 = u64::MAX;
+
+/// Get the current time, or [None] on targets without a clock, where [Instant::now] panics
+fn now() -> Option<Instant> {
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    return None;
+
+    #[cfg(not(all(target_family = "wasm", target_os = "unknown")))]
+    Some(Instant::now())
+}
+
+impl<PAS: Passable, P: GuardPolicy> PassableGuard<PAS, P> {
+    #[track_caller]
+    fn new(ptr: *mut PAS::Pointee, metadata: PAS::Metadata, options: ContainerOptions) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        config::init();
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "diagnostics")]
+        stats::record_pass(PAS::passed_size(&metadata));
+
+        // Only sampled guards pay for timing and registration
+        #[cfg(feature = "diagnostics")]
+        let sampled = registry::is_sampled(id);
+        #[cfg(not(feature = "diagnostics"))]
+        let sampled = false;
+        let passed_at = if sampled {now()} else {None};
+
+        #[cfg(feature = "diagnostics")]
+        let registered = sampled && registry::is_enabled();
+        #[cfg(feature = "diagnostics")]
+        if registered {
+            let thread = std::thread::current();
+            #[cfg(feature = "tokio")]
+            let (task_id, task_name) = registry::current_task();
+            registry::register(registry::GuardRecord {
+                id,
+                type_name: std::any::type_name::<PAS>(),
+                ptr: ptr as *mut (),
+                size: PAS::passed_size(&metadata),
+                label: options.label,
+                created: passed_at,
+                location: Location::caller(),
+                thread_id: thread.id(),
+                thread_name: thread.name().map(ToOwned::to_owned),
+                #[cfg(feature = "tokio")]
+                task_id,
+                #[cfg(feature = "tokio")]
+                task_name,
+                backtrace: if config::capture_backtraces() {Some(Arc::new(Backtrace::force_capture()))} else {None}
+            });
+        }
+
+        #[cfg(feature = "valgrind")]
+        let valgrind_block = PAS::passed_extent(&metadata).map(|size| {
+            let description = format!(
+                "passable_guard {}: {} passed at {}",
+                options.label.unwrap_or("unlabeled"),
+                std::any::type_name::<PAS>(),
+                Location::caller()
+            );
+            let description = std::ffi::CString::new(description).unwrap_or_default();
+            valgrind::create_block(ptr as *mut (), size, &description)
+        });
+
+        let guard = Self {
+            ptr,
+            metadata,
+            options,
+            id,
+            state: GuardState::Armed,
+            #[cfg(feature = "diagnostics")]
+            registered,
+            passed_at,
+            #[cfg(feature = "valgrind")]
+            valgrind_block,
+            #[cfg(feature = "diagnostics")]
+            swapped_with: AtomicU64::new(NOT_SWAPPED),
+            _phantom: Default::default()
+        };
+        #[cfg(feature = "diagnostics")]
+        diagnostics::passed(&guard);
+        guard
+    }
+
+    /// Get the label of the Container this guard was created for, if any
+    pub const fn label(&self) -> Option<&'static str> {
+        self.options.label
+    }
+
+    /// Get the id of this guard
+    ///
+    /// Ids are assigned in increasing order and are unique for the lifetime of the process,
+    /// they identify the guard in the [registry] and in all diagnostics
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Get how long the pointer of this guard has been passed for
+    ///
+    /// Returns [None] if this guard was not sampled, see [registry::set_sample_rate],
+    /// if the target has no clock, like `wasm32-unknown-unknown`, or without the `diagnostics` feature
+    pub fn held_for(&self) -> Option<Duration> {
+        self.passed_at.map(|passed_at| passed_at.elapsed())
+    }
+
+    /// Get the current [GuardState] of this guard
+    pub fn state(&self) -> GuardState {
+        self.state
+    }
+
+    /// Check whether this guard is still [GuardState::Armed]
+    pub fn is_armed(&self) -> bool {
+        self.state == GuardState::Armed
+    }
+
     /// Reconstitute a raw pointer back into a [PassableContainer]
     ///
     /// ### Errors
     /// Will return an Error if the pointer points do a different memory address then the pointer that was originally created by the pass method of the Container
     /// Will return an Error if the memory was modified by the FFI
     ///
-    /// ### Unsafe
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
     /// This function is unsafe because if the memory was modified by the FFI, it can cause UB when trying to reconstitute the [Passable]
     ///
     /// As an example, a FFI removing the terminating NULL from a NULL-terminated C-String, it can cause reads outside the original Buffer
     ///
     /// Additionally, continuing to use the pointer after the [PassableContainer] will lead to UB
-    pub	unsafe fn reconstitute(self, ptr: *mut PTR) -> Result<PassableContainer<PTR, PAS>, ReconstituteError<PTR, PAS>> {
-        if self.ptr != ptr {
+    pub	unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.check(ptr)?;
+        // Reconstitute from the pointer the guard kept, whose provenance is not affected by what the FFI did with its copy
+        let ptr = self.ptr;
+        self.reconstitute_unchecked(ptr)
+    }
+
+    /// Reconstitute the [Passable] from the address the FFI handed back, for FFIs that return addresses as integers
+    ///
+    /// Only the address is compared, the [Passable] is reconstituted from the pointer the guard kept,
+    /// so no pointer is ever created from an integer and the crate stays sound under strict provenance
+    ///
+    /// ``` rust
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(vec![0u8; 16]).pass();
+    ///
+    /// // The FFI stores and returns the buffer as a plain integer handle
+    /// let handle = ptr.addr();
+    ///
+    /// unsafe { guard.reconstitute_addr(handle) }.unwrap();
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the addresses do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_addr(self, addr: usize) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.check_addr(addr)?;
+        let ptr = self.ptr;
+        self.reconstitute_unchecked(ptr)
+    }
+
+    /// Reconstitute like [PassableGuard::reconstitute] from code that must not unwind, like the `extern "C"` callbacks of this crate
+    ///
+    /// If the pointers do not match, the guard still applies its [GuardPolicy],
+    /// but a panic of the policy is caught and the Error is returned instead
+    pub(crate) unsafe fn reconstitute_in_callback(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        if let Err(error) = self.check(ptr) {
+            // The panic hook has already reported the panic, unwinding into the caller would abort the process
+            let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || drop(self)));
+            return Err(error);
+        }
+        let ptr = self.ptr;
+        self.reconstitute_unchecked(ptr)
+    }
+
+    /// Reconstitute a const pointer created by [PassableContainer::pass_const] back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_const(self, ptr: *const PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.reconstitute(ptr as *mut PAS::Pointee)
+    }
+
+    /// Reconstitute a raw pointer back into the [Passable] itself, without the [PassableContainer] around it
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+    ///
+    /// let name = unsafe { guard.reconstitute_into(ptr) }.unwrap();
+    /// assert_eq!(name.to_str().unwrap(), "name");
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_into(self, ptr: *mut PAS::Pointee) -> Result<PAS, ReconstituteError<PAS>> {
+        self.reconstitute(ptr)
+            .map(PassableContainer::into_inner)
+    }
+
+    /// Reconstitute a raw pointer back into a [PassableContainer] without checking it against the passed pointer
+    ///
+    /// This is meant for interop patterns where the original pointer is not available,
+    /// for example because the FFI echoed it back through a different channel
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply.
+    /// Additionally, `ptr` must point to the same allocation that was originally created by the pass method of the Container
+    pub unsafe fn reconstitute_unchecked(mut self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        #[cfg(feature = "valgrind")]
+        if let Some(size) = PAS::passed_extent(&self.metadata) {
+            valgrind::check_addressable(ptr as *mut (), size);
+        }
+
+        let result = PAS::reconstitute(ptr, self.metadata);
+        self.settle(result)
+    }
+
+    /// Update the state of this guard with the result of reconstituting its [Passable]
+    fn settle(&mut self, result: Result<PAS, PAS::ReconstituteError>) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        match result {
+            Ok(passable) => {
+                self.state = GuardState::Disarmed;
+                #[cfg(feature = "diagnostics")]
+                stats::record_reconstitution();
+                #[cfg(feature = "diagnostics")]
+                diagnostics::reconstituted(self);
+                Ok(PassableContainer::with_options(passable, self.options))
+            }
+            Err(err) => {
+                self.state = GuardState::Poisoned;
+                #[cfg(feature = "diagnostics")]
+                diagnostics::reconstitute_failed(self);
+                Err(ReconstituteError::ReconstituteError {error: err})
+            }
+        }
+    }
+
+    /// Get the passed pointer again to hand it to another FFI call
+    ///
+    /// The guard stays armed, so the same buffer can be passed to several consecutive FFI calls
+    /// without reconstituting and passing it again in between
+    pub fn repass(&self) -> *mut PAS::Pointee {
+        self.ptr
+    }
+
+    /// Get the address of the passed pointer, without exposing its provenance
+    pub fn addr(&self) -> usize {
+        self.ptr.addr()
+    }
+
+    /// Check that a pointer handed back by the FFI is the pointer this guard was created for
+    ///
+    /// The guard stays armed, so it can be checked after every FFI call and reconstituted after the last one
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+    ///
+    /// // First FFI call with ptr
+    /// guard.check(ptr).unwrap();
+    ///
+    /// // Second FFI call with the same buffer
+    /// let ptr = guard.repass();
+    /// guard.check(ptr).unwrap();
+    ///
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error if the pointer points do a different memory address then the pointer that was originally created by the pass method of the Container
+    /// If the pointer belongs to a different armed guard recorded in the registry, the Error names both guards
+    pub fn check(&self, ptr: *mut PAS::Pointee) -> Result<(), ReconstituteError<PAS>> {
+        self.check_addr(ptr.addr())
+    }
+
+    /// Check that an address handed back by the FFI is the address of the pointer this guard was created for
+    ///
+    /// ### Errors
+    /// Will return an Error if the address differs from the address of the pointer that was originally created by the pass method of the Container
+    pub fn check_addr(&self, addr: usize) -> Result<(), ReconstituteError<PAS>> {
+        if self.ptr.addr() != addr {
+            // Only used for diagnostics, the mismatched pointer is never dereferenced
+            let reconstituted = self.ptr.with_addr(addr);
+            #[cfg(feature = "diagnostics")]
+            diagnostics::mismatched(self, reconstituted);
+            #[cfg(feature = "diagnostics")]
+            if let Some(swap) = registry::find_swap(self.id, addr) {
+                // Remembered for the leak report, in case the guard is dropped with the error
+                self.swapped_with.store(swap.owner.id, Ordering::Relaxed);
+                return Err(
+                    ReconstituteError::PointerSwapped {
+                        passed: self.ptr,
+                        reconstituted,
+                        swap: Box::new(swap)
+                    }
+                );
+            }
             return Err(
                 ReconstituteError::PointerMismatch {
                     passed: self.ptr,
-                    reconstituted: ptr
+                    reconstituted
                 }
             );
         }
+        Ok(())
+    }
+}
 
-        PAS::reconstitute(ptr)
-            .map(|passable| PassableContainer::new(passable))
-            .map_err(
-                |err|
-                    ReconstituteError::ReconstituteError {error: err}
-            )
+impl<PAS: Passable, P: GuardPolicy> Debug for PassableGuard<PAS, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassableGuard")
+            .field("ptr", &self.ptr)
+            .field("metadata", &self.metadata)
+            .field("label", &self.options.label)
+            .field("state", &self.state)
+            .finish()
     }
 }
 
-impl<PTR, PAS: Passable<PTR>> Drop for PassableGuard<PTR, PAS> {
-    /// This function will always panic because it should never be called outside of Error States
+impl<PAS: Passable, P: GuardPolicy> Drop for PassableGuard<PAS, P> {
+    /// This function will apply the [GuardPolicy] if the guard is still armed, since the passed memory would be leaked otherwise.
+    /// During a [shutdown](begin_shutdown), the guard is reported and leaked like with [ReportPolicy] instead
     fn drop(&mut self) {
-        panic!("Passable Guard dropped before being reconstituted");
+        #[cfg(feature = "diagnostics")]
+        if self.registered {
+            registry::unregister(self.id);
+        }
+        #[cfg(feature = "diagnostics")]
+        stats::record_release(PAS::passed_size(&self.metadata), self.held_for());
+        #[cfg(feature = "valgrind")]
+        if let Some(block) = self.valgrind_block {
+            valgrind::discard(block);
+        }
+        #[cfg(feature = "diagnostics")]
+        diagnostics::released();
+
+        if self.state == GuardState::Armed {
+            self.armed_drop(Vec::new());
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Disarm this guard and apply its [GuardPolicy], listing `children` that are still outstanding
+    pub(crate) fn armed_drop(&mut self, children: Vec<ChildInfo>) {
+        // Disarm first, so the guard does not apply its policy again if the policy panics
+        self.state = GuardState::Disarmed;
+        #[cfg(feature = "diagnostics")]
+        stats::record_leak();
+        #[cfg(feature = "diagnostics")]
+        diagnostics::leaked(self);
+        let (ptr, metadata) = (self.ptr, self.metadata);
+        let mut leak = self.options.leak_info::<PAS, PAS::Pointee>("Passable Guard dropped before being reconstituted", ptr);
+        leak.held_for = self.held_for();
+        leak.children = children;
+        #[cfg(feature = "diagnostics")]
+        {
+            let owner = self.swapped_with.load(Ordering::Relaxed);
+            if owner != NOT_SWAPPED {
+                leak.swapped_with = registry::find(owner).map(|record| record.info());
+            }
+        }
+        if shutdown::is_shutting_down() {
+            ReportPolicy::armed_drop(&leak, || {});
+            return;
+        }
+        P::armed_drop(&leak, || {
+            // The guard was still armed, so the pointer has not been reconstituted yet
+            let _ = unsafe { PAS::reconstitute(ptr, metadata) };
+        });
     }
 }
 
 /// Base Trait for something that can be converted into a raw pointer to its underlying buffer to pass it over a FFI boundary
-pub trait Passable<PTR> : Sized {
+pub trait Passable : Sized {
+    /// The type of the underlying data the passed pointer points to
+    type Pointee;
+
     type ReconstituteError;
 
-    /// Convert the [Passable] into a raw pointer to its underlying data
+    /// Additional data besides the pointer that is needed to reconstitute the [Passable], like the length of a buffer
+    ///
+    /// The guard keeps the metadata until the pointer is reconstituted
+    type Metadata: Copy + Debug;
+
+    /// Convert the [Passable] into a raw pointer to its underlying data and the metadata needed to reconstitute it
     ///
     /// ### Notes
     /// Implementations must take care to ensure the underlying memory is not freed in this conversion
     /// It must also be ensured that the memory stays valid until the pointer is reconstituted
-    fn pass(self) -> *mut PTR;
+    fn pass(self) -> (*mut Self::Pointee, Self::Metadata);
 
-    /// Reconstitute the [Passable] from a raw pointer crated by the pass method
+    /// Reconstitute the [Passable] from a raw pointer and the metadata crated by the pass method
     ///
     /// ### Notes
     /// Implementations should try to handle modification of the data by the FFI but no guarantees can be made about this
     ///
-    /// ### Unsafe
+    /// ### Safety
     /// Although Implementations should try to handle data modification by the FFI, there are modifications the cannot be detected when trying to reconstitute.
-    /// This includes freeing the memory by the FFI, removing the trailing NULL of a NULL-Terminated string and similar modifications.
-    unsafe fn reconstitute(ptr: *mut PTR) -> Result<Self, Self::ReconstituteError>;
-}
+    /// This includes freeing the memory by the FFI, writing past the end of the passed buffer and similar modifications.
+    unsafe fn reconstitute(ptr: *mut Self::Pointee, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError>;
 
-impl Passable<u8> for CString {
-    type ReconstituteError = ();
-
-    fn pass(self) -> *mut u8 {
-        self.into_raw() as *mut u8
+    /// Get the size of the passed buffer in bytes from the metadata created by the pass method, if it is known
+    ///
+    /// This is only used for diagnostics like [stats] and may include memory that is not reachable from the passed pointer,
+    /// like separate allocations owned by it. It is never used to access memory, see [passed_extent](Passable::passed_extent) for that.
+    /// The default implementation returns [None]
+    fn passed_size(_metadata: &Self::Metadata) -> Option<usize> {
+        None
     }
 
-    unsafe fn reconstitute(ptr: *mut u8) -> Result<Self, Self::ReconstituteError> {
-        Ok(CString::from_raw(ptr as *mut i8))
+    /// Get the number of bytes addressable at the passed pointer from the metadata created by the pass method, if it is known
+    ///
+    /// Integrations like `asan` and `valgrind` mark exactly this range as (un)addressable,
+    /// so it must lie within the single allocation the pointer points into and must never exceed it.
+    /// The default implementation returns [None], which leaves the memory of the type untouched by them
+    fn passed_extent(_metadata: &Self::Metadata) -> Option<usize> {
+        None
     }
 }
+
+/// A [Passable] whose passed buffer consists of a known number of elements
+pub trait PassableLen : Passable {
+    /// Get the number of elements in the passed buffer from the metadata created by the pass method
+    fn passed_len(metadata: &Self::Metadata) -> usize;
+}
@@ -0,0 +1,112 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::{Passable, PassableLen};
+
+/// A passed memory map, kept until the pointer to its first byte is reconstituted
+pub struct PassedMap<M> {
+    map: *mut M,
+    len: usize
+}
+
+impl<M> Clone for PassedMap<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for PassedMap<M> {}
+
+impl<M> Debug for PassedMap<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassedMap")
+            .field("map", &self.map)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// A writable memory map passed as a pointer to its first byte, for example a shared region lent to another library or process
+///
+/// The region is unmapped when the reconstituted [MmapMut] is dropped, so [FreePolicy](crate::FreePolicy) unmaps it
+/// instead of handing it to the Rust allocator
+///
+/// ``` rust
+/// use memmap2::MmapMut;
+/// use passable_guard::PassableContainer;
+///
+/// let region = MmapMut::map_anon(4096).unwrap();
+/// let (guard, ptr, len) = PassableContainer::new(region).pass_with_len();
+/// assert_eq!(len, 4096);
+///
+/// // Hand `ptr` to a library that writes into the region
+/// unsafe { ptr.write(1) };
+///
+/// let region = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(region[0], 1);
+/// ```
+impl Passable for MmapMut {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    type Metadata = PassedMap<MmapMut>;
+
+    fn pass(mut self) -> (*mut u8, Self::Metadata) {
+        let ptr = self.as_mut_ptr();
+        let len = self.len();
+        (ptr, PassedMap {map: Box::into_raw(Box::new(self)), len})
+    }
+
+    unsafe fn reconstitute(_ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(*Box::from_raw(metadata.map))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.len)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for MmapMut {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
+
+/// A read-only memory map passed as a pointer to its first byte
+///
+/// ### Notes
+/// The pointer is only mutable to fit [Passable], writing through it is undefined behavior
+impl Passable for Mmap {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    type Metadata = PassedMap<Mmap>;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        let ptr = self.as_ptr() as *mut u8;
+        let len = self.len();
+        (ptr, PassedMap {map: Box::into_raw(Box::new(self)), len})
+    }
+
+    unsafe fn reconstitute(_ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(*Box::from_raw(metadata.map))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.len)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for Mmap {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A [Passable] that can adopt an allocation the FFI moved to a new address
+///
+/// Some FFIs legitimately reallocate a passed buffer and hand back a new pointer.
+/// Implementing this trait allows reconstituting such a pointer through [PassableGuard::reconstitute_moved]
+///
+/// ``` rust
+/// use passable_guard::{Passable, PassableContainer, Relocatable};
+///
+/// extern "C" {
+///     fn malloc(size: usize) -> *mut u8;
+///     fn realloc(ptr: *mut u8, size: usize) -> *mut u8;
+///     fn free(ptr: *mut u8);
+/// }
+///
+/// /// A buffer allocated with the C allocator
+/// #[derive(Debug)]
+/// struct MallocBuffer(*mut u8);
+///
+/// impl Drop for MallocBuffer {
+///     fn drop(&mut self) {
+///         unsafe { free(self.0) }
+///     }
+/// }
+///
+/// impl Passable for MallocBuffer {
+///     type Pointee = u8;
+///     type ReconstituteError = ();
+///     type Metadata = ();
+///
+///     fn pass(self) -> (*mut u8, Self::Metadata) {
+///         let ptr = self.0;
+///         std::mem::forget(self);
+///         (ptr, ())
+///     }
+///
+///     unsafe fn reconstitute(ptr: *mut u8, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+///         Ok(MallocBuffer(ptr))
+///     }
+/// }
+///
+/// impl Relocatable for MallocBuffer {
+///     unsafe fn reconstitute_moved(_old: *mut u8, new: *mut u8, _metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+///         // The buffer was reallocated with the same allocator, so it can simply be adopted
+///         Ok(MallocBuffer(new))
+///     }
+/// }
+///
+/// let buffer = MallocBuffer(unsafe { malloc(16) });
+/// let (guard, ptr) = PassableContainer::new(buffer).pass();
+///
+/// // The FFI grows the buffer
+/// let new_ptr = unsafe { realloc(ptr, 64) };
+///
+/// unsafe { guard.reconstitute_moved(ptr, new_ptr) }.unwrap();
+/// ```
+pub trait Relocatable: Passable {
+    /// Reconstitute the [Passable] from a pointer the FFI moved the allocation at `old` to
+    ///
+    /// `metadata` is the metadata created by the pass method for the allocation at `old`
+    ///
+    /// ### Safety
+    /// The same restrictions as for [Passable::reconstitute] apply.
+    /// Additionally, `new` must point to an allocation this [Passable] can take ownership of, and `old` must no longer be used
+    unsafe fn reconstitute_moved(old: *mut Self::Pointee, new: *mut Self::Pointee, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError>;
+}
+
+impl<PAS: Relocatable, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Reconstitute a raw pointer the FFI moved the allocation to back into a [PassableContainer]
+    ///
+    /// `old_expected` is checked against the pointer that was originally passed,
+    /// the [Passable] then adopts the allocation at `new_ptr` through [Relocatable::reconstitute_moved]
+    ///
+    /// ### Errors
+    /// Will return an Error if `old_expected` points do a different memory address then the pointer that was originally created by the pass method of the Container
+    /// Will return an Error if the [Passable] could not adopt the new allocation
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [Relocatable::reconstitute_moved] apply
+    pub unsafe fn reconstitute_moved(mut self, old_expected: *mut PAS::Pointee, new_ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        self.check(old_expected)?;
+
+        // Hand over the pointer the guard kept, whose provenance is not affected by what the FFI did with its copy
+        let result = PAS::reconstitute_moved(self.ptr, new_ptr, self.metadata);
+        self.settle(result)
+    }
+}
@@ -0,0 +1,58 @@
+/// Implement [Passable](crate::Passable) for a tuple struct wrapping a single [Passable](crate::Passable)
+///
+/// The newtype is passed like the wrapped value, with the same pointer, metadata and reconstitution error.
+/// Add `: PassableLen` to also implement [PassableLen](crate::PassableLen)
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{passable_newtype, PassableContainer};
+///
+/// pub struct UserName(CString);
+/// passable_newtype!(UserName(CString));
+///
+/// pub struct Frame(Vec<u8>);
+/// passable_newtype!(Frame(Vec<u8>): PassableLen);
+///
+/// let (guard, ptr) = PassableContainer::new(UserName(CString::new("name").unwrap())).pass();
+/// let name = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(name.0.to_str().unwrap(), "name");
+///
+/// let (guard, ptr, len) = PassableContainer::new(Frame(vec![0; 16])).pass_with_len();
+/// assert_eq!(len, 16);
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// ```
+#[macro_export]
+macro_rules! passable_newtype {
+    ($name:ident ($inner:ty)) => {
+        impl $crate::Passable for $name {
+            type Pointee = <$inner as $crate::Passable>::Pointee;
+            type ReconstituteError = <$inner as $crate::Passable>::ReconstituteError;
+            type Metadata = <$inner as $crate::Passable>::Metadata;
+
+            fn pass(self) -> (*mut Self::Pointee, Self::Metadata) {
+                $crate::Passable::pass(self.0)
+            }
+
+            unsafe fn reconstitute(ptr: *mut Self::Pointee, metadata: Self::Metadata) -> ::std::result::Result<Self, Self::ReconstituteError> {
+                <$inner as $crate::Passable>::reconstitute(ptr, metadata).map($name)
+            }
+
+            fn passed_size(metadata: &Self::Metadata) -> ::std::option::Option<usize> {
+                <$inner as $crate::Passable>::passed_size(metadata)
+            }
+
+            fn passed_extent(metadata: &Self::Metadata) -> ::std::option::Option<usize> {
+                <$inner as $crate::Passable>::passed_extent(metadata)
+            }
+        }
+    };
+    ($name:ident ($inner:ty): PassableLen) => {
+        $crate::passable_newtype!($name($inner));
+
+        impl $crate::PassableLen for $name {
+            fn passed_len(metadata: &Self::Metadata) -> usize {
+                <$inner as $crate::PassableLen>::passed_len(metadata)
+            }
+        }
+    };
+}
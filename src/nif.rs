@@ -0,0 +1,150 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Mutex, PoisonError};
+
+use rustler::{Binary, Env, OwnedBinary, Resource, ResourceArc};
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen};
+
+/// A passed [OwnedBinary], kept until the pointer to its first byte is reconstituted
+pub struct PassedBinary {
+    binary: *mut OwnedBinary,
+    len: usize
+}
+
+impl Clone for PassedBinary {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl Copy for PassedBinary {}
+
+impl Debug for PassedBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassedBinary")
+            .field("binary", &self.binary)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// A binary allocated with `enif_alloc_binary`, passed as a pointer to its first byte, for example to fill it from a C library
+///
+/// The reconstituted [OwnedBinary] can be handed to the BEAM with [OwnedBinary::release] or is freed with `enif_release_binary`
+impl Passable for OwnedBinary {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    type Metadata = PassedBinary;
+
+    fn pass(mut self) -> (*mut u8, Self::Metadata) {
+        let ptr = self.as_mut_slice().as_mut_ptr();
+        let len = self.len();
+        (ptr, PassedBinary {binary: Box::into_raw(Box::new(self)), len})
+    }
+
+    unsafe fn reconstitute(_ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(*Box::from_raw(metadata.binary))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(metadata.len)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for OwnedBinary {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        metadata.len
+    }
+}
+
+/// The guard of a buffer lent to the BEAM
+struct Lent<PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    ptr: *mut u8
+}
+
+// The guard only holds the pointer and metadata of a Passable that is Send
+unsafe impl<PAS: Passable + Send> Send for Lent<PAS> {}
+
+/// The resource behind the binaries created by [lend_to_beam], reconstituting the buffer in its destructor
+pub struct BeamBuffer {
+    ptr: *const u8,
+    len: usize,
+    reclaim: Mutex<Option<Box<dyn FnOnce() + Send>>>
+}
+
+// The buffer is only read through the binaries until the destructor reconstitutes it
+unsafe impl Send for BeamBuffer {}
+unsafe impl Sync for BeamBuffer {}
+
+impl BeamBuffer {
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[rustler::resource_impl]
+impl Resource for BeamBuffer {
+    const IMPLEMENTS_DESTRUCTOR: bool = true;
+
+    fn destructor(self, _env: Env<'_>) {
+        let reclaim = self.reclaim
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(reclaim) = reclaim {
+            reclaim();
+        }
+    }
+}
+
+impl Debug for BeamBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BeamBuffer")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+/// Lend the buffer of `container` to the BEAM as a resource binary, without copying it
+///
+/// The guard lives until the garbage collector frees the last binary referencing the buffer,
+/// then the destructor of the [BeamBuffer] resource disarms it by reconstituting the buffer.
+/// Buffers the BEAM never lets go of stay visible in the [registry](crate::registry) and [stats](crate::stats).
+/// The resource is registered by `rustler::init!` like the resources of the NIF itself
+///
+/// ``` rust
+/// use rustler::{Binary, Env};
+/// use passable_guard::PassableContainer;
+///
+/// #[rustler::nif]
+/// fn read_frame(env: Env<'_>) -> Binary<'_> {
+///     let frame = vec![0u8; 1024];
+///     passable_guard::lend_to_beam(env, PassableContainer::new(frame))
+/// }
+/// ```
+#[track_caller]
+pub fn lend_to_beam<PAS>(env: Env<'_>, container: PassableContainer<PAS>) -> Binary<'_>
+where
+    PAS: PassableLen<Pointee = u8> + Send + 'static
+{
+    let (guard, ptr, len) = container.pass_with_len();
+    let lent = Lent {guard, ptr};
+
+    let resource = ResourceArc::new(BeamBuffer {
+        ptr,
+        len,
+        reclaim: Mutex::new(Some(Box::new(move || {
+            let Lent {guard, ptr} = lent;
+            // A failure is reported by the guard, there is nobody to return it to
+            let _ = unsafe { guard.reconstitute(ptr) };
+        })))
+    });
+    resource.make_binary(env, BeamBuffer::as_slice)
+}
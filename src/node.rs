@@ -0,0 +1,33 @@
+use napi::{Env, JsArrayBufferValue};
+
+use crate::{PassableContainer, PassableGuard, PassableLen};
+
+/// Hand the buffer of `container` to JS as an external `ArrayBuffer`, whose finalizer reconstitutes it
+///
+/// The guard lives until the garbage collector finalizes the `ArrayBuffer`,
+/// so buffers JS never lets go of stay visible in the [registry](crate::registry) and [stats](crate::stats).
+/// If the runtime does not allow external buffers, the data is copied and the buffer is reconstituted right away
+///
+/// ``` rust
+/// use napi::{Env, JsArrayBuffer};
+/// use passable_guard::PassableContainer;
+///
+/// fn create_buffer(env: Env) -> napi::Result<JsArrayBuffer> {
+///     let buffer = passable_guard::pass_to_arraybuffer(&env, PassableContainer::new(vec![0u8; 64]))?;
+///     Ok(buffer.into_raw())
+/// }
+/// ```
+///
+/// ### Errors
+/// Will return an Error if the `ArrayBuffer` could not be created, the guard is leaked in that case
+#[track_caller]
+pub fn pass_to_arraybuffer<PAS: PassableLen<Pointee = u8>>(env: &Env, container: PassableContainer<PAS>) -> napi::Result<JsArrayBufferValue> {
+    let (guard, ptr, len) = container.pass_with_len();
+
+    let finalize = |(guard, ptr): (PassableGuard<PAS>, *mut u8), _env: Env| {
+        // A failure is reported by the guard, there is nobody to return it to
+        let _ = unsafe { guard.reconstitute(ptr) };
+    };
+    // The buffer stays valid until the finalizer reconstitutes it
+    unsafe { env.create_arraybuffer_with_borrowed_data(ptr, len, (guard, ptr), finalize) }
+}
@@ -0,0 +1,141 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::marker::PhantomData;
+
+use crate::{Passable, PassableContainer};
+
+/// An Error that can occur while adopting the pointer of an [OutPtr]
+#[derive(Debug, Clone)]
+pub enum AdoptError<RE> {
+    /// The FFI did not write a pointer into the out-parameter
+    Null,
+    ReconstituteError{error: RE}
+}
+
+impl<RE: Display> Display for AdoptError<RE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AdoptError::Null => write!(f, "the out-parameter does not contain a pointer"),
+            AdoptError::ReconstituteError {error} => write!(f, "could not reconstitute the passable: {}", error)
+        }
+    }
+}
+
+impl<RE: Error + 'static> Error for AdoptError<RE> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AdoptError::Null => None,
+            AdoptError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+/// An out-parameter for FFI functions that produce a pointer
+///
+/// The FFI gets a `*mut *mut PAS::Pointee` from [OutPtr::as_out_param] and writes a pointer into it.
+/// Afterwards, the pointer has to be either adopted into a [PassableContainer] or explicitly discarded.
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{OutPtr, PassableContainer};
+///
+/// let mut out = OutPtr::<CString>::new();
+///
+/// // The FFI writes a pointer it previously received from Rust into the out-parameter
+/// let ptr = PassableContainer::new(CString::new("name").unwrap()).leak();
+/// unsafe { *out.as_out_param() = ptr };
+///
+/// // The metadata of a CString is its length without the terminator
+/// let name = unsafe { out.adopt(4) }.unwrap();
+/// assert_eq!(name.into_inner().to_str().unwrap(), "name");
+/// ```
+///
+/// ### Panic
+/// If this out-parameter is dropped while it contains a pointer that has not been adopted or discarded, it will panic
+#[must_use = "the pointer written by the FFI has to be adopted or discarded"]
+pub struct OutPtr<PAS: Passable> {
+    slot: *mut PAS::Pointee,
+    _phantom: PhantomData<PAS>
+}
+
+impl<PAS: Passable> OutPtr<PAS> {
+    /// Creates a new, empty [OutPtr]
+    pub const fn new() -> Self {
+        Self {
+            slot: std::ptr::null_mut(),
+            _phantom: PhantomData
+        }
+    }
+
+    /// Get the out-parameter to pass it over a FFI boundary
+    ///
+    /// ### Notes
+    /// The returned pointer is only valid as long as this [OutPtr] is neither moved nor dropped
+    pub fn as_out_param(&mut self) -> *mut *mut PAS::Pointee {
+        &mut self.slot
+    }
+
+    /// Get the pointer the FFI wrote into the out-parameter, without adopting it
+    pub fn as_ptr(&self) -> *mut PAS::Pointee {
+        self.slot
+    }
+
+    /// Check whether the FFI has not written a pointer into the out-parameter
+    pub fn is_null(&self) -> bool {
+        self.slot.is_null()
+    }
+
+    /// Adopt the pointer the FFI wrote into the out-parameter into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return [AdoptError::Null] if the FFI did not write a pointer
+    /// Will return an Error if the [Passable] could not be reconstituted from the pointer
+    ///
+    /// ### Safety
+    /// The pointer must point to memory the [Passable] can take ownership of, described by `metadata`,
+    /// and the FFI must not use it anymore.
+    /// The same restrictions as for [Passable::reconstitute] apply
+    pub unsafe fn adopt(mut self, metadata: PAS::Metadata) -> Result<PassableContainer<PAS>, AdoptError<PAS::ReconstituteError>> {
+        let ptr = std::mem::replace(&mut self.slot, std::ptr::null_mut());
+        if ptr.is_null() {
+            return Err(AdoptError::Null);
+        }
+
+        PAS::reconstitute(ptr, metadata)
+            .map(|passable| PassableContainer::new(passable))
+            .map_err(
+                |error|
+                    AdoptError::ReconstituteError {error}
+            )
+    }
+
+    /// Explicitly discard the pointer the FFI wrote into the out-parameter and return it
+    ///
+    /// Ownership of the pointed to memory stays with whoever is responsible for it on the FFI side
+    pub fn discard(mut self) -> *mut PAS::Pointee {
+        std::mem::replace(&mut self.slot, std::ptr::null_mut())
+    }
+}
+
+impl<PAS: Passable> Default for OutPtr<PAS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<PAS: Passable> Debug for OutPtr<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutPtr")
+            .field("slot", &self.slot)
+            .finish()
+    }
+}
+
+impl<PAS: Passable> Drop for OutPtr<PAS> {
+    /// This function will panic if the out-parameter still contains a pointer
+    fn drop(&mut self) {
+        if !self.slot.is_null() && !std::thread::panicking() {
+            panic!("Out Pointer dropped without adopting or discarding its pointer");
+        }
+    }
+}
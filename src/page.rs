@@ -0,0 +1,303 @@
+//! Buffers followed by a guard page
+//!
+//! A C-side overflow of a heap buffer usually corrupts whatever happens to follow it
+//! and only surfaces much later as heap weirdness. A [GuardPageBuffer] places its bytes at the very end of a page
+//! and makes the page after it inaccessible, so an overflow faults immediately at the point of corruption.
+//!
+//! ``` rust
+//! use passable_guard::{GuardPageBuffer, PassableContainer};
+//!
+//! let (guard, ptr, len) = PassableContainer::new(GuardPageBuffer::new(100)).pass_with_len();
+//!
+//! // Writing past `len` bytes would fault right here instead of corrupting the heap
+//! unsafe { std::ptr::write_bytes(ptr, 1, len) };
+//!
+//! let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+//! assert!(buffer.iter().all(|byte| *byte == 1));
+//! ```
+//!
+//! Every buffer takes at least two pages, so this is meant for debugging rather than production.
+//! Underflows before the start of the buffer are not caught.
+//!
+//! Miri cannot change the protection of pages, so under Miri the buffer is allocated without a guard page instead,
+//! ending right at the end of its allocation, where Miri reports an overflow just as immediately
+
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+
+use crate::{Passable, PassableLen};
+
+#[cfg(all(unix, not(miri)))]
+mod sys {
+    use std::alloc::Layout;
+    use std::os::raw::{c_int, c_long, c_void};
+
+    pub(super) const GUARD_PAGES: usize = 1;
+
+    const PROT_NONE: c_int = 0;
+    const PROT_READ: c_int = 1;
+    const PROT_WRITE: c_int = 2;
+
+    #[cfg(target_os = "linux")]
+    const SC_PAGESIZE: c_int = 30;
+    #[cfg(target_os = "android")]
+    const SC_PAGESIZE: c_int = 39;
+    #[cfg(target_vendor = "apple")]
+    const SC_PAGESIZE: c_int = 29;
+    #[cfg(target_os = "freebsd")]
+    const SC_PAGESIZE: c_int = 47;
+
+    extern "C" {
+        fn sysconf(name: c_int) -> c_long;
+        fn mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int;
+    }
+
+    pub(super) fn page_size() -> usize {
+        unsafe { sysconf(SC_PAGESIZE) as usize }
+    }
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, page_size()).expect("The page size is a power of two")
+    }
+
+    /// Allocate `size` zeroed bytes, starting at a page boundary
+    pub(super) fn allocate(size: usize) -> *mut u8 {
+        let layout = layout(size);
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        ptr
+    }
+
+    pub(super) unsafe fn protect(page: *mut u8, accessible: bool) {
+        let prot = if accessible {PROT_READ | PROT_WRITE} else {PROT_NONE};
+        let result = mprotect(page as *mut c_void, page_size(), prot);
+        assert_eq!(result, 0, "Could not change the protection of the guard page at {:p}", page);
+    }
+
+    pub(super) unsafe fn free(ptr: *mut u8, size: usize) {
+        std::alloc::dealloc(ptr, layout(size));
+    }
+}
+
+#[cfg(all(windows, not(miri)))]
+mod sys {
+    use std::os::raw::c_void;
+
+    pub(super) const GUARD_PAGES: usize = 1;
+
+    const MEM_COMMIT: u32 = 0x1000;
+    const MEM_RESERVE: u32 = 0x2000;
+    const MEM_RELEASE: u32 = 0x8000;
+    const PAGE_NOACCESS: u32 = 0x01;
+    const PAGE_READWRITE: u32 = 0x04;
+
+    #[repr(C)]
+    struct SystemInfo {
+        processor_architecture: u16,
+        reserved: u16,
+        page_size: u32,
+        minimum_application_address: *mut c_void,
+        maximum_application_address: *mut c_void,
+        active_processor_mask: usize,
+        number_of_processors: u32,
+        processor_type: u32,
+        allocation_granularity: u32,
+        processor_level: u16,
+        processor_revision: u16
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemInfo(info: *mut SystemInfo);
+        fn VirtualAlloc(address: *mut c_void, size: usize, allocation_type: u32, protect: u32) -> *mut c_void;
+        fn VirtualProtect(address: *mut c_void, size: usize, protect: u32, old_protect: *mut u32) -> i32;
+        fn VirtualFree(address: *mut c_void, size: usize, free_type: u32) -> i32;
+    }
+
+    pub(super) fn page_size() -> usize {
+        let mut info = std::mem::MaybeUninit::<SystemInfo>::uninit();
+        unsafe {
+            GetSystemInfo(info.as_mut_ptr());
+            info.assume_init().page_size as usize
+        }
+    }
+
+    /// Allocate `size` zeroed bytes, starting at a page boundary
+    pub(super) fn allocate(size: usize) -> *mut u8 {
+        let ptr = unsafe { VirtualAlloc(std::ptr::null_mut(), size, MEM_COMMIT | MEM_RESERVE, PAGE_READWRITE) } as *mut u8;
+        assert!(!ptr.is_null(), "Could not allocate {} bytes with VirtualAlloc", size);
+        ptr
+    }
+
+    pub(super) unsafe fn protect(page: *mut u8, accessible: bool) {
+        let protect = if accessible {PAGE_READWRITE} else {PAGE_NOACCESS};
+        let mut old = 0;
+        let result = VirtualProtect(page as *mut c_void, page_size(), protect, &mut old);
+        assert_ne!(result, 0, "Could not change the protection of the guard page at {:p}", page);
+    }
+
+    pub(super) unsafe fn free(ptr: *mut u8, _size: usize) {
+        VirtualFree(ptr as *mut c_void, 0, MEM_RELEASE);
+    }
+}
+
+#[cfg(miri)]
+mod sys {
+    use std::alloc::Layout;
+
+    pub(super) const GUARD_PAGES: usize = 0;
+
+    const PAGE_SIZE: usize = 4096;
+
+    pub(super) fn page_size() -> usize {
+        PAGE_SIZE
+    }
+
+    fn layout(size: usize) -> Layout {
+        Layout::from_size_align(size, PAGE_SIZE).expect("The page size is a power of two")
+    }
+
+    /// Allocate `size` zeroed bytes, starting at a page boundary
+    pub(super) fn allocate(size: usize) -> *mut u8 {
+        let layout = layout(size);
+        let ptr = unsafe { std::alloc::alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+        ptr
+    }
+
+    pub(super) unsafe fn protect(_page: *mut u8, _accessible: bool) {}
+
+    pub(super) unsafe fn free(ptr: *mut u8, size: usize) {
+        std::alloc::dealloc(ptr, layout(size));
+    }
+}
+
+/// A zeroed byte buffer that ends right before an inaccessible guard page
+pub struct GuardPageBuffer {
+    ptr: *mut u8,
+    len: usize
+}
+
+// The buffer is owned exclusively, like a Box<[u8]>
+unsafe impl Send for GuardPageBuffer {}
+unsafe impl Sync for GuardPageBuffer {}
+
+/// The size of the pages holding `len` bytes and of the whole allocation including the guard page
+fn sizes(len: usize) -> (usize, usize) {
+    let page = sys::page_size();
+    let data = len.div_ceil(page) * page;
+    // An empty buffer still needs an allocation without a guard page
+    (data, (data + page * sys::GUARD_PAGES).max(page))
+}
+
+impl GuardPageBuffer {
+    /// Allocate a zeroed buffer of `len` bytes followed by a guard page
+    ///
+    /// ### Panic
+    /// Panics if the buffer could not be allocated or the guard page could not be protected
+    pub fn new(len: usize) -> Self {
+        let (data, total) = sizes(len);
+        let base = sys::allocate(total);
+        unsafe {
+            sys::protect(base.add(data), false);
+            Self {
+                ptr: base.add(data - len),
+                len
+            }
+        }
+    }
+
+    /// Allocate a buffer containing a copy of `data`, followed by a guard page
+    ///
+    /// ### Panic
+    /// Panics if the buffer could not be allocated or the guard page could not be protected
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut buffer = Self::new(data.len());
+        buffer.copy_from_slice(data);
+        buffer
+    }
+
+    /// Take ownership of a buffer of `len` bytes
+    ///
+    /// ### Safety
+    /// `ptr` and `len` have to come from [GuardPageBuffer::into_raw] and the buffer must not be owned by anything else
+    pub unsafe fn from_raw(ptr: *mut u8, len: usize) -> Self {
+        Self {ptr, len}
+    }
+
+    /// Give up ownership of the buffer, it has to be taken back with [GuardPageBuffer::from_raw]
+    pub fn into_raw(self) -> *mut u8 {
+        ManuallyDrop::new(self).ptr
+    }
+}
+
+impl Deref for GuardPageBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl DerefMut for GuardPageBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for GuardPageBuffer {
+    fn drop(&mut self) {
+        let (data, total) = sizes(self.len);
+        unsafe {
+            let guard_page = self.ptr.add(self.len);
+            let base = guard_page.sub(data);
+            sys::protect(guard_page, true);
+            sys::free(base, total);
+        }
+    }
+}
+
+impl Debug for GuardPageBuffer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GuardPageBuffer")
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl Passable for GuardPageBuffer {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    /// The length of the buffer in bytes
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        let len = self.len;
+        (self.into_raw(), len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(GuardPageBuffer::from_raw(ptr, metadata))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(*metadata)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl PassableLen for GuardPageBuffer {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{GuardPolicy, PassableContainer, PassableGuard, ReconstituteError};
+
+impl<T> PassableContainer<Vec<T>> {
+    /// Convert the [PassableContainer] into the raw parts of its [Vec] to pass them over a FFI boundary
+    ///
+    /// This is meant for C APIs that take a buffer with its length and capacity
+    /// and report the new length back after writing into the spare capacity
+    ///
+    /// ``` rust
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr, len, capacity) = PassableContainer::new(Vec::<u8>::with_capacity(8)).pass_raw_parts();
+    /// assert_eq!(len, 0);
+    ///
+    /// // The FFI fills the buffer and reports the new length
+    /// unsafe { ptr.write_bytes(7, 3) };
+    ///
+    /// let buffer = unsafe { guard.reconstitute_raw_parts(ptr, 3, capacity) }.unwrap();
+    /// assert_eq!(buffer.into_inner(), vec![7, 7, 7]);
+    /// ```
+    #[track_caller]
+    pub fn pass_raw_parts(self) -> (PassableGuard<Vec<T>>, *mut T, usize, usize) {
+        let (guard, ptr) = self.pass();
+        let (len, capacity) = (guard.metadata.len, guard.metadata.capacity);
+        (guard, ptr, len, capacity)
+    }
+}
+
+impl<T, P: GuardPolicy> PassableGuard<Vec<T>, P> {
+    /// Reconstitute the raw parts of a [Vec] reported back by the FFI into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error if the pointer points do a different memory address then the pointer that was originally created by the pass method of the Container.
+    /// Will return an Error if `capacity` differs from the capacity the [Vec] was passed with or `len` exceeds it
+    ///
+    /// ``` rust
+    /// use passable_guard::{LeakPolicy, PassableContainer, ReconstituteError};
+    ///
+    /// let (guard, ptr) = PassableContainer::new(Vec::<u8>::with_capacity(8)).pass_with_policy::<LeakPolicy>();
+    ///
+    /// let result = unsafe { guard.reconstitute_raw_parts(ptr, 16, 16) };
+    /// assert!(matches!(result, Err(ReconstituteError::InvalidParts {len: 16, capacity: 16, ..})));
+    /// ```
+    ///
+    /// ### Panic
+    /// If the pointers do not match or the parts are invalid, the guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply.
+    /// Additionally, the same restrictions as for [Vec::from_raw_parts] apply to `len` and `capacity`,
+    /// in particular the first `len` elements must be initialized
+    pub unsafe fn reconstitute_raw_parts(mut self, ptr: *mut T, len: usize, capacity: usize) -> Result<PassableContainer<Vec<T>>, ReconstituteError<Vec<T>>> {
+        self.check(ptr)?;
+        let passed_capacity = self.metadata.capacity;
+        if capacity != passed_capacity || len > capacity {
+            return Err(
+                ReconstituteError::InvalidParts {
+                    len,
+                    capacity,
+                    passed_capacity
+                }
+            );
+        }
+        // Rebuild from the pointer the guard kept, whose provenance is not affected by what the FFI did with its copy
+        self.settle(Ok(Vec::from_raw_parts(self.ptr, len, capacity)))
+    }
+}
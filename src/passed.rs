@@ -0,0 +1,91 @@
+use std::ops::Deref;
+
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A raw pointer created by [PassableContainer::pass_tracked] that remembers which guard it belongs to
+///
+/// It dereferences to the raw pointer for FFI calls and can be handed back to [PassableGuard::reconstitute_passed]
+#[derive(Debug, PartialEq, Eq)]
+pub struct PassedPtr<PTR> {
+    ptr: *mut PTR,
+    guard_id: u64
+}
+
+impl<PTR> PassedPtr<PTR> {
+    /// Get the raw pointer to pass it over a FFI boundary
+    pub fn as_ptr(&self) -> *mut PTR {
+        self.ptr
+    }
+}
+
+impl<PTR> Clone for PassedPtr<PTR> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<PTR> Copy for PassedPtr<PTR> {}
+
+impl<PTR> Deref for PassedPtr<PTR> {
+    type Target = *mut PTR;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ptr
+    }
+}
+
+impl<PTR> From<PassedPtr<PTR>> for *mut PTR {
+    fn from(passed: PassedPtr<PTR>) -> Self {
+        passed.ptr
+    }
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a [PassedPtr] to pass it over a FFI boundary
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass_tracked();
+    ///
+    /// let raw: *mut u8 = *ptr; // Give this to the FFI
+    ///
+    /// unsafe { guard.reconstitute_passed(ptr) }.unwrap();
+    /// ```
+    #[track_caller]
+    pub fn pass_tracked(self) -> (PassableGuard<PAS>, PassedPtr<PAS::Pointee>) {
+        let (guard, ptr) = self.pass();
+        let passed = PassedPtr {
+            ptr,
+            guard_id: guard.id
+        };
+        (guard, passed)
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Reconstitute a [PassedPtr] back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return [ReconstituteError::PointerMismatch] if the [PassedPtr] was created for a different guard
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Panic
+    /// If the [PassedPtr] belongs to a different guard, this guard is dropped while still armed and will apply its [GuardPolicy]
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_passed(self, ptr: PassedPtr<PAS::Pointee>) -> Result<PassableContainer<PAS>, ReconstituteError<PAS>> {
+        if self.id != ptr.guard_id {
+            return Err(
+                ReconstituteError::PointerMismatch {
+                    passed: self.ptr,
+                    reconstituted: ptr.ptr
+                }
+            );
+        }
+
+        self.reconstitute(ptr.ptr)
+    }
+}
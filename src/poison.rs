@@ -0,0 +1,161 @@
+//! Poisoning reclaimed memory in debug builds
+//!
+//! C code that stashed a pointer and uses it after the buffer has been reconstituted and freed
+//! usually reads stale data that still looks plausible. Wrapping the [Passable] in [Poisoned] fills its allocation
+//! with [POISON] right before it is freed, whether the reconstituted value is dropped or a guard frees it,
+//! so such a use after free fails loudly and recognizably instead.
+//!
+//! ``` rust
+//! use passable_guard::{PassableContainer, Poisoned};
+//!
+//! let (guard, ptr) = PassableContainer::new(Poisoned::new(vec![1u8, 2, 3])).pass();
+//! let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+//! assert_eq!(*buffer, [1, 2, 3]);
+//!
+//! // In debug builds, the allocation is filled with POISON before it is freed
+//! drop(buffer);
+//! ```
+//!
+//! In release builds, [Poisoned] drops the value without touching its allocation
+
+use std::ffi::CString;
+use std::fmt::{Debug, Formatter};
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ops::{Deref, DerefMut};
+
+use crate::{Passable, PassableLen};
+
+/// The byte that reclaimed allocations are filled with
+pub const POISON: u8 = 0xDD;
+
+/// A [Passable] that can fill its allocation with [POISON] before it is freed
+pub trait Poisonable : Passable {
+    /// Drop the value, filling its whole allocation with [POISON] before it is freed
+    fn drop_poisoned(self);
+}
+
+impl<T> Poisonable for Vec<T> {
+    fn drop_poisoned(mut self) {
+        // Dropping the elements first, no poisoned value is ever dropped
+        self.clear();
+        let size = self.capacity() * std::mem::size_of::<T>();
+        // The allocation is valid for its whole capacity
+        unsafe { std::ptr::write_bytes(self.as_mut_ptr() as *mut u8, POISON, size) };
+    }
+}
+
+impl Poisonable for String {
+    fn drop_poisoned(self) {
+        self.into_bytes().drop_poisoned();
+    }
+}
+
+impl Poisonable for CString {
+    fn drop_poisoned(self) {
+        self.into_bytes_with_nul().drop_poisoned();
+    }
+}
+
+impl<T> Poisonable for Box<T> {
+    fn drop_poisoned(self) {
+        let ptr = Box::into_raw(self);
+        unsafe {
+            std::ptr::drop_in_place(ptr);
+            std::ptr::write_bytes(ptr as *mut u8, POISON, std::mem::size_of::<T>());
+            // The value has already been dropped, only the allocation is left to free
+            drop(Box::from_raw(ptr as *mut MaybeUninit<T>));
+        }
+    }
+}
+
+impl<T> Poisonable for Box<[T]> {
+    fn drop_poisoned(self) {
+        self.into_vec().drop_poisoned();
+    }
+}
+
+/// A wrapper around a [Poisonable] that fills its allocation with [POISON] when it is dropped in debug builds
+///
+/// It is passed exactly like the wrapped value
+pub struct Poisoned<PAS: Poisonable>(ManuallyDrop<PAS>);
+
+impl<PAS: Poisonable> Poisoned<PAS> {
+    /// Wrap a [Poisonable] to poison its allocation when it is dropped
+    pub const fn new(passable: PAS) -> Self {
+        Self(ManuallyDrop::new(passable))
+    }
+
+    /// Get back the wrapped value, which is no longer poisoned when it is dropped
+    pub fn into_inner(self) -> PAS {
+        let mut this = ManuallyDrop::new(self);
+        // The wrapper is never dropped, so the value is only taken once
+        unsafe { ManuallyDrop::take(&mut this.0) }
+    }
+}
+
+impl<PAS: Poisonable> Deref for Poisoned<PAS> {
+    type Target = PAS;
+
+    fn deref(&self) -> &PAS {
+        &self.0
+    }
+}
+
+impl<PAS: Poisonable> DerefMut for Poisoned<PAS> {
+    fn deref_mut(&mut self) -> &mut PAS {
+        &mut self.0
+    }
+}
+
+impl<PAS: Poisonable> Drop for Poisoned<PAS> {
+    fn drop(&mut self) {
+        // The value is only taken here, while the wrapper is dropped
+        let passable = unsafe { ManuallyDrop::take(&mut self.0) };
+        #[cfg(debug_assertions)]
+        passable.drop_poisoned();
+        #[cfg(not(debug_assertions))]
+        drop(passable);
+    }
+}
+
+impl<PAS: Poisonable> Passable for Poisoned<PAS> {
+    type Pointee = PAS::Pointee;
+    type ReconstituteError = PAS::ReconstituteError;
+    type Metadata = PAS::Metadata;
+
+    fn pass(self) -> (*mut Self::Pointee, Self::Metadata) {
+        self.into_inner().pass()
+    }
+
+    unsafe fn reconstitute(ptr: *mut Self::Pointee, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        PAS::reconstitute(ptr, metadata).map(Poisoned::new)
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        PAS::passed_size(metadata)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        PAS::passed_extent(metadata)
+    }
+}
+
+impl<PAS: Poisonable + PassableLen> PassableLen for Poisoned<PAS> {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        PAS::passed_len(metadata)
+    }
+}
+
+impl<PAS: Poisonable + Clone> Clone for Poisoned<PAS> {
+    fn clone(&self) -> Self {
+        Self::new(PAS::clone(self))
+    }
+}
+
+impl<PAS: Poisonable + Debug> Debug for Poisoned<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Poisoned")
+            .field(&*self.0)
+            .finish()
+    }
+}
@@ -0,0 +1,206 @@
+//! Drop policies
+//!
+//! A [GuardPolicy] decides what happens when a [PassableGuard](crate::PassableGuard) is dropped while still armed.
+//! The policy is a type parameter of the guard, so the decision is made at compile time
+//! and a guard using [LeakPolicy] contains no panic machinery at all.
+//!
+//! The default [ConfiguredPolicy] instead applies the [DropPolicy] configured at runtime,
+//! for example through [PassableContainer::builder](crate::PassableContainer::builder).
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use passable_guard::{LeakPolicy, PassableContainer};
+//!
+//! let container = PassableContainer::new(CString::new("name").unwrap());
+//! let (guard, _ptr) = container.pass_with_policy::<LeakPolicy>();
+//!
+//! // Does not panic, the memory is silently leaked instead
+//! drop(guard);
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use crate::ChildInfo;
+#[cfg(feature = "diagnostics")]
+use crate::GuardInfo;
+
+/// Describes what would be leaked by a guard that is dropped while still armed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LeakInfo {
+    /// Describes the obligation that has not been fulfilled
+    pub message: &'static str,
+    /// The type name of the guarded value
+    pub type_name: &'static str,
+    /// The guarded pointer
+    pub ptr: *mut (),
+    /// The label of the guard, if any
+    pub label: Option<&'static str>,
+    /// The [DropPolicy] configured for the guard at runtime, if any
+    pub policy: Option<DropPolicy>,
+    /// How long the pointer had been passed for when the guard was dropped, if known
+    pub held_for: Option<Duration>,
+    /// The children of a [ParentGuard](crate::ParentGuard) that were still outstanding when it was dropped
+    pub children: Vec<ChildInfo>,
+    /// The armed guard a pointer handed to the dropped guard belonged to, if it was swapped with another recorded guard
+    #[cfg(feature = "diagnostics")]
+    pub swapped_with: Option<GuardInfo>
+}
+
+impl LeakInfo {
+    pub(crate) fn new<T: ?Sized, PTR>(message: &'static str, ptr: *mut PTR) -> Self {
+        Self {
+            message,
+            type_name: std::any::type_name::<T>(),
+            ptr: ptr as *mut (),
+            label: None,
+            policy: None,
+            held_for: None,
+            children: Vec::new(),
+            #[cfg(feature = "diagnostics")]
+            swapped_with: None
+        }
+    }
+}
+
+impl Display for LeakInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.label {
+            Some(label) => write!(f, "{} ({}: {} at {:p}", self.message, label, self.type_name, self.ptr)?,
+            None => write!(f, "{} ({} at {:p}", self.message, self.type_name, self.ptr)?
+        }
+        if let Some(held_for) = self.held_for {
+            write!(f, ", held for {:?}", held_for)?;
+        }
+        if !self.children.is_empty() {
+            let children = self.children
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, ", outstanding children: [{}]", children)?;
+        }
+        #[cfg(feature = "diagnostics")]
+        if let Some(owner) = &self.swapped_with {
+            write!(f, ", handed the pointer of {}", owner)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Decides what happens when a guard is dropped while still armed
+pub trait GuardPolicy {
+    /// Called when a guard is dropped while still armed
+    ///
+    /// `leak` describes the guarded value, calling `free` releases it
+    ///
+    /// ### Notes
+    /// The pointer has not been handed back, so the FFI might still be using it
+    fn armed_drop<F: FnOnce()>(leak: &LeakInfo, free: F);
+}
+
+/// Panic when an armed guard is dropped
+///
+/// If the thread is already panicking, the memory is leaked instead to avoid aborting the process
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PanicPolicy;
+
+impl GuardPolicy for PanicPolicy {
+    fn armed_drop<F: FnOnce()>(leak: &LeakInfo, _free: F) {
+        if !std::thread::panicking() {
+            panic!("{}", leak);
+        }
+    }
+}
+
+/// Silently leak the memory when an armed guard is dropped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeakPolicy;
+
+impl GuardPolicy for LeakPolicy {
+    #[inline(always)]
+    fn armed_drop<F: FnOnce()>(_leak: &LeakInfo, _free: F) {}
+}
+
+/// Leak the memory and report it to the registered [Reporter](crate::Reporter)s when an armed guard is dropped
+///
+/// Without a registered reporter, or without the `diagnostics` feature, the report is printed to stderr
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReportPolicy;
+
+impl GuardPolicy for ReportPolicy {
+    fn armed_drop<F: FnOnce()>(leak: &LeakInfo, _free: F) {
+        #[cfg(feature = "diagnostics")]
+        crate::reporter::leak_reported(leak);
+        #[cfg(not(feature = "diagnostics"))]
+        eprintln!("{}, leaking it", leak);
+    }
+}
+
+/// Abort the process when an armed guard is dropped
+///
+/// Unlike [PanicPolicy], this cannot be caught and is safe to use in code that must not unwind
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AbortPolicy;
+
+impl GuardPolicy for AbortPolicy {
+    fn armed_drop<F: FnOnce()>(_leak: &LeakInfo, _free: F) {
+        std::process::abort();
+    }
+}
+
+/// Release the guarded value when an armed guard is dropped, for a [Passable](crate::Passable) by reconstituting and dropping it
+///
+/// ### Notes
+/// This is only sound if the FFI is guaranteed to no longer use the pointer once the guard is dropped
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FreePolicy;
+
+impl GuardPolicy for FreePolicy {
+    fn armed_drop<F: FnOnce()>(_leak: &LeakInfo, free: F) {
+        free();
+    }
+}
+
+/// A drop policy that is chosen at runtime
+///
+/// Each variant behaves like the [GuardPolicy] of the same name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DropPolicy {
+    #[default]
+    Panic,
+    Leak,
+    Report,
+    Abort,
+    Free
+}
+
+impl DropPolicy {
+    /// Apply this policy to a guard that is dropped while still armed
+    pub fn apply<F: FnOnce()>(self, leak: &LeakInfo, free: F) {
+        match self {
+            DropPolicy::Panic => PanicPolicy::armed_drop(leak, free),
+            DropPolicy::Leak => LeakPolicy::armed_drop(leak, free),
+            DropPolicy::Report => ReportPolicy::armed_drop(leak, free),
+            DropPolicy::Abort => AbortPolicy::armed_drop(leak, free),
+            DropPolicy::Free => FreePolicy::armed_drop(leak, free)
+        }
+    }
+}
+
+/// Apply the [DropPolicy] configured for the guard at runtime,
+/// or the default policy of the global [Config](crate::Config) if there is none, which is [DropPolicy::Panic] unless configured otherwise
+///
+/// This is the default policy
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ConfiguredPolicy;
+
+impl GuardPolicy for ConfiguredPolicy {
+    fn armed_drop<F: FnOnce()>(leak: &LeakInfo, free: F) {
+        leak.policy
+            .unwrap_or_else(crate::config::default_policy)
+            .apply(leak, free);
+    }
+}
@@ -0,0 +1,244 @@
+//! Lending guarded buffers to Python through [PyO3](https://docs.rs/pyo3)
+//!
+//! [lend_to_python] hands Python a [PyLentBuffer], which exposes the buffer through the buffer protocol
+//! and as an `address` for ctypes, and keeps a [PyLoan] on the Rust side.
+//! Python signals it is done by calling `release()` or leaving a `with` block,
+//! only then can the loan be [reclaimed](PyLoan::reclaim).
+//! A loan that is dropped without being reclaimed is reported like any other armed guard,
+//! and a buffer cannot be released while a `memoryview` of it is still alive.
+//!
+//! ``` rust
+//! use pyo3::prelude::*;
+//! use passable_guard::{PassableContainer, PyLentBuffer, PyLoan};
+//!
+//! // Python runs `(ctypes.c_ubyte * buffer.len).from_address(buffer.address)` and then `buffer.release()`
+//! fn lend(py: Python<'_>, data: Vec<u8>) -> PyResult<(PyLoan<Vec<u8>>, Py<PyLentBuffer>)> {
+//!     passable_guard::lend_to_python(py, PassableContainer::new(data))
+//! }
+//!
+//! fn reclaim(loan: PyLoan<Vec<u8>>) -> Vec<u8> {
+//!     match loan.reclaim() {
+//!         Ok(container) => container.into_inner(),
+//!         Err(error) => panic!("{}", error)
+//!     }
+//! }
+//! ```
+
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::os::raw::{c_int, c_void};
+use std::rc::Rc;
+
+use pyo3::exceptions::{PyBufferError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::ffi;
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+#[derive(Debug, Default)]
+struct LoanState {
+    released: Cell<bool>,
+    exports: Cell<usize>
+}
+
+/// The Python side of a buffer lent with [lend_to_python]
+///
+/// Available to Python as `LentBuffer` with the read-only attributes `address`, `len` and `nbytes`,
+/// the buffer protocol, a `release()` method and context manager support
+#[pyclass(name = "LentBuffer", unsendable)]
+pub struct PyLentBuffer {
+    // Kept as a pointer, so the buffer protocol hands out a pointer with its provenance
+    ptr: *mut c_void,
+    len: usize,
+    nbytes: usize,
+    state: Rc<LoanState>
+}
+
+impl PyLentBuffer {
+    fn check_lent(&self) -> PyResult<()> {
+        if self.state.released.get() {
+            return Err(PyValueError::new_err("the buffer has already been released"));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyLentBuffer {
+    /// The address of the buffer, for example for `ctypes.from_address`
+    #[getter]
+    fn address(&self) -> PyResult<usize> {
+        self.check_lent()?;
+        // Python turns the address back into a pointer, so its provenance has to be exposed
+        Ok(self.ptr.expose_provenance())
+    }
+
+    /// The number of elements in the buffer
+    #[getter]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The size of the buffer in bytes
+    #[getter]
+    fn nbytes(&self) -> usize {
+        self.nbytes
+    }
+
+    /// Hand the buffer back to Rust, it must not be used by Python afterwards
+    fn release(&self) -> PyResult<()> {
+        if self.state.exports.get() > 0 {
+            return Err(PyBufferError::new_err("the buffer is still exported through a memoryview"));
+        }
+        self.state.released.set(true);
+        Ok(())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(&self, _type: &Bound<'_, PyAny>, _value: &Bound<'_, PyAny>, _traceback: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.release()?;
+        Ok(false)
+    }
+
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        let buffer = slf.borrow();
+        if buffer.state.released.get() {
+            return Err(PyBufferError::new_err("the buffer has already been released"));
+        }
+
+        let result = ffi::PyBuffer_FillInfo(view, slf.as_ptr(), buffer.ptr, buffer.nbytes as ffi::Py_ssize_t, 0, flags);
+        if result == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        buffer.state.exports.set(buffer.state.exports.get() + 1);
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {
+        self.state.exports.set(self.state.exports.get().saturating_sub(1));
+    }
+}
+
+/// The Rust side of a buffer lent with [lend_to_python], which has to be [reclaimed](PyLoan::reclaim) once Python released it
+pub struct PyLoan<PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    ptr: *mut PAS::Pointee,
+    state: Rc<LoanState>
+}
+
+impl<PAS: Passable> PyLoan<PAS> {
+    /// Check whether Python has released the buffer
+    pub fn is_released(&self) -> bool {
+        self.state.released.get()
+    }
+
+    /// Reconstitute the lent buffer once Python has released it
+    ///
+    /// ### Errors
+    /// Will return an Error containing this loan if Python has not released the buffer yet
+    /// Will return an Error if the memory was modified by Python
+    pub fn reclaim(self) -> Result<PassableContainer<PAS>, PyLoanError<PAS>> {
+        if !self.is_released() {
+            return Err(PyLoanError::StillLent {loan: self});
+        }
+
+        // The pointer is the one the guard was created for
+        unsafe { self.guard.reconstitute(self.ptr) }
+            .map_err(|error| PyLoanError::ReconstituteError {error})
+    }
+}
+
+impl<PAS: Passable> Debug for PyLoan<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PyLoan")
+            .field("guard", &self.guard)
+            .field("released", &self.is_released())
+            .finish()
+    }
+}
+
+/// An Error that can occur while reclaiming a buffer with [PyLoan::reclaim]
+pub enum PyLoanError<PAS: Passable> {
+    /// Python has not released the buffer yet, the loan is handed back so it can be reclaimed later
+    StillLent{loan: PyLoan<PAS>},
+    ReconstituteError{error: ReconstituteError<PAS>}
+}
+
+impl<PAS: Passable> PyLoanError<PAS> {
+    /// Get back the loan if Python has not released the buffer yet
+    pub fn into_loan(self) -> Option<PyLoan<PAS>> {
+        match self {
+            PyLoanError::StillLent {loan} => Some(loan),
+            PyLoanError::ReconstituteError {..} => None
+        }
+    }
+}
+
+impl<PAS: Passable> Debug for PyLoanError<PAS> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PyLoanError::StillLent {loan} => f.debug_struct("StillLent")
+                .field("loan", loan)
+                .finish(),
+            PyLoanError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish()
+        }
+    }
+}
+
+impl<PAS: Passable> Display for PyLoanError<PAS> where PAS::ReconstituteError: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PyLoanError::StillLent {loan} =>
+                write!(f, "the buffer at {:p} is still lent to Python", loan.ptr),
+            PyLoanError::ReconstituteError {error} =>
+                write!(f, "could not reclaim the buffer: {}", error)
+        }
+    }
+}
+
+impl<PAS: Passable + 'static> Error for PyLoanError<PAS> where PAS::ReconstituteError: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PyLoanError::StillLent {..} => None,
+            PyLoanError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+/// Lend the buffer of `container` to Python, returning the loan to reclaim it and the object to hand to Python
+///
+/// ### Errors
+/// Will return an Error if the Python object could not be created, the container is dropped in that case
+#[track_caller]
+pub fn lend_to_python<PAS: PassableLen>(py: Python<'_>, container: PassableContainer<PAS>) -> PyResult<(PyLoan<PAS>, Py<PyLentBuffer>)> {
+    let (guard, ptr, len) = container.pass_with_len();
+    let state = Rc::new(LoanState::default());
+
+    let buffer = PyLentBuffer {
+        ptr: ptr as *mut c_void,
+        len,
+        nbytes: PAS::passed_extent(&guard.metadata).unwrap_or(len * std::mem::size_of::<PAS::Pointee>()),
+        state: state.clone()
+    };
+    let loan = PyLoan {
+        guard,
+        ptr,
+        state
+    };
+
+    match Py::new(py, buffer) {
+        Ok(buffer) => Ok((loan, buffer)),
+        Err(error) => {
+            // Python never saw the buffer, so it can be reclaimed right away
+            loan.state.released.set(true);
+            drop(loan.reclaim());
+            Err(error)
+        }
+    }
+}
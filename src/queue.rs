@@ -0,0 +1,205 @@
+//! Returning pointers from threads where no real work may be done
+//!
+//! Some libraries run their completion callbacks on internal threads or even in signal handlers,
+//! where locking, allocating or dropping a [Passable] is not allowed.
+//! A [ReturnQueue] keeps the guards of everything passed through it, the callbacks only push the returned pointers
+//! with a few atomic operations, and a Rust thread drains the queue and does the actual reconstitution
+//!
+//! ``` rust
+//! use std::os::raw::c_void;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//! use passable_guard::{PassableContainer, ReturnQueue};
+//!
+//! let queue = Arc::new(ReturnQueue::<Vec<u8>>::new(16));
+//!
+//! let ptr = queue.pass(PassableContainer::new(vec![0u8; 64])).unwrap();
+//!
+//! // The library calls this from its own thread once it is done with the buffer
+//! unsafe { (ReturnQueue::<Vec<u8>>::PUSH)(queue.as_context(), ptr as *mut c_void) };
+//!
+//! let drain = {
+//!     let queue = queue.clone();
+//!     std::thread::spawn(move || queue.run(Duration::from_millis(1), |buffer| assert_eq!(buffer.unwrap().len(), 64)))
+//! };
+//! queue.close();
+//! drain.join().unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::os::raw::c_void;
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A queue of returned pointers that is filled from any thread and drained by Rust
+///
+/// ### Panic
+/// Guards that are still in the queue when it is dropped are dropped while still armed and apply their policy
+pub struct ReturnQueue<PAS: Passable> {
+    slots: Box<[AtomicPtr<c_void>]>,
+    // Passables that share an address, like empty Vecs, are kept side by side
+    guards: Mutex<HashMap<usize, Vec<PassableGuard<PAS>>>>,
+    closed: AtomicBool
+}
+
+// The guards only hold the pointers and metadata of Passables that are Send, and are only used behind the mutex,
+// the slots are atomics, so the queue can be shared with the threads of the library and the draining thread
+unsafe impl<PAS: Passable + Send> Send for ReturnQueue<PAS> {}
+unsafe impl<PAS: Passable + Send> Sync for ReturnQueue<PAS> {}
+
+impl<PAS: Passable> ReturnQueue<PAS> {
+    /// The function to hand to the library with [ReturnQueue::as_context], `bool (*)(const void *queue, void *ptr)`
+    ///
+    /// Returns whether the pointer could be queued, see [ReturnQueue::push]
+    pub const PUSH: unsafe extern "C" fn(*const c_void, *mut c_void) -> bool = push_raw::<PAS>;
+
+    /// Create a queue that can hold up to `capacity` passed [Passable]s at once
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity).map(|_| AtomicPtr::new(null_mut())).collect(),
+            guards: Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false)
+        }
+    }
+
+    fn guards(&self) -> MutexGuard<'_, HashMap<usize, Vec<PassableGuard<PAS>>>> {
+        self.guards.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the pointer to hand to the library as the context of [ReturnQueue::PUSH]
+    ///
+    /// The queue must not be moved or dropped while the library still holds the pointer, keep it in an `Arc`
+    pub fn as_context(&self) -> *const c_void {
+        self as *const Self as *const c_void
+    }
+
+    /// Convert the [PassableContainer] into a pointer, keeping its guard in the queue until the pointer is pushed and drained
+    ///
+    /// Containers whose pointers share an address, like empty [Vec]s, can be passed at the same time,
+    /// every pointer pushed with that address reconstitutes one of them
+    ///
+    /// ``` rust
+    /// use passable_guard::{PassableContainer, ReturnQueue};
+    ///
+    /// let queue = ReturnQueue::<Vec<u8>>::new(2);
+    /// let first = queue.pass(PassableContainer::new(Vec::new())).unwrap();
+    /// let second = queue.pass(PassableContainer::new(Vec::new())).unwrap();
+    /// assert_eq!(first, second);
+    /// assert_eq!(queue.outstanding(), 2);
+    ///
+    /// unsafe { queue.push(first) };
+    /// unsafe { queue.push(second) };
+    /// assert_eq!(queue.drain().len(), 2);
+    /// ```
+    ///
+    /// ### Errors
+    /// Returns the container if the queue already holds `capacity` guards,
+    /// so every pushed pointer is guaranteed to find a free slot
+    #[track_caller]
+    pub fn pass(&self, container: PassableContainer<PAS>) -> Result<*mut PAS::Pointee, PassableContainer<PAS>> {
+        let mut guards = self.guards();
+        if count(&guards) >= self.slots.len() {
+            return Err(container);
+        }
+        let (guard, ptr) = container.pass();
+        guards.entry(ptr.addr()).or_default().push(guard);
+        Ok(ptr)
+    }
+
+    /// Queue a pointer the library has returned
+    ///
+    /// This only uses atomic operations, so it is async-signal-safe and can be called from any thread.
+    /// Returns false if every slot is taken, which only happens if pointers are pushed more than once
+    ///
+    /// ### Safety
+    /// The FFI must no longer use the pointer, it is reconstituted by the next [ReturnQueue::drain]
+    pub unsafe fn push(&self, ptr: *mut PAS::Pointee) -> bool {
+        self.slots.iter().any(|slot| {
+            slot.compare_exchange(null_mut(), ptr as *mut c_void, Ordering::AcqRel, Ordering::Relaxed).is_ok()
+        })
+    }
+
+    /// Reconstitute every queued pointer, in no particular order
+    ///
+    /// Pointers that were not passed through this queue are reported to the registered [Reporter](crate::Reporter)s and ignored,
+    /// or printed to stderr without the `diagnostics` feature
+    pub fn drain(&self) -> Vec<Result<PassableContainer<PAS>, ReconstituteError<PAS>>> {
+        let mut drained = Vec::new();
+        for slot in self.slots.iter() {
+            let ptr = slot.swap(null_mut(), Ordering::AcqRel);
+            if ptr.is_null() {
+                continue;
+            }
+            // Take the guard out before reconstituting, so its policy is never applied with the lock held
+            let guard = take(&mut self.guards(), ptr.addr());
+            match guard {
+                // Pushing a pointer asserts that the FFI is done with it
+                Some(guard) => drained.push(unsafe { guard.reconstitute_addr(ptr.addr()) }),
+                #[cfg(feature = "diagnostics")]
+                None => crate::reporter::unknown_pointer_returned(ptr as *mut ()),
+                #[cfg(not(feature = "diagnostics"))]
+                None => eprintln!("Return Queue: {:p} was not passed through this queue", ptr)
+            }
+        }
+        drained
+    }
+
+    /// Drain the queue every `interval` and call `f` with every reconstituted [Passable], until the queue is [closed](ReturnQueue::close)
+    ///
+    /// After the queue is closed, the loop keeps draining until every guard has been reconstituted
+    pub fn run<F: FnMut(Result<PassableContainer<PAS>, ReconstituteError<PAS>>)>(&self, interval: Duration, mut f: F) {
+        loop {
+            // Check before draining, so nothing pushed before the queue was closed is missed
+            let closed = self.closed.load(Ordering::Acquire);
+            self.drain().into_iter().for_each(&mut f);
+            if closed && self.outstanding() == 0 {
+                return;
+            }
+            std::thread::sleep(interval);
+        }
+    }
+
+    /// Make [ReturnQueue::run] return once every guard has been reconstituted
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+
+    /// Get the number of passed [Passable]s that have not been drained yet
+    pub fn outstanding(&self) -> usize {
+        count(&self.guards())
+    }
+}
+
+/// Count the guards kept for every address
+fn count<PAS: Passable>(guards: &HashMap<usize, Vec<PassableGuard<PAS>>>) -> usize {
+    guards.values().map(Vec::len).sum()
+}
+
+/// Take one of the guards kept for an address, they are interchangeable since they share the address
+fn take<PAS: Passable>(guards: &mut HashMap<usize, Vec<PassableGuard<PAS>>>, addr: usize) -> Option<PassableGuard<PAS>> {
+    let shared = guards.get_mut(&addr)?;
+    let guard = shared.pop();
+    if shared.is_empty() {
+        guards.remove(&addr);
+    }
+    guard
+}
+
+unsafe extern "C" fn push_raw<PAS: Passable>(queue: *const c_void, ptr: *mut c_void) -> bool {
+    (*(queue as *const ReturnQueue<PAS>)).push(ptr as *mut PAS::Pointee)
+}
+
+impl<PAS: Passable> Debug for ReturnQueue<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReturnQueue")
+            .field("capacity", &self.slots.len())
+            .field("outstanding", &self.outstanding())
+            .field("closed", &self.closed.load(Ordering::Relaxed))
+            .finish()
+    }
+}
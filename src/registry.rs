@@ -0,0 +1,556 @@
+//! Global registry of armed guards
+//!
+//! The registry is opt-in. Once it has been enabled with [enable],
+//! every newly created guard is recorded until it is dropped, reconstituted or not.
+//! [active] answers the question what is currently lent out to the FFI.
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use passable_guard::{registry, PassableContainer};
+//!
+//! registry::enable();
+//!
+//! let container = PassableContainer::builder(CString::new("name").unwrap())
+//!     .label("names")
+//!     .build();
+//! let (guard, ptr) = container.pass();
+//!
+//! let active = registry::active();
+//! assert!(active.iter().any(|record| record.label == Some("names")));
+//!
+//! unsafe { guard.reconstitute(ptr) }.unwrap();
+//! assert!(registry::active().iter().all(|record| record.label != Some("names")));
+//! ```
+//!
+//! With the `tokio` feature, every record also knows the tokio task it was created in,
+//! since the thread of a runtime worker says little about which part of an async program lent a pointer out.
+//! Tasks can be given a name for the records with [with_task_name]
+
+use std::backtrace::Backtrace;
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+
+use crate::atomic::AtomicU64;
+use crate::GuardInfo;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SAMPLE_RATE: AtomicU64 = AtomicU64::new(1);
+static RECORDS: Mutex<BTreeMap<u64, GuardRecord>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "tokio")]
+tokio::task_local! {
+    static TASK_NAME: &'static str;
+}
+
+/// A guard that was armed while the registry was enabled
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct GuardRecord {
+    /// The id of the guard
+    pub id: u64,
+    /// The type name of the guarded [Passable](crate::Passable)
+    pub type_name: &'static str,
+    /// The passed pointer
+    pub ptr: *mut (),
+    /// The size of the passed buffer in bytes, if it is known
+    pub size: Option<usize>,
+    /// The label of the guard, if any
+    pub label: Option<&'static str>,
+    /// When the guard was created, or [None] if the target has no clock, like `wasm32-unknown-unknown`
+    pub created: Option<Instant>,
+    /// Where the guard was created
+    pub location: &'static Location<'static>,
+    /// The thread the guard was created on
+    pub thread_id: ThreadId,
+    /// The name of the thread the guard was created on, if any
+    pub thread_name: Option<String>,
+    /// The tokio task the guard was created in, if it was created in one
+    #[cfg(feature = "tokio")]
+    pub task_id: Option<tokio::task::Id>,
+    /// The name given to the tokio task the guard was created in with [with_task_name], if any
+    #[cfg(feature = "tokio")]
+    pub task_name: Option<&'static str>,
+    /// The backtrace of the creation of the guard, if [Config::capture_backtraces](crate::Config::capture_backtraces) is set
+    pub backtrace: Option<Arc<Backtrace>>
+}
+
+// The pointer is only kept for diagnostics and is never dereferenced
+unsafe impl Send for GuardRecord {}
+unsafe impl Sync for GuardRecord {}
+
+impl GuardRecord {
+    /// Get how long the guard has been armed for, if the target has a clock
+    pub fn held_for(&self) -> Option<Duration> {
+        self.created.map(|created| created.elapsed())
+    }
+
+    pub(crate) fn info(&self) -> GuardInfo {
+        GuardInfo {
+            id: self.id,
+            type_name: self.type_name,
+            ptr: self.ptr,
+            label: self.label,
+            held_for: self.held_for()
+        }
+    }
+}
+
+impl Display for GuardRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.label {
+            Some(label) => write!(f, "#{} {}: {} at {:p}, passed at {}", self.id, label, self.type_name, self.ptr, self.location)?,
+            None => write!(f, "#{} {} at {:p}, passed at {}", self.id, self.type_name, self.ptr, self.location)?
+        }
+        match &self.thread_name {
+            Some(name) => write!(f, " on thread {}", name)?,
+            None => write!(f, " on thread {:?}", self.thread_id)?
+        }
+        #[cfg(feature = "tokio")]
+        match (self.task_name, self.task_id) {
+            (Some(name), Some(id)) => write!(f, " in task {} ({})", name, id)?,
+            (Some(name), None) => write!(f, " in task {}", name)?,
+            (None, Some(id)) => write!(f, " in task {}", id)?,
+            (None, None) => {}
+        }
+        Ok(())
+    }
+}
+
+fn records() -> MutexGuard<'static, BTreeMap<u64, GuardRecord>> {
+    RECORDS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Start recording every newly created guard
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording newly created guards
+///
+/// Guards that have already been recorded stay in the registry until they are dropped
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+/// Check whether newly created guards are recorded
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Only fully track one in every `every` guards, the others skip timing and registration
+///
+/// This keeps the overhead low enough to leave the registry enabled on hot paths in production.
+/// A rate of 0 or 1 tracks every guard, which is the default
+///
+/// ``` rust
+/// use passable_guard::registry;
+///
+/// registry::enable();
+/// registry::set_sample_rate(100);
+/// ```
+pub fn set_sample_rate(every: u64) {
+    SAMPLE_RATE.store(every.max(1), Ordering::Relaxed);
+}
+
+pub(crate) fn is_sampled(id: u64) -> bool {
+    id % SAMPLE_RATE.load(Ordering::Relaxed) == 0
+}
+
+/// Get all recorded guards that are still alive, in the order they were created
+pub fn active() -> Vec<GuardRecord> {
+    records().values()
+        .cloned()
+        .collect()
+}
+
+/// Get the record of the guard with the given [id](crate::PassableGuard::id), if it is still alive
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{registry, PassableContainer};
+///
+/// registry::enable();
+///
+/// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+/// let id = guard.id();
+///
+/// assert_eq!(registry::find(id).unwrap().ptr, ptr as *mut ());
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// assert!(registry::find(id).is_none());
+/// ```
+pub fn find(id: u64) -> Option<GuardRecord> {
+    records().get(&id).cloned()
+}
+
+/// Get the record of the armed guard that passed the given pointer, if it is recorded
+pub fn find_by_ptr(ptr: *mut ()) -> Option<GuardRecord> {
+    records().values()
+        .find(|record| record.ptr.addr() == ptr.addr())
+        .cloned()
+}
+
+/// Two recorded guards whose pointers were mixed up, because one was handed the pointer of the other
+///
+/// ``` rust
+/// use passable_guard::{registry, PassableContainer, ReconstituteError};
+///
+/// registry::enable();
+///
+/// let (input, input_ptr) = PassableContainer::builder(vec![0u8; 16]).label("input").build().pass();
+/// let (output, output_ptr) = PassableContainer::builder(vec![0u8; 16]).label("output").build().pass();
+///
+/// match input.check(output_ptr) {
+///     Err(ReconstituteError::PointerSwapped {swap, ..}) => assert_eq!(swap.owner.label, Some("output")),
+///     _ => unreachable!()
+/// }
+///
+/// unsafe { input.reconstitute(input_ptr) }.unwrap();
+/// unsafe { output.reconstitute(output_ptr) }.unwrap();
+/// ```
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct PointerSwap {
+    /// The record of the guard that was handed the wrong pointer, or [None] if it was not recorded
+    pub guard: Option<GuardRecord>,
+    /// The record of the armed guard the pointer belongs to
+    pub owner: GuardRecord
+}
+
+impl Display for PointerSwap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.guard {
+            Some(guard) => write!(f, "handed to {} but belonging to {}", guard, self.owner),
+            None => write!(f, "belonging to {}", self.owner)
+        }
+    }
+}
+
+/// Look for another armed guard the mismatched address belongs to
+pub(crate) fn find_swap(id: u64, addr: usize) -> Option<PointerSwap> {
+    let records = records();
+    let owner = records.values()
+        .find(|record| record.id != id && record.ptr.addr() == addr)?
+        .clone();
+    Some(PointerSwap {
+        guard: records.get(&id).cloned(),
+        owner
+    })
+}
+
+/// Get a [Report] of all recorded guards that are still alive, which can be filtered and grouped
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{registry, PassableContainer};
+///
+/// registry::enable();
+///
+/// let (guard, ptr) = PassableContainer::builder(vec![0u8; 32])
+///     .label("sqlite")
+///     .build()
+///     .pass();
+///
+/// let sqlite = registry::report().by_label("sqlite");
+/// assert_eq!(sqlite.len(), 1);
+/// assert_eq!(sqlite.bytes(), 32);
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// ```
+pub fn report() -> Report {
+    Report {
+        records: active()
+    }
+}
+
+/// A snapshot of recorded guards that can be filtered and grouped by label or type name
+///
+/// Created by [report]
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    records: Vec<GuardRecord>
+}
+
+impl Report {
+    /// Get the guards in this report, in the order they were created
+    pub fn records(&self) -> &[GuardRecord] {
+        &self.records
+    }
+
+    /// Get the number of guards in this report
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Check whether this report contains no guards
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Get the total size of the passed buffers in this report, as far as it is known
+    pub fn bytes(&self) -> usize {
+        self.records.iter()
+            .filter_map(|record| record.size)
+            .sum()
+    }
+
+    /// Only keep the guards with the given label
+    pub fn by_label(&self, label: &str) -> Report {
+        self.filter(|record| record.label == Some(label))
+    }
+
+    /// Only keep the guards of the given [Passable](crate::Passable) type name
+    pub fn by_type(&self, type_name: &str) -> Report {
+        self.filter(|record| record.type_name == type_name)
+    }
+
+    /// Only keep the guards created in the tokio task with the given id
+    #[cfg(feature = "tokio")]
+    pub fn by_task(&self, id: tokio::task::Id) -> Report {
+        self.filter(|record| record.task_id == Some(id))
+    }
+
+    /// Only keep the guards matching `predicate`
+    pub fn filter<F: Fn(&GuardRecord) -> bool>(&self, predicate: F) -> Report {
+        Report {
+            records: self.records.iter()
+                .filter(|record| predicate(record))
+                .cloned()
+                .collect()
+        }
+    }
+
+    /// Split this report by the label of the guards
+    pub fn group_by_label(&self) -> BTreeMap<Option<&'static str>, Report> {
+        self.group_by(|record| record.label)
+    }
+
+    /// Split this report by the [Passable](crate::Passable) type name of the guards
+    pub fn group_by_type(&self) -> BTreeMap<&'static str, Report> {
+        self.group_by(|record| record.type_name)
+    }
+
+    fn group_by<K: Ord, F: Fn(&GuardRecord) -> K>(&self, key: F) -> BTreeMap<K, Report> {
+        let mut groups: BTreeMap<K, Report> = BTreeMap::new();
+        for record in &self.records {
+            groups.entry(key(record))
+                .or_default()
+                .records
+                .push(record.clone());
+        }
+        groups
+    }
+}
+
+impl IntoIterator for Report {
+    type Item = GuardRecord;
+    type IntoIter = std::vec::IntoIter<GuardRecord>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} Passable Guards, {} bytes:", self.len(), self.bytes())?;
+        for record in &self.records {
+            writeln!(f, "    {}", record)?;
+        }
+        Ok(())
+    }
+}
+
+/// Get all recorded guards that are still alive and were created on the current thread, in the order they were created
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{registry, PassableContainer};
+///
+/// registry::enable();
+///
+/// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+///
+/// let other = std::thread::spawn(registry::report_for_current_thread).join().unwrap();
+/// assert!(other.is_empty());
+/// assert_eq!(registry::report_for_current_thread().len(), 1);
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// ```
+pub fn report_for_current_thread() -> Vec<GuardRecord> {
+    let current = std::thread::current().id();
+    records().values()
+        .filter(|record| record.thread_id == current)
+        .cloned()
+        .collect()
+}
+
+/// Get all recorded guards that are still alive and were created in the current tokio task, in the order they were created
+///
+/// Outside of a task, this is empty
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{registry, PassableContainer};
+///
+/// registry::enable();
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let task = tokio::spawn(registry::with_task_name("sqlite", async {
+///     let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+///
+///     let records = registry::report_for_current_task();
+///     assert_eq!(records.len(), 1);
+///     assert_eq!(records[0].task_name, Some("sqlite"));
+///
+///     unsafe { guard.reconstitute(ptr) }.unwrap();
+/// }));
+/// task.await.unwrap();
+/// # });
+/// ```
+#[cfg(feature = "tokio")]
+pub fn report_for_current_task() -> Vec<GuardRecord> {
+    let current = match tokio::task::try_id() {
+        Some(current) => current,
+        None => return Vec::new()
+    };
+    records().values()
+        .filter(|record| record.task_id == Some(current))
+        .cloned()
+        .collect()
+}
+
+/// Run `future` with `name` as the task name of every guard created while it is polled
+///
+/// The name is visible in the records and reports of those guards, next to the id of the task
+#[cfg(feature = "tokio")]
+pub fn with_task_name<F: std::future::Future>(name: &'static str, future: F) -> tokio::task::futures::TaskLocalFuture<&'static str, F> {
+    TASK_NAME.scope(name, future)
+}
+
+/// Get the id and name of the tokio task that is currently running, if any
+#[cfg(feature = "tokio")]
+pub(crate) fn current_task() -> (Option<tokio::task::Id>, Option<&'static str>) {
+    (tokio::task::try_id(), TASK_NAME.try_with(|name| *name).ok())
+}
+
+pub(crate) fn register(record: GuardRecord) {
+    if is_enabled() {
+        records().insert(record.id, record);
+    }
+}
+
+pub(crate) fn unregister(id: u64) {
+    records().remove(&id);
+}
+
+type ExitReporter = Box<dyn Fn(&[GuardRecord]) + Send>;
+
+static EXIT_REPORTER: Mutex<Option<ExitReporter>> = Mutex::new(None);
+
+#[cfg(not(any(all(target_family = "wasm", target_os = "unknown"), miri)))]
+extern "C" {
+    fn atexit(callback: extern "C" fn()) -> std::os::raw::c_int;
+}
+
+#[cfg(not(any(all(target_family = "wasm", target_os = "unknown"), miri)))]
+extern "C" fn report_on_exit() {
+    use std::sync::TryLockError;
+
+    // Another thread can still hold a lock when the process exits, waiting for it would hang the exit
+    fn try_lock<T>(mutex: &Mutex<T>) -> Option<MutexGuard<'_, T>> {
+        match mutex.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None
+        }
+    }
+
+    // Unwinding out of an atexit handler would abort the process
+    let _ = std::panic::catch_unwind(|| {
+        let active = match try_lock(&RECORDS) {
+            Some(records) => records.values().cloned().collect::<Vec<_>>(),
+            None => {
+                eprintln!("Passable Guard registry busy at exit, armed guards are not reported");
+                return;
+            }
+        };
+        if let Some(reporter) = try_lock(&EXIT_REPORTER).as_deref().and_then(Option::as_ref) {
+            reporter(&active);
+        }
+    });
+}
+
+/// Print every guard that is still armed when the process exits normally to stderr
+///
+/// This enables the registry, since only recorded guards can be reported
+///
+/// ``` rust
+/// passable_guard::registry::report_at_exit();
+/// ```
+pub fn report_at_exit() {
+    report_at_exit_with(|active| print_records("still armed at exit", active));
+}
+
+/// Call `reporter` with every guard that is still armed when the process exits normally
+///
+/// Calling this again replaces the previous reporter.
+/// This enables the registry, since only recorded guards can be reported
+///
+/// ### Notes
+/// On `wasm32-unknown-unknown` the module never exits, so the reporter is never called.
+/// Call [report] from an export of the module instead.
+/// Miri cannot register exit handlers, so under Miri the reporter is never called either.
+/// If another thread holds the registry while the process exits, the report is skipped instead of waiting for it
+pub fn report_at_exit_with<F: Fn(&[GuardRecord]) + Send + 'static>(reporter: F) {
+    enable();
+    *EXIT_REPORTER.lock().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(reporter));
+
+    #[cfg(not(any(all(target_family = "wasm", target_os = "unknown"), miri)))]
+    {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+
+        INSTALL.call_once(|| unsafe {
+            atexit(report_on_exit);
+        });
+    }
+}
+
+/// Install a panic hook that appends a dump of all currently armed guards to the output of any panic
+///
+/// The previously installed hook is still called first.
+/// This enables the registry, since only recorded guards can be dumped
+///
+/// ``` rust
+/// passable_guard::install_panic_hook();
+/// ```
+pub fn install_panic_hook() {
+    enable();
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        previous(info);
+
+        // A panicking guard removes itself from the registry before its policy is applied
+        print_records("armed at the time of the panic", &active());
+    }));
+}
+
+fn print_records(situation: &str, records: &[GuardRecord]) {
+    if records.is_empty() {
+        return;
+    }
+
+    eprintln!("{} Passable Guards {}:", records.len(), situation);
+    for record in records {
+        eprintln!("    {}", record);
+        if let Some(backtrace) = &record.backtrace {
+            eprintln!("{}", backtrace);
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Pluggable sinks for diagnostic reports
+//!
+//! Leak reports of [ReportPolicy](crate::ReportPolicy), alerts of the [watchdog](crate::enable_watchdog),
+//! failures of dropped [PassScope](crate::PassScope)s, unknown pointers pushed to a [ReturnQueue](crate::ReturnQueue) and snapshots sent with [report_stats] are handed to every [Reporter] registered with [add_reporter].
+//! As long as none is registered, the [StderrReporter] is used.
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use passable_guard::{LeakInfo, PassableContainer, ReportPolicy, Reporter};
+//!
+//! static LEAKS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! struct CountingReporter;
+//!
+//! impl Reporter for CountingReporter {
+//!     fn leak_reported(&self, _leak: &LeakInfo) {
+//!         LEAKS.fetch_add(1, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! passable_guard::add_reporter(CountingReporter);
+//!
+//! let container = PassableContainer::new(CString::new("name").unwrap());
+//! let (guard, _ptr) = container.pass_with_policy::<ReportPolicy>();
+//! drop(guard);
+//!
+//! assert_eq!(LEAKS.load(Ordering::Relaxed), 1);
+//! passable_guard::clear_reporters();
+//! ```
+
+use std::sync::{PoisonError, RwLock};
+
+use crate::{GuardInfo, LeakInfo, ScopeFailure, Stats};
+
+static REPORTERS: RwLock<Vec<Box<dyn Reporter>>> = RwLock::new(Vec::new());
+
+/// A sink for diagnostic reports
+///
+/// Every method does nothing by default, so a reporter only has to implement the reports it is interested in
+///
+/// ### Notes
+/// The methods are called synchronously from the code that caused the report, including drop implementations,
+/// so they should be fast and must not panic
+pub trait Reporter: Send + Sync {
+    /// Called when a guard is dropped while still armed and its policy reports the leak
+    fn leak_reported(&self, _leak: &LeakInfo) {}
+
+    /// Called with a snapshot of the runtime statistics when [report_stats] is called
+    fn stats_snapshot(&self, _stats: &Stats) {}
+
+    /// Called when the [watchdog](crate::enable_watchdog) finds a guard held longer than its threshold
+    fn watchdog_alert(&self, _guard: &GuardInfo) {}
+
+    /// Called for every [Passable](crate::Passable) a [PassScope](crate::PassScope) could not reconstitute when it was dropped
+    fn scope_failed(&self, _failure: &ScopeFailure) {}
+
+    /// Called when a [ReturnQueue](crate::ReturnQueue) drains a pointer that was not passed through it
+    fn unknown_pointer_returned(&self, _ptr: *mut ()) {}
+}
+
+/// Print every report to stderr
+///
+/// This is used as long as no other [Reporter] is registered
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrReporter;
+
+impl Reporter for StderrReporter {
+    fn leak_reported(&self, leak: &LeakInfo) {
+        eprintln!("{}, leaking it", leak);
+    }
+
+    fn stats_snapshot(&self, stats: &Stats) {
+        eprintln!("{:?}", stats);
+    }
+
+    fn watchdog_alert(&self, guard: &GuardInfo) {
+        match guard.held_for {
+            Some(held_for) => eprintln!("Passable Guard {} has been held for {:?}", guard, held_for),
+            None => eprintln!("Passable Guard {} has been held too long", guard)
+        }
+    }
+
+    fn scope_failed(&self, failure: &ScopeFailure) {
+        eprintln!("Pass Scope dropped: {}", failure);
+    }
+
+    fn unknown_pointer_returned(&self, ptr: *mut ()) {
+        eprintln!("Return Queue: {:p} was not passed through this queue", ptr);
+    }
+}
+
+/// Log every report through the [log] crate, with the target `passable_guard`
+///
+/// Leaks are logged as errors, watchdog alerts as warnings and statistics as info
+#[cfg(feature = "log")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogReporter;
+
+#[cfg(feature = "log")]
+impl Reporter for LogReporter {
+    fn leak_reported(&self, leak: &LeakInfo) {
+        log::error!(target: "passable_guard", "{}, leaking it", leak);
+    }
+
+    fn stats_snapshot(&self, stats: &Stats) {
+        log::info!(target: "passable_guard", "{:?}", stats);
+    }
+
+    fn watchdog_alert(&self, guard: &GuardInfo) {
+        log::warn!(target: "passable_guard", "guard held longer than the watchdog threshold ({}, held for {:?})", guard, guard.held_for);
+    }
+
+    fn scope_failed(&self, failure: &ScopeFailure) {
+        log::error!(target: "passable_guard", "Pass Scope dropped: {}", failure);
+    }
+
+    fn unknown_pointer_returned(&self, ptr: *mut ()) {
+        log::error!(target: "passable_guard", "Return Queue: {:p} was not passed through this queue", ptr);
+    }
+}
+
+/// Register `reporter` to receive every report, in addition to the ones registered before
+///
+/// Once a reporter is registered, the [StderrReporter] is no longer used unless it is registered explicitly
+pub fn add_reporter<R: Reporter + 'static>(reporter: R) {
+    REPORTERS.write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .push(Box::new(reporter));
+}
+
+/// Remove every reporter registered with [add_reporter], falling back to the [StderrReporter]
+pub fn clear_reporters() {
+    REPORTERS.write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+/// Send a snapshot of the [stats](crate::stats) to every registered [Reporter]
+///
+/// ``` rust
+/// passable_guard::report_stats();
+/// ```
+pub fn report_stats() {
+    let stats = crate::stats();
+    dispatch(|reporter| reporter.stats_snapshot(&stats));
+}
+
+pub(crate) fn leak_reported(leak: &LeakInfo) {
+    dispatch(|reporter| reporter.leak_reported(leak));
+}
+
+pub(crate) fn watchdog_alert(guard: &GuardInfo) {
+    dispatch(|reporter| reporter.watchdog_alert(guard));
+}
+
+pub(crate) fn scope_failed(failure: &ScopeFailure) {
+    dispatch(|reporter| reporter.scope_failed(failure));
+}
+
+pub(crate) fn unknown_pointer_returned(ptr: *mut ()) {
+    dispatch(|reporter| reporter.unknown_pointer_returned(ptr));
+}
+
+fn dispatch<F: Fn(&dyn Reporter)>(report: F) {
+    let reporters = REPORTERS.read().unwrap_or_else(PoisonError::into_inner);
+    if reporters.is_empty() {
+        report(&StderrReporter);
+    }
+    for reporter in reporters.iter() {
+        report(reporter.as_ref());
+    }
+}
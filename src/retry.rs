@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// An Error that can occur while reconstituting with [PassableGuard::reconstitute_or_retry]
+pub enum RetryError<PAS: Passable, P: GuardPolicy> {
+    /// The pointer did not match, the still armed guard is handed back so it can be reconstituted with the correct pointer
+    PointerMismatch{guard: PassableGuard<PAS, P>, reconstituted: *mut PAS::Pointee},
+    ReconstituteError{error: PAS::ReconstituteError}
+}
+
+impl<PAS: Passable, P: GuardPolicy> RetryError<PAS, P> {
+    /// Get back the still armed guard if the pointer did not match
+    pub fn into_guard(self) -> Option<PassableGuard<PAS, P>> {
+        match self {
+            RetryError::PointerMismatch {guard, ..} => Some(guard),
+            RetryError::ReconstituteError {..} => None
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Debug for RetryError<PAS, P> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryError::PointerMismatch {guard, reconstituted} => f.debug_struct("PointerMismatch")
+                .field("guard", guard)
+                .field("reconstituted", reconstituted)
+                .finish(),
+            RetryError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish()
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Display for RetryError<PAS, P> where PAS::ReconstituteError: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryError::PointerMismatch {guard, reconstituted} =>
+                write!(f, "pointer mismatch, passed {:p} but reconstituted {:p}", guard.ptr, reconstituted),
+            RetryError::ReconstituteError {error} =>
+                write!(f, "could not reconstitute the passable: {}", error)
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Error for RetryError<PAS, P> where PAS::ReconstituteError: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RetryError::PointerMismatch {..} => None,
+            RetryError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Reconstitute a raw pointer back into a [PassableContainer], handing back the still armed guard if the pointer does not match
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+    ///
+    /// let error = unsafe { guard.reconstitute_or_retry(std::ptr::null_mut()) }.unwrap_err();
+    /// eprintln!("{}", error);
+    ///
+    /// // Try again with the correct pointer
+    /// let guard = error.into_guard().unwrap();
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error containing this guard if the pointer points do a different memory address then the pointer that was originally created by the pass method of the Container
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute_or_retry(self, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, RetryError<PAS, P>> {
+        if self.check(ptr).is_err() {
+            return Err(
+                RetryError::PointerMismatch {
+                    guard: self,
+                    reconstituted: ptr
+                }
+            );
+        }
+
+        let ptr = self.ptr;
+        self.reconstitute_unchecked(ptr)
+            .map_err(
+                |error| match error {
+                    ReconstituteError::ReconstituteError {error} => RetryError::ReconstituteError {error},
+                    ReconstituteError::PointerMismatch {..} => unreachable!("The pointer has already been checked"),
+                    ReconstituteError::ContentModified {..} => unreachable!("Only a ChecksumGuard verifies the content"),
+                    ReconstituteError::InvalidParts {..} => unreachable!("Only raw parts are validated"),
+                    #[cfg(feature = "diagnostics")]
+                    ReconstituteError::PointerSwapped {..} => unreachable!("The pointer has already been checked")
+                }
+            )
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+
+#[cfg(feature = "diagnostics")]
+use crate::{diagnostics, stats};
+use crate::{ContainerOptions, GuardPolicy, GuardState, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A pointer that has been handed back by the FFI but not yet reconstituted into a [Passable]
+///
+/// Created by [PassableGuard::mark_returned].
+/// The pointer check has already been performed and the guard has been disarmed,
+/// so the [Passable] can be rebuilt at a later point, possibly on another thread.
+///
+/// ### Notes
+/// Dropping this token without calling [Returned::reconstitute] leaks the underlying memory.
+/// With the `asan` feature, the memory is poisoned until the token is reconstituted
+#[must_use = "dropping a Returned token leaks the memory it points to"]
+pub struct Returned<PAS: Passable> {
+    ptr: *mut PAS::Pointee,
+    metadata: PAS::Metadata,
+    options: ContainerOptions,
+    _phantom: PhantomData<PAS>
+}
+
+impl<PAS: Passable> Debug for Returned<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Returned")
+            .field("ptr", &self.ptr)
+            .field("metadata", &self.metadata)
+            .finish()
+    }
+}
+
+// The token is the sole owner of the returned allocation, just like the Passable it will become
+unsafe impl<PAS: Passable + Send> Send for Returned<PAS> {}
+
+impl<PAS: Passable> Returned<PAS> {
+    /// Get the returned raw pointer
+    pub fn as_ptr(&self) -> *mut PAS::Pointee {
+        self.ptr
+    }
+
+    /// Reconstitute the returned pointer back into a [PassableContainer]
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply.
+    /// Additionally, the FFI must not use the pointer anymore once it has been marked as returned
+    pub unsafe fn reconstitute(self) -> Result<PassableContainer<PAS>, PAS::ReconstituteError> {
+        #[cfg(feature = "asan")]
+        crate::asan::unpoison::<PAS>(self.ptr, &self.metadata);
+        PAS::reconstitute(self.ptr, self.metadata)
+            .map(|passable| PassableContainer::with_options(passable, self.options))
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> PassableGuard<PAS, P> {
+    /// Check the pointer handed back by the FFI and disarm the guard, without reconstituting the [Passable] yet
+    ///
+    /// This decouples the FFI being done with the pointer from rebuilding the Rust value,
+    /// which can then be done later through [Returned::reconstitute]
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+    ///
+    /// let returned = guard.mark_returned(ptr).unwrap();
+    ///
+    /// let name = std::thread::spawn(move || {
+    ///     unsafe { returned.reconstitute() }.unwrap().into_inner()
+    /// }).join().unwrap();
+    ///
+    /// assert_eq!(name.to_str().unwrap(), "name");
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error if the pointer points do a different memory address then the pointer that was originally created by the pass method of the Container
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its [GuardPolicy]
+    pub fn mark_returned(mut self, ptr: *mut PAS::Pointee) -> Result<Returned<PAS>, ReconstituteError<PAS>> {
+        self.check(ptr)?;
+        // Keep the pointer the guard kept, whose provenance is not affected by what the FFI did with its copy
+        let ptr = self.ptr;
+        self.state = GuardState::Disarmed;
+        #[cfg(feature = "diagnostics")]
+        stats::record_reconstitution();
+        #[cfg(feature = "diagnostics")]
+        diagnostics::defused(&self);
+        // Nothing may use the buffer until the token is reconstituted
+        #[cfg(feature = "asan")]
+        crate::asan::poison::<PAS>(ptr, &self.metadata);
+
+        Ok(Returned {
+            ptr,
+            metadata: self.metadata,
+            options: self.options,
+            _phantom: Default::default()
+        })
+    }
+}
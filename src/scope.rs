@@ -0,0 +1,162 @@
+use std::fmt::{Display, Formatter};
+
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard};
+
+/// A [Passable] owned by a [PassScope] that could not be reconstituted when the scope ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScopeFailure {
+    /// The pointer that was passed for the [Passable]
+    pub ptr: *mut (),
+    /// The type name of the [Passable]
+    pub type_name: &'static str
+}
+
+impl Display for ScopeFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {:p} could not be reconstituted", self.type_name, self.ptr)
+    }
+}
+
+trait ScopeEntry {
+    unsafe fn finish(self: Box<Self>) -> Result<(), ScopeFailure>;
+}
+
+impl<PAS: Passable, P: GuardPolicy> ScopeEntry for PassableGuard<PAS, P> {
+    unsafe fn finish(self: Box<Self>) -> Result<(), ScopeFailure> {
+        let ptr = self.ptr;
+        self.reconstitute(ptr)
+            .map(drop)
+            .map_err(
+                |_|
+                    ScopeFailure {
+                        ptr: ptr as *mut (),
+                        type_name: std::any::type_name::<PAS>()
+                    }
+            )
+    }
+}
+
+/// An arena that owns everything passed through it until the scope ends
+///
+/// Pointers obtained from the scope stay valid until the scope is finished or dropped,
+/// at which point everything is reconstituted and dropped in one place.
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{PassableContainer, PassScope};
+///
+/// let mut scope = unsafe { PassScope::new() };
+///
+/// let first = scope.pass(PassableContainer::new(CString::new("first").unwrap()));
+/// let second = scope.pass(PassableContainer::new(CString::new("second").unwrap()));
+///
+/// // Make one big FFI call with first and second here
+///
+/// unsafe { scope.finish() }.unwrap();
+/// ```
+///
+/// The scope can own [Passable]s that borrow from the stack, like buffers lent to a scoped thread,
+/// since it cannot outlive anything passed through it
+///
+/// ``` rust
+/// use passable_guard::{PassableContainer, PassScope};
+///
+/// let mut buffers = [[0u8; 64]; 4];
+///
+/// std::thread::scope(|threads| {
+///     for buffer in buffers.iter_mut() {
+///         threads.spawn(move || {
+///             let mut scope = unsafe { PassScope::new() };
+///             let ptr = scope.pass(PassableContainer::new(&mut buffer[..]));
+///             // FFI call writing to ptr on this worker thread
+///             unsafe { ptr.write(1) };
+///             unsafe { scope.finish() }.unwrap();
+///         });
+///     }
+/// });
+///
+/// assert!(buffers.iter().all(|buffer| buffer[0] == 1));
+/// ```
+///
+/// ### Notes
+/// If the scope is dropped without being finished, every [Passable] that could not be reconstituted is reported to the registered [Reporter](crate::Reporter)s,
+/// or printed to stderr without a registered reporter or without the `diagnostics` feature
+pub struct PassScope<'a> {
+    entries: Vec<Box<dyn ScopeEntry + 'a>>
+}
+
+impl<'a> PassScope<'a> {
+    /// Creates a new, empty [PassScope]
+    ///
+    /// ### Safety
+    /// When the scope is finished or dropped, every pointer passed through it is reconstituted.
+    /// The FFI must be done with all those pointers by then and the same restrictions as for [PassableGuard::reconstitute] apply
+    pub const unsafe fn new() -> Self {
+        Self {
+            entries: Vec::new()
+        }
+    }
+
+    /// Get the number of [Passable]s owned by this scope
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether this scope owns no [Passable]s
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Convert the [PassableContainer] into a pointer that stays valid until this scope ends
+    pub fn pass<PAS: Passable + 'a>(&mut self, container: PassableContainer<PAS>) -> *mut PAS::Pointee {
+        let (guard, ptr) = container.pass();
+        self.entries.push(Box::new(guard));
+        ptr
+    }
+
+    /// Reconstitute and drop everything passed through this scope
+    ///
+    /// ### Errors
+    /// Will return a [ScopeFailure] for every [Passable] that could not be reconstituted
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassScope::new] apply
+    pub unsafe fn finish(mut self) -> Result<(), Vec<ScopeFailure>> {
+        let failures = self.finish_entries();
+        if failures.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(failures)
+        }
+    }
+
+    unsafe fn finish_entries(&mut self) -> Vec<ScopeFailure> {
+        self.entries.drain(..)
+            .filter_map(|entry| entry.finish().err())
+            .collect()
+    }
+}
+
+impl std::fmt::Debug for PassScope<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassScope")
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
+
+impl Drop for PassScope<'_> {
+    /// Reconstitutes and drops everything passed through this scope and reports failures to the registered [Reporter](crate::Reporter)s
+    ///
+    /// Without the `diagnostics` feature, failures are printed to stderr
+    fn drop(&mut self) {
+        // The caller of PassScope::new accepted that everything is reconstituted when the scope ends
+        for failure in unsafe { self.finish_entries() } {
+            #[cfg(feature = "diagnostics")]
+            crate::reporter::scope_failed(&failure);
+            #[cfg(not(feature = "diagnostics"))]
+            eprintln!("Pass Scope dropped: {}", failure);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Scoped passing
+//!
+//! The scoped API passes a [PassableContainer], hands the raw pointer to a closure
+//! and reconstitutes the container as soon as the closure returns,
+//! so the guard never has to be handled manually.
+
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::{GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError, ReportPolicy};
+
+/// The result of a scoped pass together with the recovered container
+pub(crate) type WithContainer<R, PAS> = (R, PassableContainer<PAS>);
+
+/// Reconstitute a guard with the pointer it was created for
+pub(crate) unsafe fn reconstitute_scoped<PAS: Passable, P: GuardPolicy>(guard: PassableGuard<PAS, P>, ptr: *mut PAS::Pointee) -> Result<PassableContainer<PAS>, PAS::ReconstituteError> {
+    match guard.reconstitute(ptr) {
+        Ok(container) => Ok(container),
+        Err(ReconstituteError::ReconstituteError {error}) => Err(error),
+        Err(ReconstituteError::PointerMismatch {..}) => unreachable!("The scoped pointer always matches its guard"),
+        Err(ReconstituteError::ContentModified {..}) => unreachable!("Only a ChecksumGuard verifies the content"),
+        Err(ReconstituteError::InvalidParts {..}) => unreachable!("Only raw parts are validated"),
+        #[cfg(feature = "diagnostics")]
+        Err(ReconstituteError::PointerSwapped {..}) => unreachable!("The scoped pointer always matches its guard")
+    }
+}
+
+/// An Error that can occur in [PassableContainer::try_pass_with]
+#[derive(Debug, Clone)]
+pub enum TryPassError<E, RE> {
+    /// The closure failed, the container has been reconstituted and dropped
+    Closure{error: E},
+    /// The container could not be reconstituted, the result of the closure has been discarded
+    ReconstituteError{error: RE}
+}
+
+impl<E: Display, RE: Display> Display for TryPassError<E, RE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TryPassError::Closure {error} => write!(f, "the scoped closure failed: {}", error),
+            TryPassError::ReconstituteError {error} => write!(f, "could not reconstitute the passable: {}", error)
+        }
+    }
+}
+
+impl<E: Error + 'static, RE: Error + 'static> Error for TryPassError<E, RE> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TryPassError::Closure {error} => Some(error),
+            TryPassError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+impl<PAS: Passable> PassableContainer<PAS> {
+    /// Pass the [PassableContainer], call `f` with the raw pointer and reconstitute the container afterwards
+    ///
+    /// Returns the result of `f` together with the recovered container
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// extern "C" {
+    ///     /// Takes a pointer to a NULL-Terminated utf-8 string
+    ///     /// Returns 0 on failure and >0 on success
+    ///     fn setName(ptr: *mut u8) -> u8;
+    /// }
+    ///
+    /// fn scoped_example(name: CString) -> Result<(), Box<dyn std::error::Error>> {
+    ///     let (result, _name) = unsafe {
+    ///         PassableContainer::new(name).pass_with(|ptr| setName(ptr))
+    ///     }?;
+    ///
+    ///     return if result == 0 {
+    ///         Err("setName failed".into())
+    ///     }
+    ///     else {
+    ///         Ok(())
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute](crate::PassableGuard::reconstitute) apply.
+    /// Additionally, `f` must make sure the FFI does not use the pointer after `f` returns
+    pub unsafe fn pass_with<R, F>(self, f: F) -> Result<WithContainer<R, PAS>, PAS::ReconstituteError>
+        where F: FnOnce(*mut PAS::Pointee) -> R
+    {
+        let (guard, ptr) = self.pass();
+
+        let result = f(ptr);
+
+        reconstitute_scoped(guard, ptr).map(|container| (result, container))
+    }
+
+    /// Pass the [PassableContainer], call the fallible `f` with the raw pointer and reconstitute the container afterwards
+    ///
+    /// The container is reconstituted regardless of whether `f` succeeds
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::{PassableContainer, TryPassError};
+    ///
+    /// let container = PassableContainer::new(CString::new("name").unwrap());
+    ///
+    /// let result = unsafe {
+    ///     container.try_pass_with(|_ptr| Err::<(), _>("FFI call failed"))
+    /// };
+    ///
+    /// assert!(matches!(result, Err(TryPassError::Closure {error: "FFI call failed"})));
+    /// ```
+    ///
+    /// ### Errors
+    /// Will return [TryPassError::Closure] if `f` fails
+    /// Will return [TryPassError::ReconstituteError] if the memory was modified by the FFI, even if `f` failed as well
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableContainer::pass_with] apply
+    pub unsafe fn try_pass_with<T, E, F>(self, f: F) -> Result<WithContainer<T, PAS>, TryPassError<E, PAS::ReconstituteError>>
+        where F: FnOnce(*mut PAS::Pointee) -> Result<T, E>
+    {
+        let (result, container) = self.pass_with(f)
+            .map_err(
+                |error|
+                    TryPassError::ReconstituteError {error}
+            )?;
+
+        result
+            .map(|value| (value, container))
+            .map_err(
+                |error|
+                    TryPassError::Closure {error}
+            )
+    }
+
+    /// Pass the [PassableContainer], await the future created by `f` with the raw pointer and reconstitute the container once it completes
+    ///
+    /// The guard is held across all `.await` points of the future.
+    /// If the returned [PassWithFuture] is dropped before it completes, the FFI might still be using the pointer,
+    /// so the memory is leaked and reported according to [ReportPolicy] instead of panicking inside the executor.
+    ///
+    /// ``` rust
+    /// use std::ffi::CString;
+    /// use passable_guard::PassableContainer;
+    ///
+    /// async fn async_example(name: CString) -> CString {
+    ///     let (length, name) = unsafe {
+    ///         PassableContainer::new(name).pass_with_async(|ptr| async move {
+    ///             // Await the FFI call using ptr here
+    ///             4
+    ///         })
+    ///     }.await.unwrap();
+    ///
+    ///     name.into_inner()
+    /// }
+    /// ```
+    ///
+    /// ### Errors
+    /// The future will resolve to an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableContainer::pass_with] apply,
+    /// the FFI must not use the pointer after the future created by `f` completes
+    pub unsafe fn pass_with_async<R, F, Fut>(self, f: F) -> PassWithFuture<PAS, ReportPolicy, Fut>
+        where F: FnOnce(*mut PAS::Pointee) -> Fut,
+              Fut: Future<Output = R>
+    {
+        self.pass_with_async_policy(f)
+    }
+
+    /// Like [PassableContainer::pass_with_async], but the [GuardPolicy] `P` decides what happens if the future is dropped before it completes
+    ///
+    /// ### Errors
+    /// The future will resolve to an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableContainer::pass_with_async] apply
+    pub unsafe fn pass_with_async_policy<P, R, F, Fut>(self, f: F) -> PassWithFuture<PAS, P, Fut>
+        where P: GuardPolicy,
+              F: FnOnce(*mut PAS::Pointee) -> Fut,
+              Fut: Future<Output = R>
+    {
+        let (guard, ptr) = self.pass_with_policy();
+        PassWithFuture {
+            future: f(ptr),
+            guard: Some(guard),
+            ptr
+        }
+    }
+}
+
+/// The future created by [PassableContainer::pass_with_async]
+///
+/// Resolves to the output of the inner future together with the recovered container
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct PassWithFuture<PAS: Passable, P: GuardPolicy, Fut> {
+    // The inner future has to be dropped before the guard applies its policy
+    future: Fut,
+    guard: Option<PassableGuard<PAS, P>>,
+    ptr: *mut PAS::Pointee
+}
+
+impl<PAS: Passable, P: GuardPolicy, Fut> Debug for PassWithFuture<PAS, P, Fut> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PassWithFuture")
+            .field("guard", &self.guard)
+            .finish()
+    }
+}
+
+// The future owns the passed memory through its guard, just like the Passable it will become
+unsafe impl<PAS: Passable + Send, P: GuardPolicy, Fut: Send> Send for PassWithFuture<PAS, P, Fut> {}
+
+impl<PAS: Passable, P: GuardPolicy, Fut: Future> Future for PassWithFuture<PAS, P, Fut> {
+    type Output = Result<WithContainer<Fut::Output, PAS>, PAS::ReconstituteError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // The inner future is never moved out of the pinned struct
+        let this = unsafe { self.get_unchecked_mut() };
+        let future = unsafe { Pin::new_unchecked(&mut this.future) };
+
+        let result = match future.poll(cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => return Poll::Pending
+        };
+
+        let guard = this.guard.take().expect("PassWithFuture polled after completion");
+
+        // The safety requirements have been accepted by the caller of pass_with_async
+        let container = unsafe { reconstitute_scoped(guard, this.ptr) };
+        Poll::Ready(container.map(|container| (result, container)))
+    }
+}
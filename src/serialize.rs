@@ -0,0 +1,69 @@
+//! Serialization of the diagnostic report types
+//!
+//! Pointers are serialized as hexadecimal strings, since they are only meaningful for humans reading the report
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[cfg(feature = "diagnostics")]
+use crate::registry::{GuardRecord, Report};
+use crate::{ChildInfo, LeakInfo};
+
+fn format_ptr(ptr: *mut ()) -> String {
+    format!("{:p}", ptr)
+}
+
+impl Serialize for LeakInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LeakInfo", 7)?;
+        state.serialize_field("message", self.message)?;
+        state.serialize_field("type_name", self.type_name)?;
+        state.serialize_field("ptr", &format_ptr(self.ptr))?;
+        state.serialize_field("label", &self.label)?;
+        state.serialize_field("policy", &self.policy)?;
+        state.serialize_field("held_for", &self.held_for.map(|held_for| held_for.as_secs_f64()))?;
+        state.serialize_field("children", &self.children)?;
+        state.end()
+    }
+}
+
+impl Serialize for ChildInfo {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ChildInfo", 2)?;
+        state.serialize_field("ptr", &format_ptr(self.ptr))?;
+        state.serialize_field("type_name", self.type_name)?;
+        state.end()
+    }
+}
+
+/// The creation time is serialized as the number of seconds the guard has been armed for, if known,
+/// since an [Instant](std::time::Instant) has no meaning outside of the process
+#[cfg(feature = "diagnostics")]
+impl Serialize for GuardRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let fields = if cfg!(feature = "tokio") {12} else {10};
+        let mut state = serializer.serialize_struct("GuardRecord", fields)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("type_name", self.type_name)?;
+        state.serialize_field("ptr", &format_ptr(self.ptr))?;
+        state.serialize_field("size", &self.size)?;
+        state.serialize_field("label", &self.label)?;
+        state.serialize_field("armed_for", &self.held_for().map(|held_for| held_for.as_secs_f64()))?;
+        state.serialize_field("location", &self.location.to_string())?;
+        state.serialize_field("thread_id", &format!("{:?}", self.thread_id))?;
+        state.serialize_field("thread_name", &self.thread_name)?;
+        #[cfg(feature = "tokio")]
+        state.serialize_field("task_id", &self.task_id.map(|id| id.to_string()))?;
+        #[cfg(feature = "tokio")]
+        state.serialize_field("task_name", &self.task_name)?;
+        state.serialize_field("backtrace", &self.backtrace.as_ref().map(ToString::to_string))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "diagnostics")]
+impl Serialize for Report {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.records())
+    }
+}
@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+
+use crate::{ConfiguredPolicy, GuardPolicy, Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A collection of outstanding guards that can be looked up by their pointer
+///
+/// Useful when a batch of buffers is passed to a FFI that hands them back one by one, for example via a callback.
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{GuardSet, PassableContainer};
+///
+/// let mut set = GuardSet::new();
+/// let mut pointers = Vec::new();
+///
+/// for name in ["first", "second"].iter() {
+///     let (guard, ptr) = PassableContainer::new(CString::new(*name).unwrap()).pass();
+///     set.insert(guard);
+///     pointers.push(ptr);
+/// }
+///
+/// // The FFI hands back the second pointer first
+/// let second = unsafe { set.reconstitute(pointers[1]) }.unwrap().unwrap();
+/// assert_eq!(second.into_inner().to_str().unwrap(), "second");
+///
+/// let remaining = unsafe { set.reconstitute_all() };
+/// assert_eq!(remaining.len(), 1);
+/// ```
+///
+/// ### Panic
+/// Every guard still contained in the set when it is dropped applies its [GuardPolicy]
+pub struct GuardSet<PAS: Passable, P: GuardPolicy = ConfiguredPolicy> {
+    // Removed guards leave a hole to keep the insertion order, until the set is compacted
+    guards: Vec<Option<PassableGuard<PAS, P>>>,
+    indices: HashMap<*mut PAS::Pointee, usize>
+}
+
+impl<PAS: Passable, P: GuardPolicy> GuardSet<PAS, P> {
+    /// Creates a new, empty [GuardSet]
+    pub fn new() -> Self {
+        Self {
+            guards: Vec::new(),
+            indices: HashMap::new()
+        }
+    }
+
+    /// Get the number of guards in the set
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Check whether the set contains no guards
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Insert a guard into the set
+    ///
+    /// Returns the guard that was previously stored for the same pointer, if any.
+    /// Guards of distinct values can share a pointer when nothing is allocated for them,
+    /// like empty [Vec]s or Boxes of zero-sized types, so this can happen with valid input
+    pub fn insert(&mut self, guard: PassableGuard<PAS, P>) -> Option<PassableGuard<PAS, P>> {
+        let previous = self.remove(guard.ptr);
+        self.indices.insert(guard.ptr, self.guards.len());
+        self.guards.push(Some(guard));
+        previous
+    }
+
+    /// Check whether the set contains a guard for `ptr`
+    pub fn contains(&self, ptr: *mut PAS::Pointee) -> bool {
+        self.indices.contains_key(&ptr)
+    }
+
+    /// Get the guard for `ptr`
+    pub fn get(&self, ptr: *mut PAS::Pointee) -> Option<&PassableGuard<PAS, P>> {
+        self.indices.get(&ptr)
+            .and_then(|index| self.guards[*index].as_ref())
+    }
+
+    /// Remove the guard for `ptr` from the set and return it
+    pub fn remove(&mut self, ptr: *mut PAS::Pointee) -> Option<PassableGuard<PAS, P>> {
+        let guard = self.indices.remove(&ptr)
+            .and_then(|index| self.guards[index].take());
+        if self.guards.len() > 2 * self.indices.len() {
+            self.compact();
+        }
+        guard
+    }
+
+    /// Close the holes left by removed guards, once they make up more than half of the slots
+    fn compact(&mut self) {
+        self.guards.retain(Option::is_some);
+        for (index, guard) in self.guards.iter().flatten().enumerate() {
+            self.indices.insert(guard.ptr, index);
+        }
+    }
+
+    /// Get the pointers of all guards in the set, in insertion order
+    pub fn pointers(&self) -> Vec<*mut PAS::Pointee> {
+        self.guards.iter()
+            .flatten()
+            .map(|guard| guard.ptr)
+            .collect()
+    }
+
+    /// Reconstitute the guard for `ptr` back into a [PassableContainer] and remove it from the set
+    ///
+    /// Returns [None] if the set does not contain a guard for `ptr`
+    ///
+    /// ### Errors
+    /// Will return an Error if the memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(&mut self, ptr: *mut PAS::Pointee) -> Option<Result<PassableContainer<PAS>, ReconstituteError<PAS>>> {
+        self.remove(ptr)
+            .map(|guard| guard.reconstitute(ptr))
+    }
+
+    /// Reconstitute all guards in the set back into [PassableContainer]s, in insertion order
+    ///
+    /// ### Errors
+    /// Will return an Error for every guard whose memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply to every guard in the set.
+    /// The FFI must be done with all pointers in the set
+    pub unsafe fn reconstitute_all(mut self) -> Vec<Result<PassableContainer<PAS>, ReconstituteError<PAS>>> {
+        self.indices.clear();
+        self.guards.drain(..)
+            .flatten()
+            .map(|guard| {
+                let ptr = guard.ptr;
+                guard.reconstitute(ptr)
+            })
+            .collect()
+    }
+
+    /// Reconstitute the guards for `pointers` back into [PassableContainer]s, in the order of `pointers`
+    ///
+    /// This consumes the pointers created by [PassAll::pass_all] again.
+    /// Any guard in the set whose pointer is not contained in `pointers` is dropped while still armed
+    ///
+    /// Returns [None] for every pointer the set does not contain a guard for
+    ///
+    /// ### Errors
+    /// Will return an Error for every guard whose memory was modified by the FFI
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply to every guard in the set
+    pub unsafe fn reconstitute_pointers<I>(mut self, pointers: I) -> Vec<Option<Result<PassableContainer<PAS>, ReconstituteError<PAS>>>>
+        where I: IntoIterator<Item = *mut PAS::Pointee>
+    {
+        pointers.into_iter()
+            .map(|ptr| self.reconstitute(ptr))
+            .collect()
+    }
+}
+
+/// Pass every [PassableContainer] of an iterator at once, collecting the guards into a [GuardSet]
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::{PassableContainer, PassAll};
+///
+/// let containers = vec![
+///     PassableContainer::new(CString::new("first").unwrap()),
+///     PassableContainer::new(CString::new("second").unwrap())
+/// ];
+///
+/// let (set, pointers) = containers.into_iter().pass_all();
+/// assert_eq!(set.len(), 2);
+///
+/// // FFI call with pointers
+///
+/// let names = unsafe { set.reconstitute_pointers(pointers) };
+/// assert_eq!(names.len(), 2);
+/// ```
+pub trait PassAll<PAS: Passable>: Iterator<Item = PassableContainer<PAS>> + Sized {
+    /// Pass every Container, getting a [GuardSet] with their guards and the pointers in iteration order
+    ///
+    /// ### Panic
+    /// Containers that pass the same pointer, like empty [Vec]s, displace each other in the set,
+    /// and every displaced guard is dropped while still armed and applies its [GuardPolicy]
+    fn pass_all(self) -> (GuardSet<PAS>, Vec<*mut PAS::Pointee>) {
+        let mut set = GuardSet::new();
+        let pointers = self
+            .map(|container| {
+                let (guard, ptr) = container.pass();
+                set.insert(guard);
+                ptr
+            })
+            .collect();
+        (set, pointers)
+    }
+}
+
+impl<PAS: Passable, I: Iterator<Item = PassableContainer<PAS>>> PassAll<PAS> for I {}
+
+impl<PAS: Passable, P: GuardPolicy> Debug for GuardSet<PAS, P> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.guards.iter().flatten())
+            .finish()
+    }
+}
+
+impl<PAS: Passable, P: GuardPolicy> Default for GuardSet<PAS, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
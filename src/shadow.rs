@@ -0,0 +1,109 @@
+//! Shadow-copy verification
+//!
+//! A [ShadowGuard] takes a snapshot of the passed buffer and compares it against the buffer handed back by the FFI,
+//! reporting exactly which byte ranges have been modified.
+//! This is meant for debugging FFIs that should not touch a buffer but somehow corrupt it anyway.
+//!
+//! ``` rust
+//! use passable_guard::PassableContainer;
+//!
+//! let (guard, ptr) = unsafe { PassableContainer::new(vec![0u8; 8]).pass_shadowed() };
+//!
+//! // The FFI modifies the buffer
+//! unsafe {
+//!     *ptr.add(2) = 1;
+//!     *ptr.add(3) = 1;
+//!     *ptr.add(6) = 1;
+//! }
+//!
+//! let (_container, modified) = unsafe { guard.reconstitute(ptr) }.unwrap();
+//! assert_eq!(modified, vec![2..4, 6..7]);
+//! ```
+
+use std::ops::Range;
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+/// The container and the modified byte ranges of a reconstituted [ShadowGuard]
+type WithModified<PAS> = (PassableContainer<PAS>, Vec<Range<usize>>);
+
+/// A guard that keeps a snapshot of the passed buffer to detect modifications by the FFI
+///
+/// Created by [PassableContainer::pass_shadowed]
+///
+/// ### Panic
+/// If this guard is dropped before it has been reconstituted, it will panic just like a [PassableGuard]
+#[derive(Debug)]
+pub struct ShadowGuard<PAS: PassableLen> {
+    guard: PassableGuard<PAS>,
+    snapshot: Vec<u8>
+}
+
+impl<PAS: PassableLen> ShadowGuard<PAS> {
+    /// Get the byte ranges of the passed buffer that differ from the snapshot
+    ///
+    /// ### Safety
+    /// The buffer must not be modified concurrently by the FFI while it is compared
+    pub unsafe fn modified(&self) -> Vec<Range<usize>> {
+        let current = std::slice::from_raw_parts(self.guard.ptr as *const u8, self.snapshot.len());
+        diff(&self.snapshot, current)
+    }
+
+    /// Reconstitute a raw pointer back into a [PassableContainer] and get the byte ranges the FFI has modified
+    ///
+    /// ### Errors
+    /// Will return an Error under the same conditions as [PassableGuard::reconstitute]
+    ///
+    /// ### Panic
+    /// If the pointers do not match, the guard is dropped while still armed and will apply its policy
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<WithModified<PAS>, ReconstituteError<PAS>> {
+        self.guard.check(ptr)?;
+
+        let modified = self.modified();
+        self.guard
+            .reconstitute(ptr)
+            .map(|container| (container, modified))
+    }
+
+    /// Discard the snapshot and get the plain [PassableGuard]
+    pub fn into_guard(self) -> PassableGuard<PAS> {
+        self.guard
+    }
+}
+
+/// Collect the ranges in which two equally long byte slices differ
+fn diff(snapshot: &[u8], current: &[u8]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+
+    for (index, (old, new)) in snapshot.iter().zip(current).enumerate() {
+        if old == new {
+            continue;
+        }
+
+        match ranges.last_mut() {
+            Some(range) if range.end == index => range.end += 1,
+            _ => ranges.push(index..index + 1)
+        }
+    }
+
+    ranges
+}
+
+impl<PAS: PassableLen> PassableContainer<PAS> {
+    /// Convert the [PassableContainer] into a pointer and a [ShadowGuard] that remembers the passed buffer
+    ///
+    /// ### Safety
+    /// The buffer is compared bytewise, so the pointee type must not contain any padding bytes
+    #[track_caller]
+    pub unsafe fn pass_shadowed(self) -> (ShadowGuard<PAS>, *mut PAS::Pointee) {
+        let (guard, ptr) = self.pass();
+
+        let size = PAS::passed_len(&guard.metadata) * std::mem::size_of::<<PAS as Passable>::Pointee>();
+        let snapshot = std::slice::from_raw_parts(ptr as *const u8, size).to_vec();
+
+        (ShadowGuard {guard, snapshot}, ptr)
+    }
+}
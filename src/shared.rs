@@ -0,0 +1,276 @@
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::{ConfiguredPolicy, GuardPolicy, LeakInfo, Passable};
+
+enum SharedState<PAS: Passable> {
+    Idle(PAS),
+    Passed{ptr: *mut PAS::Pointee, metadata: PAS::Metadata, outstanding: usize},
+    Poisoned
+}
+
+struct SharedInner<PAS: Passable> {
+    state: Mutex<SharedState<PAS>>
+}
+
+impl<PAS: Passable> SharedInner<PAS> {
+    fn lock(&self) -> MutexGuard<'_, SharedState<PAS>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<PAS: Passable> Drop for SharedInner<PAS> {
+    /// This function will apply the configured [DropPolicy](crate::DropPolicy) if a pass is still outstanding
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+        if let SharedState::Passed {ptr, metadata, ..} = std::mem::replace(state, SharedState::Poisoned) {
+            let leak = LeakInfo::new::<PAS, PAS::Pointee>("Shared Container dropped with outstanding passes", ptr);
+            ConfiguredPolicy::armed_drop(&leak, || {
+                // The FFI is done with every outstanding pass if the policy frees
+                drop(unsafe { PAS::reconstitute(ptr, metadata) });
+            });
+        }
+    }
+}
+
+/// An Error that can occur while handing back a pass with [SharedGuard::reconstitute]
+pub enum SharedError<PAS: Passable> {
+    /// The pointer did not match, the guard is handed back so the pass can be handed back with the correct pointer
+    PointerMismatch{guard: SharedGuard<PAS>, passed: *mut PAS::Pointee, reconstituted: *mut PAS::Pointee},
+    /// The last outstanding pass was handed back but the [Passable] could not be reconstituted, it is lost
+    ReconstituteError{error: PAS::ReconstituteError}
+}
+
+// The pointers are only kept for diagnostics and are never dereferenced
+unsafe impl<PAS: Passable + Send> Send for SharedError<PAS> where PAS::ReconstituteError: Send {}
+unsafe impl<PAS: Passable + Send> Sync for SharedError<PAS> where PAS::ReconstituteError: Sync {}
+
+impl<PAS: Passable> SharedError<PAS> {
+    /// Get back the guard of the still outstanding pass if the pointer did not match
+    pub fn into_guard(self) -> Option<SharedGuard<PAS>> {
+        match self {
+            SharedError::PointerMismatch {guard, ..} => Some(guard),
+            SharedError::ReconstituteError {..} => None
+        }
+    }
+}
+
+impl<PAS: Passable> Debug for SharedError<PAS> where PAS::ReconstituteError: Debug {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SharedError::PointerMismatch {guard, passed, reconstituted} => f.debug_struct("PointerMismatch")
+                .field("guard", guard)
+                .field("passed", passed)
+                .field("reconstituted", reconstituted)
+                .finish(),
+            SharedError::ReconstituteError {error} => f.debug_struct("ReconstituteError")
+                .field("error", error)
+                .finish()
+        }
+    }
+}
+
+impl<PAS: Passable> Display for SharedError<PAS> where PAS::ReconstituteError: Display {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SharedError::PointerMismatch {passed, reconstituted, ..} =>
+                write!(f, "pointer mismatch, passed {:p} but reconstituted {:p}", passed, reconstituted),
+            SharedError::ReconstituteError {error} =>
+                write!(f, "could not reconstitute the passable: {}", error)
+        }
+    }
+}
+
+impl<PAS: Passable> Error for SharedError<PAS> where PAS::ReconstituteError: Error + 'static {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SharedError::PointerMismatch {..} => None,
+            SharedError::ReconstituteError {error} => Some(error)
+        }
+    }
+}
+
+/// A thread-safe Container that can be passed over a FFI boundary repeatedly from multiple call sites
+///
+/// Cloning the Container creates another handle to the same [Passable].
+/// Passing it while a pass is already outstanding hands out the same pointer again,
+/// the [Passable] is only reconstituted once every pass has been reconstituted.
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::SharedContainer;
+///
+/// let config = SharedContainer::new(CString::new("config").unwrap());
+///
+/// let handles = (0..4)
+///     .map(|_| {
+///         let config = config.clone();
+///         std::thread::spawn(move || {
+///             let (guard, ptr) = config.pass().unwrap();
+///             // FFI call with ptr
+///             unsafe { guard.reconstitute(ptr) }.unwrap();
+///         })
+///     })
+///     .collect::<Vec<_>>();
+///
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// assert_eq!(config.outstanding(), 0);
+/// ```
+///
+/// ### Notes
+/// If the last handle to the Container is dropped while a pass is still outstanding,
+/// the default policy of the global [Config](crate::Config) is applied, which panics unless configured otherwise
+pub struct SharedContainer<PAS: Passable> {
+    inner: Arc<SharedInner<PAS>>
+}
+
+// The state is only accessed behind the mutex and the pointer is only handed out, never dereferenced
+unsafe impl<PAS: Passable + Send> Send for SharedContainer<PAS> {}
+unsafe impl<PAS: Passable + Send> Sync for SharedContainer<PAS> {}
+
+impl<PAS: Passable> SharedContainer<PAS> {
+    /// Creates a new [SharedContainer] from a [Passable]
+    pub fn new(passable: PAS) -> Self {
+        Self {
+            inner: Arc::new(SharedInner {
+                state: Mutex::new(SharedState::Idle(passable))
+            })
+        }
+    }
+
+    /// Get the number of passes that have not been reconstituted yet
+    pub fn outstanding(&self) -> usize {
+        match *self.inner.lock() {
+            SharedState::Passed {outstanding, ..} => outstanding,
+            _ => 0
+        }
+    }
+
+    /// Convert the [Passable] into a pointer to pass it over a FFI boundary, or hand out the same pointer again
+    ///
+    /// Returns [None] if a previous reconstitution failed and the [Passable] has been lost
+    pub fn pass(&self) -> Option<(SharedGuard<PAS>, *mut PAS::Pointee)> {
+        let mut state = self.inner.lock();
+
+        let ptr = match std::mem::replace(&mut *state, SharedState::Poisoned) {
+            SharedState::Idle(passable) => {
+                let (ptr, metadata) = passable.pass();
+                *state = SharedState::Passed {ptr, metadata, outstanding: 1};
+                ptr
+            }
+            SharedState::Passed {ptr, metadata, outstanding} => {
+                *state = SharedState::Passed {ptr, metadata, outstanding: outstanding + 1};
+                ptr
+            }
+            SharedState::Poisoned => return None
+        };
+
+        let guard = SharedGuard {
+            inner: self.inner.clone()
+        };
+        Some((guard, ptr))
+    }
+}
+
+impl<PAS: Passable> Clone for SharedContainer<PAS> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone()
+        }
+    }
+}
+
+impl<PAS: Passable> Debug for SharedContainer<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedContainer")
+            .field("outstanding", &self.outstanding())
+            .finish()
+    }
+}
+
+/// A guard for a single outstanding pass of a [SharedContainer]
+///
+/// ### Notes
+/// Dropping this guard without reconstituting it leaves the pass outstanding,
+/// so the [SharedContainer] applies its policy once its last handle is dropped
+#[must_use = "the pass stays outstanding until the guard is reconstituted"]
+pub struct SharedGuard<PAS: Passable> {
+    inner: Arc<SharedInner<PAS>>
+}
+
+// Same reasoning as for the SharedContainer the guard belongs to
+unsafe impl<PAS: Passable + Send> Send for SharedGuard<PAS> {}
+unsafe impl<PAS: Passable + Send> Sync for SharedGuard<PAS> {}
+
+impl<PAS: Passable> SharedGuard<PAS> {
+    /// Hand back the pointer of this pass
+    ///
+    /// Once every outstanding pass has been handed back, the [Passable] is reconstituted
+    /// and the next pass converts it into a pointer again
+    ///
+    /// ### Errors
+    /// Will return an Error if the pointer points do a different memory address then the pointer that was handed out,
+    /// the pass then stays outstanding and the guard is handed back inside the Error.
+    /// Will return an Error if the memory was modified by the FFI, the [Passable] is lost in that case
+    ///
+    /// ``` rust
+    /// use passable_guard::SharedContainer;
+    ///
+    /// let buffer = SharedContainer::new(vec![0u8; 16]);
+    /// let (guard, ptr) = buffer.pass().unwrap();
+    ///
+    /// let guard = unsafe { guard.reconstitute(std::ptr::null_mut()) }.unwrap_err().into_guard().unwrap();
+    /// assert_eq!(buffer.outstanding(), 1);
+    ///
+    /// unsafe { guard.reconstitute(ptr) }.unwrap();
+    /// assert_eq!(buffer.outstanding(), 0);
+    /// ```
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute](crate::PassableGuard::reconstitute) apply
+    /// once the last outstanding pass is handed back
+    pub unsafe fn reconstitute(self, ptr: *mut PAS::Pointee) -> Result<(), SharedError<PAS>> {
+        let mut state = self.inner.lock();
+
+        match std::mem::replace(&mut *state, SharedState::Poisoned) {
+            SharedState::Passed {ptr: passed, metadata, outstanding} => {
+                if passed != ptr {
+                    *state = SharedState::Passed {ptr: passed, metadata, outstanding};
+                    drop(state);
+                    return Err(
+                        SharedError::PointerMismatch {
+                            guard: self,
+                            passed,
+                            reconstituted: ptr
+                        }
+                    );
+                }
+
+                if outstanding > 1 {
+                    *state = SharedState::Passed {ptr: passed, metadata, outstanding: outstanding - 1};
+                    return Ok(());
+                }
+
+                // Reconstitute from the kept pointer, whose provenance is not affected by what the FFI did with its copies
+                let passable = PAS::reconstitute(passed, metadata)
+                    .map_err(
+                        |error|
+                            SharedError::ReconstituteError {error}
+                    )?;
+                *state = SharedState::Idle(passable);
+                Ok(())
+            }
+            _ => unreachable!("A shared guard only exists while its pass is outstanding")
+        }
+    }
+}
+
+impl<PAS: Passable> Debug for SharedGuard<PAS> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedGuard")
+            .finish()
+    }
+}
@@ -0,0 +1,82 @@
+//! Orderly shutdown of async runtimes
+//!
+//! Shutting down an executor drops every future that has not completed yet,
+//! together with the guards they hold across `.await` points.
+//! Between [begin_shutdown] and the end of the returned [ShutdownScope], every [CancelGuard](crate::CancelGuard)
+//! dropped while still armed applies the [CancelFallback] of the shutdown instead of its own,
+//! and every other guard is reported and leaked instead of applying its [GuardPolicy](crate::GuardPolicy),
+//! so a clean shutdown does not turn into a cascade of guard panics.
+//!
+//! Only a [CancelGuard](crate::CancelGuard) is known to hold a [Passable](crate::Passable) that is `Send + 'static`,
+//! so it is the only guard that can be deferred and freed later from any thread
+//!
+//! ``` rust
+//! use passable_guard::{CancelFallback, PassableContainer};
+//!
+//! let (guard, _ptr) = PassableContainer::new(vec![0u8; 64]).pass_cancellable(CancelFallback::Report);
+//! let pending_task = async move {
+//!     // Await the FFI operation here
+//!     let _guard = guard;
+//! };
+//!
+//! let shutdown = passable_guard::begin_shutdown(CancelFallback::Defer);
+//! // Shut down the runtime, dropping `pending_task` without panicking
+//! drop(pending_task);
+//! drop(shutdown);
+//!
+//! // Once the FFI library is shut down as well
+//! assert_eq!(unsafe { passable_guard::free_deferred() }, 1);
+//! ```
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::CancelFallback;
+
+const RUNNING: u8 = 0;
+const REPORT: u8 = 1;
+const DEFER: u8 = 2;
+
+static FALLBACK: AtomicU8 = AtomicU8::new(RUNNING);
+
+/// A running shutdown, ended when this is dropped
+///
+/// Created by [begin_shutdown]
+#[derive(Debug)]
+#[must_use = "the shutdown ends as soon as the ShutdownScope is dropped"]
+pub struct ShutdownScope {
+    previous: u8
+}
+
+impl Drop for ShutdownScope {
+    fn drop(&mut self) {
+        FALLBACK.store(self.previous, Ordering::SeqCst);
+    }
+}
+
+/// Apply `fallback` to every [CancelGuard](crate::CancelGuard) dropped while still armed, on any thread,
+/// and report and leak every other armed guard, until the returned [ShutdownScope] is dropped
+///
+/// Starting a shutdown while another one is running replaces its fallback until the inner one ends
+pub fn begin_shutdown(fallback: CancelFallback) -> ShutdownScope {
+    let fallback = match fallback {
+        CancelFallback::Report => REPORT,
+        CancelFallback::Defer => DEFER
+    };
+    ShutdownScope {
+        previous: FALLBACK.swap(fallback, Ordering::SeqCst)
+    }
+}
+
+/// Check whether a shutdown is running
+pub fn is_shutting_down() -> bool {
+    FALLBACK.load(Ordering::SeqCst) != RUNNING
+}
+
+/// Get the fallback of the running shutdown, if any
+pub(crate) fn fallback() -> Option<CancelFallback> {
+    match FALLBACK.load(Ordering::SeqCst) {
+        REPORT => Some(CancelFallback::Report),
+        DEFER => Some(CancelFallback::Defer),
+        _ => None
+    }
+}
@@ -0,0 +1,175 @@
+//! Guards for composite data that exposes several pointers
+//!
+//! A [Splittable] value is passed as a tuple of [Passable]s at once.
+//! The resulting [SplitGuard] can then be split into one [PassableGuard] per pointer,
+//! so every part can be reconstituted independently as its pointer comes back from the FFI.
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use passable_guard::Splittable;
+//!
+//! struct Person {
+//!     name: CString,
+//!     email: CString
+//! }
+//!
+//! impl Splittable for Person {
+//!     type Parts = (CString, CString);
+//!
+//!     fn into_parts(self) -> Self::Parts {
+//!         (self.name, self.email)
+//!     }
+//! }
+//!
+//! let person = Person {
+//!     name: CString::new("name").unwrap(),
+//!     email: CString::new("email").unwrap()
+//! };
+//!
+//! let (guard, (name_ptr, email_ptr)) = person.pass_split();
+//! let (name_guard, email_guard) = guard.split();
+//!
+//! // The pointers come back from different callbacks
+//! unsafe { email_guard.reconstitute(email_ptr) }.unwrap();
+//! unsafe { name_guard.reconstitute(name_ptr) }.unwrap();
+//! ```
+//!
+//! If the pointers come back together, all parts can also be reconstituted at once
+//!
+//! ``` rust
+//! use std::ffi::CString;
+//! use passable_guard::Splittable;
+//!
+//! let parts = (CString::new("name").unwrap(), vec![1u8, 2, 3], CString::new("email").unwrap());
+//!
+//! let (guard, pointers) = parts.pass_split();
+//! let (name, bytes, email) = unsafe { guard.reconstitute(pointers) };
+//!
+//! assert_eq!(name.unwrap().to_str().unwrap(), "name");
+//! assert_eq!(bytes.unwrap().into_inner(), vec![1, 2, 3]);
+//! assert_eq!(email.unwrap().to_str().unwrap(), "email");
+//! ```
+
+use std::fmt::{Debug, Formatter};
+
+use crate::{Passable, PassableContainer, PassableGuard, ReconstituteError};
+
+/// A tuple of [Passable]s that can be passed at once
+///
+/// This is implemented for tuples of two up to twelve [Passable]s
+pub trait PassParts {
+    /// A tuple of one [PassableGuard] per part
+    type Guards: Debug;
+    /// A tuple of one pointer per part
+    type Pointers;
+    /// A tuple of the result of reconstituting each part
+    type Reconstituted;
+
+    /// Pass every part, getting a guard and a pointer for each of them
+    fn pass_parts(self) -> (Self::Guards, Self::Pointers);
+
+    /// Reconstitute every part with its pointer
+    ///
+    /// ### Panic
+    /// If a pointer does not match, its guard is dropped while still armed and will apply its [GuardPolicy](crate::GuardPolicy)
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply to every part
+    unsafe fn reconstitute_parts(guards: Self::Guards, pointers: Self::Pointers) -> Self::Reconstituted;
+}
+
+/// Implement [PassParts] and [Splittable] for the tuple of the given parts and every shorter tuple of at least two
+///
+/// Every part is given as its type parameter followed by the name of its pointer,
+/// the longest list sets the largest supported arity
+macro_rules! impl_pass_parts {
+    ($first:ident $first_ptr:ident, $second:ident $second_ptr:ident) => {
+        impl_pass_parts!(@impl $first $first_ptr, $second $second_ptr);
+    };
+    ($first:ident $first_ptr:ident, $($part:ident $ptr:ident),+) => {
+        impl_pass_parts!(@impl $first $first_ptr, $($part $ptr),+);
+        impl_pass_parts!($($part $ptr),+);
+    };
+    (@impl $($part:ident $ptr:ident),+) => {
+        impl<$($part: Passable),+> PassParts for ($($part,)+) {
+            type Guards = ($(PassableGuard<$part>,)+);
+            type Pointers = ($(*mut $part::Pointee,)+);
+            type Reconstituted = ($(Result<PassableContainer<$part>, ReconstituteError<$part>>,)+);
+
+            #[allow(non_snake_case)]
+            fn pass_parts(self) -> (Self::Guards, Self::Pointers) {
+                let ($($part,)+) = self;
+                $(let $part = PassableContainer::new($part).pass();)+
+                (($($part.0,)+), ($($part.1,)+))
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn reconstitute_parts(guards: Self::Guards, pointers: Self::Pointers) -> Self::Reconstituted {
+                let ($($part,)+) = guards;
+                let ($($ptr,)+) = pointers;
+                ($($part.reconstitute($ptr),)+)
+            }
+        }
+
+        impl<$($part: Passable),+> Splittable for ($($part,)+) {
+            type Parts = Self;
+
+            fn into_parts(self) -> Self::Parts {
+                self
+            }
+        }
+    };
+}
+
+impl_pass_parts!(A a, B b, C c, D d, E e, F f, G g, H h, I i, J j, K k, L l);
+
+/// Composite data that can be passed as several independent pointers
+pub trait Splittable: Sized {
+    /// The [Passable] parts of this value
+    type Parts: PassParts;
+
+    /// Break this value up into its [Passable] parts
+    fn into_parts(self) -> Self::Parts;
+
+    /// Pass every part of this value, getting a single [SplitGuard] and a pointer for each part
+    fn pass_split(self) -> (SplitGuard<Self>, <Self::Parts as PassParts>::Pointers) {
+        let (guards, pointers) = self.into_parts().pass_parts();
+        (SplitGuard {guards}, pointers)
+    }
+}
+
+/// A single guard for all parts of a [Splittable] value
+///
+/// ### Panic
+/// If this guard is dropped before it has been split, every part will panic just like a [PassableGuard]
+pub struct SplitGuard<S: Splittable> {
+    guards: <S::Parts as PassParts>::Guards
+}
+
+impl<S: Splittable> SplitGuard<S> {
+    /// Split this guard into one [PassableGuard] per part, in the order of [Splittable::Parts]
+    pub fn split(self) -> <S::Parts as PassParts>::Guards {
+        self.guards
+    }
+
+    /// Reconstitute every part at once, if all pointers came back together
+    ///
+    /// Every part is reconstituted independently, so a part that fails does not hold back the others
+    ///
+    /// ### Panic
+    /// If a pointer does not match, its guard is dropped while still armed and will apply its [GuardPolicy](crate::GuardPolicy)
+    ///
+    /// ### Safety
+    /// The same restrictions as for [PassableGuard::reconstitute] apply to every part
+    pub unsafe fn reconstitute(self, pointers: <S::Parts as PassParts>::Pointers) -> <S::Parts as PassParts>::Reconstituted {
+        S::Parts::reconstitute_parts(self.guards, pointers)
+    }
+}
+
+impl<S: Splittable> Debug for SplitGuard<S> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SplitGuard")
+            .field("guards", &self.guards)
+            .finish()
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use crate::atomic::AtomicU64;
+
+/// The upper bounds of the buckets of [Stats::hold_histogram], the last bucket holds everything above
+pub const HOLD_HISTOGRAM_BOUNDS: [Duration; 5] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10)
+];
+
+static OUTSTANDING: AtomicU64 = AtomicU64::new(0);
+static OUTSTANDING_BYTES: AtomicU64 = AtomicU64::new(0);
+static PASSES: AtomicU64 = AtomicU64::new(0);
+static RECONSTITUTIONS: AtomicU64 = AtomicU64::new(0);
+static LEAKS: AtomicU64 = AtomicU64::new(0);
+static HOLD_HISTOGRAM: [AtomicU64; 6] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0)
+];
+
+/// A snapshot of the runtime statistics of all guards in the process
+///
+/// Created by [stats]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of guards that are currently alive
+    pub outstanding: u64,
+    /// The total size of the buffers of all guards that are currently alive, as far as it is known
+    ///
+    /// See [Passable::passed_size](crate::Passable::passed_size)
+    pub outstanding_bytes: u64,
+    /// The number of guards that have been created
+    pub passes: u64,
+    /// The number of guards whose pointer has been handed back
+    pub reconstitutions: u64,
+    /// The number of guards that have been dropped while still armed
+    pub leaks: u64,
+    /// How long the sampled guards that are no longer alive were held, bucketed by [HOLD_HISTOGRAM_BOUNDS]
+    pub hold_histogram: [u64; 6]
+}
+
+/// Get a snapshot of the runtime statistics of all guards in the process
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use passable_guard::PassableContainer;
+///
+/// let (guard, ptr) = PassableContainer::new(vec![0u8; 16]).pass();
+///
+/// let stats = passable_guard::stats();
+/// assert_eq!(stats.outstanding, 1);
+/// assert_eq!(stats.outstanding_bytes, 16);
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+///
+/// let stats = passable_guard::stats();
+/// assert_eq!(stats.outstanding, 0);
+/// assert_eq!(stats.reconstitutions, 1);
+/// assert_eq!(stats.hold_histogram.iter().sum::<u64>(), 1);
+/// ```
+///
+/// ### Notes
+/// The counters are updated independently, so a snapshot taken while other threads pass or reconstitute is not necessarily consistent
+pub fn stats() -> Stats {
+    Stats {
+        outstanding: OUTSTANDING.load(Ordering::Relaxed),
+        outstanding_bytes: OUTSTANDING_BYTES.load(Ordering::Relaxed),
+        passes: PASSES.load(Ordering::Relaxed),
+        reconstitutions: RECONSTITUTIONS.load(Ordering::Relaxed),
+        leaks: LEAKS.load(Ordering::Relaxed),
+        hold_histogram: [
+            HOLD_HISTOGRAM[0].load(Ordering::Relaxed),
+            HOLD_HISTOGRAM[1].load(Ordering::Relaxed),
+            HOLD_HISTOGRAM[2].load(Ordering::Relaxed),
+            HOLD_HISTOGRAM[3].load(Ordering::Relaxed),
+            HOLD_HISTOGRAM[4].load(Ordering::Relaxed),
+            HOLD_HISTOGRAM[5].load(Ordering::Relaxed)
+        ]
+    }
+}
+
+pub(crate) fn record_pass(bytes: Option<usize>) {
+    PASSES.fetch_add(1, Ordering::Relaxed);
+    OUTSTANDING.fetch_add(1, Ordering::Relaxed);
+    OUTSTANDING_BYTES.fetch_add(bytes.unwrap_or(0) as u64, Ordering::Relaxed);
+}
+
+pub(crate) fn record_release(bytes: Option<usize>, held_for: Option<Duration>) {
+    OUTSTANDING.fetch_sub(1, Ordering::Relaxed);
+    OUTSTANDING_BYTES.fetch_sub(bytes.unwrap_or(0) as u64, Ordering::Relaxed);
+
+    let held_for = match held_for {
+        Some(held_for) => held_for,
+        None => return
+    };
+    let bucket = HOLD_HISTOGRAM_BOUNDS.iter()
+        .position(|bound| held_for < *bound)
+        .unwrap_or(HOLD_HISTOGRAM_BOUNDS.len());
+    HOLD_HISTOGRAM[bucket].fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_reconstitution() {
+    RECONSTITUTIONS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn record_leak() {
+    LEAKS.fetch_add(1, Ordering::Relaxed);
+}
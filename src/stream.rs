@@ -0,0 +1,216 @@
+//! Streaming buffers into a FFI that consumes them asynchronously
+//!
+//! Encoders and similar libraries take one buffer after another
+//! and signal through a "buffer consumed" callback when they are done with each of them.
+//! A [BufferPipe] is a [Sink] that passes every buffer sent into it and a [Stream] of the buffers the library has consumed,
+//! so they can be refilled and sent again. At most `capacity` buffers are in flight at once
+
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::os::raw::c_void;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::{Passable, PassableContainer, PassableGuard, PassableLen, ReconstituteError};
+
+/// A buffer reconstituted after the library consumed it
+type Consumed<PAS> = Result<PassableContainer<PAS>, ReconstituteError<PAS>>;
+
+struct State<PAS: Passable> {
+    in_flight: usize,
+    consumed: VecDeque<Consumed<PAS>>,
+    closed: bool,
+    sink_waker: Option<Waker>,
+    stream_waker: Option<Waker>
+}
+
+/// The state shared by a [BufferPipe] and the buffers it has in flight
+struct Shared<PAS: Passable> {
+    state: Mutex<State<PAS>>
+}
+
+impl<PAS: Passable> Shared<PAS> {
+    fn lock(&self) -> MutexGuard<'_, State<PAS>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A buffer in flight, handed to the library as the context of its consumed callback
+struct InFlight<PAS: Passable> {
+    guard: PassableGuard<PAS>,
+    shared: Arc<Shared<PAS>>
+}
+
+/// A [Sink] passing buffers to a FFI and a [Stream] of the buffers it has consumed
+///
+/// ``` rust
+/// use std::os::raw::c_void;
+/// use futures::{SinkExt, StreamExt};
+/// use passable_guard::BufferPipe;
+///
+/// extern "C" fn encoder_push(frame: *mut u8, len: usize, done: unsafe extern "C" fn(*mut c_void, *mut c_void), context: *mut c_void) {
+///     // A real encoder would consume the frame later, from any thread
+///     unsafe { done(context, frame as *mut c_void) };
+/// }
+///
+/// # futures::executor::block_on(async {
+/// let mut pipe = unsafe {
+///     BufferPipe::new(2, |frame, len, context| encoder_push(frame, len, BufferPipe::<Vec<u8>>::CONSUMED, context))
+/// };
+///
+/// pipe.send(vec![0u8; 1024]).await.unwrap();
+///
+/// // Get the consumed frame back to refill it
+/// let frame = pipe.next().await.unwrap().unwrap();
+/// assert_eq!(frame.len(), 1024);
+/// # });
+/// ```
+///
+/// ### Notes
+/// If the pipe is dropped while buffers are still in flight, they are reconstituted and dropped once the library consumes them.
+/// Buffers the library never consumes are leaked.
+/// A buffer consumed with a different pointer than it was passed with applies the policy of its guard,
+/// without unwinding into the library, and is yielded as the Error
+pub struct BufferPipe<PAS: Passable, F = fn(*mut <PAS as Passable>::Pointee, usize, *mut c_void)> {
+    shared: Arc<Shared<PAS>>,
+    capacity: usize,
+    submit: F
+}
+
+// The pipe only holds the pointers and metadata of buffers that are Send,
+// so it can move between the threads of an executor
+unsafe impl<PAS: Passable + Send, F: Send> Send for BufferPipe<PAS, F> {}
+
+impl<PAS: PassableLen, F: FnMut(*mut PAS::Pointee, usize, *mut c_void)> BufferPipe<PAS, F> {
+    /// The consumed callback to hand to the library with the context, `void (*)(void *context, void *buffer)`
+    pub const CONSUMED: unsafe extern "C" fn(*mut c_void, *mut c_void) = consumed::<PAS>;
+
+    /// Create a pipe with at most `capacity` buffers in flight
+    ///
+    /// `submit` is called with the pointer and length of every buffer sent into the pipe and a context pointer,
+    /// it hands them to the library together with [BufferPipe::CONSUMED].
+    /// A capacity of 0 is treated as 1
+    ///
+    /// ### Safety
+    /// The library has to call [BufferPipe::CONSUMED] exactly once for every buffer, with its context and pointer,
+    /// once it no longer uses the buffer.
+    /// The same restrictions as for [PassableGuard::reconstitute] apply
+    pub unsafe fn new(capacity: usize, submit: F) -> Self {
+        let state = State {
+            in_flight: 0,
+            consumed: VecDeque::new(),
+            closed: false,
+            sink_waker: None,
+            stream_waker: None
+        };
+        Self {
+            shared: Arc::new(Shared {state: Mutex::new(state)}),
+            capacity: capacity.max(1),
+            submit
+        }
+    }
+
+    /// Get the number of buffers the library has not consumed yet
+    pub fn in_flight(&self) -> usize {
+        self.shared.lock().in_flight
+    }
+}
+
+unsafe extern "C" fn consumed<PAS: Passable>(context: *mut c_void, ptr: *mut c_void) {
+    let InFlight {guard, shared} = *Box::from_raw(context as *mut InFlight<PAS>);
+    let result = guard.reconstitute_in_callback(ptr as *mut PAS::Pointee);
+
+    let (sink_waker, stream_waker) = {
+        let mut state = shared.lock();
+        state.in_flight -= 1;
+        state.consumed.push_back(result);
+        (state.sink_waker.take(), state.stream_waker.take())
+    };
+    if let Some(waker) = sink_waker {
+        waker.wake();
+    }
+    if let Some(waker) = stream_waker {
+        waker.wake();
+    }
+}
+
+impl<PAS: PassableLen, F: FnMut(*mut PAS::Pointee, usize, *mut c_void) + Unpin> Sink<PAS> for BufferPipe<PAS, F> {
+    type Error = Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut state = self.shared.lock();
+        if state.in_flight < self.capacity {
+            return Poll::Ready(Ok(()));
+        }
+        state.sink_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: PAS) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let (guard, ptr, len) = PassableContainer::new(item).pass_with_len();
+        this.shared.lock().in_flight += 1;
+        let context = Box::into_raw(Box::new(InFlight {
+            guard,
+            shared: this.shared.clone()
+        }));
+        (this.submit)(ptr, len, context as *mut c_void);
+        Ok(())
+    }
+
+    /// Wait until the library has consumed every buffer in flight
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut state = self.shared.lock();
+        if state.in_flight == 0 {
+            return Poll::Ready(Ok(()));
+        }
+        state.sink_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+
+    /// Wait until the library has consumed every buffer in flight and end the stream of consumed buffers after them
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let stream_waker = {
+            let mut state = self.shared.lock();
+            state.closed = true;
+            state.stream_waker.take()
+        };
+        if let Some(waker) = stream_waker {
+            waker.wake();
+        }
+        self.poll_flush(cx)
+    }
+}
+
+impl<PAS: Passable, F: Unpin> Stream for BufferPipe<PAS, F> {
+    type Item = Consumed<PAS>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.lock();
+        if let Some(consumed) = state.consumed.pop_front() {
+            return Poll::Ready(Some(consumed));
+        }
+        if state.closed && state.in_flight == 0 {
+            return Poll::Ready(None);
+        }
+        state.stream_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<PAS: Passable, F> Debug for BufferPipe<PAS, F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let state = self.shared.lock();
+        f.debug_struct("BufferPipe")
+            .field("capacity", &self.capacity)
+            .field("in_flight", &state.in_flight)
+            .field("consumed", &state.consumed.len())
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
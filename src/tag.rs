@@ -0,0 +1,117 @@
+//! Type tags for context pointers
+//!
+//! A context pointer handed to C loses its type, and converting it back with the wrong [Passable] type
+//! silently reinterprets the guard and the buffer behind it.
+//! Tagged contexts carry a small hidden header with a hash of their type in front of the guard,
+//! so converting one back as the wrong type yields a [TypeTagError] instead:
+//!
+//! ``` rust
+//! use passable_guard::{CompletionGuard, PassableContainer};
+//!
+//! let (guard, ptr) = PassableContainer::new(vec![0u8; 64]).pass_completion();
+//! let context = guard.into_tagged_context();
+//!
+//! // The callback was registered for the wrong type
+//! let error = unsafe { CompletionGuard::<String>::from_tagged_context(context) }.unwrap_err();
+//! assert!(error.found.unwrap().contains("Vec<u8>"));
+//!
+//! // The context is left untouched, so it can still be converted back as the right type
+//! let guard = unsafe { CompletionGuard::<Vec<u8>>::from_tagged_context(context) }.unwrap();
+//! unsafe { guard.reconstitute(ptr) }.unwrap();
+//! ```
+//!
+//! Only the context pointers of completion guards are tagged, through [CompletionGuard::into_tagged_context](crate::CompletionGuard::into_tagged_context)
+//! and [GuardCompletion::into_tagged_context](crate::GuardCompletion::into_tagged_context).
+//! Passed pointers carry no header, since the FFI reads the buffer behind them directly,
+//! so reconstituting a [PassableGuard](crate::PassableGuard) is not covered.
+//! The `RawGuard` and `GuardHandle` of the `c-api` feature are not tagged either:
+//! a `RawGuard` checks its type when it is converted back and hands itself back on a mismatch,
+//! and a `GuardHandle` always releases the type-erased guard it was created for.
+
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_void;
+
+/// Marks a header written by [into_tagged], to catch contexts that were never tagged
+const MAGIC: u64 = 0x7061_7373_6761_7264;
+
+/// The hidden header in front of a tagged value
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TypeTag {
+    magic: u64,
+    hash: u64,
+    type_name: &'static str
+}
+
+impl TypeTag {
+    fn of<T>() -> Self {
+        let type_name = std::any::type_name::<T>();
+        let mut hasher = DefaultHasher::new();
+        type_name.hash(&mut hasher);
+        Self {
+            magic: MAGIC,
+            hash: hasher.finish(),
+            type_name
+        }
+    }
+}
+
+#[repr(C)]
+struct Tagged<T> {
+    tag: TypeTag,
+    value: T
+}
+
+/// An Error returned when a tagged context pointer is converted back as a different type than it was created for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeTagError {
+    /// The type the context was converted back as
+    pub expected: &'static str,
+    /// The type the context was created for, or [None] if the context was never tagged
+    pub found: Option<&'static str>
+}
+
+impl Display for TypeTagError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.found {
+            Some(found) => write!(f, "context tagged as {} was converted back as {}", found, self.expected),
+            None => write!(f, "untagged context was converted back as {}", self.expected)
+        }
+    }
+}
+
+impl Error for TypeTagError {}
+
+/// Move a value behind a type tag and get the pointer to it
+pub(crate) fn into_tagged<T>(value: T) -> *mut c_void {
+    let tagged = Tagged {
+        tag: TypeTag::of::<T>(),
+        value
+    };
+    Box::into_raw(Box::new(tagged)) as *mut c_void
+}
+
+/// Take back a value created by [into_tagged], if its tag matches `T`
+///
+/// ### Errors
+/// Leaves the value behind the pointer untouched if its tag does not match `T`
+///
+/// ### Safety
+/// `ptr` has to point to at least the size of a tag of readable memory, like any pointer created by [into_tagged],
+/// and a value with a matching tag must not have been taken before
+pub(crate) unsafe fn from_tagged<T>(ptr: *mut c_void) -> Result<T, TypeTagError> {
+    let expected = TypeTag::of::<T>();
+    // The magic is the first field of every tagged value, whatever its type
+    let found = std::ptr::read(ptr as *const u64);
+    if found != MAGIC {
+        return Err(TypeTagError {expected: expected.type_name, found: None});
+    }
+    let found = std::ptr::read(ptr as *const TypeTag);
+    if found.hash != expected.hash || found.type_name != expected.type_name {
+        return Err(TypeTagError {expected: expected.type_name, found: Some(found.type_name)});
+    }
+    Ok(Box::from_raw(ptr as *mut Tagged<T>).value)
+}
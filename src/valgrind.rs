@@ -0,0 +1,68 @@
+//! Memcheck client requests
+//!
+//! The requests are encoded with the magic instruction sequences from `valgrind.h`,
+//! which do nothing when the process is not running under Valgrind.
+//! On architectures without a known sequence and under Miri, which cannot run inline assembly, every request is a no-op.
+
+use std::ffi::CStr;
+
+const MEMCHECK_BASE: u64 = ((b'M' as u64) << 24) | ((b'C' as u64) << 16);
+const CHECK_MEM_IS_ADDRESSABLE: u64 = MEMCHECK_BASE + 4;
+const DISCARD: u64 = MEMCHECK_BASE + 3;
+const CREATE_BLOCK: u64 = MEMCHECK_BASE + 10;
+
+#[cfg(all(target_arch = "x86_64", not(miri)))]
+fn client_request(default: u64, args: [u64; 6]) -> u64 {
+    let mut result = default;
+    // The rotations of rdi add up to 128 bits, so its value is restored
+    unsafe {
+        std::arch::asm!(
+            "rol rdi, 3",
+            "rol rdi, 13",
+            "rol rdi, 61",
+            "rol rdi, 51",
+            "xchg rbx, rbx",
+            in("rax") args.as_ptr(),
+            inout("rdx") result,
+        );
+    }
+    result
+}
+
+#[cfg(all(target_arch = "aarch64", not(miri)))]
+fn client_request(default: u64, args: [u64; 6]) -> u64 {
+    let mut result = default;
+    // The rotations of x12 add up to 128 bits, so its value is restored
+    unsafe {
+        std::arch::asm!(
+            "ror x12, x12, #3",
+            "ror x12, x12, #13",
+            "ror x12, x12, #51",
+            "ror x12, x12, #61",
+            "orr x10, x10, x10",
+            in("x4") args.as_ptr(),
+            inout("x3") result,
+        );
+    }
+    result
+}
+
+#[cfg(any(miri, not(any(target_arch = "x86_64", target_arch = "aarch64"))))]
+fn client_request(default: u64, _args: [u64; 6]) -> u64 {
+    default
+}
+
+/// Describe the block at `ptr` with `description` in Memcheck reports, returning a handle for [discard]
+pub(crate) fn create_block(ptr: *mut (), len: usize, description: &CStr) -> u64 {
+    client_request(0, [CREATE_BLOCK, ptr as u64, len as u64, description.as_ptr() as u64, 0, 0])
+}
+
+/// Remove the description of a block created with [create_block]
+pub(crate) fn discard(block: u64) {
+    client_request(0, [DISCARD, 0, block, 0, 0, 0]);
+}
+
+/// Let Memcheck report an error if the memory at `ptr` is no longer addressable, for example because the FFI freed it
+pub(crate) fn check_addressable(ptr: *mut (), len: usize) {
+    client_request(0, [CHECK_MEM_IS_ADDRESSABLE, ptr as u64, len as u64, 0, 0, 0]);
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, Once, PoisonError};
+use std::time::Duration;
+
+use crate::atomic::AtomicU64;
+use crate::events::{self, GuardEvent, GuardInfo};
+use crate::{registry, reporter};
+
+static THRESHOLD_NANOS: AtomicU64 = AtomicU64::new(0);
+static REPORTED: Mutex<Option<HashSet<u64>>> = Mutex::new(None);
+
+/// Start a background thread that reports every guard held longer than `threshold`
+///
+/// Each stale guard is reported once, as a [GuardEvent::Stale] through the [event hook](crate::set_event_hook)
+/// and to the registered [Reporter](crate::Reporter)s.
+/// Calling this again only changes the threshold, there is at most one watchdog per process.
+/// This enables the registry, since only recorded guards can be watched
+///
+/// ``` rust
+/// use std::ffi::CString;
+/// use std::sync::mpsc;
+/// use std::time::Duration;
+/// use passable_guard::{GuardEvent, PassableContainer};
+///
+/// let (sender, receiver) = mpsc::channel();
+/// let sender = std::sync::Mutex::new(sender);
+/// passable_guard::set_event_hook(move |event| {
+///     if let GuardEvent::Stale {guard} = event {
+///         sender.lock().unwrap().send(guard.id).unwrap();
+///     }
+/// });
+/// passable_guard::enable_watchdog(Duration::from_millis(10));
+///
+/// let (guard, ptr) = PassableContainer::new(CString::new("name").unwrap()).pass();
+///
+/// // The pointer is never handed back in time, so the watchdog reports the guard
+/// assert_eq!(receiver.recv().unwrap(), guard.id());
+///
+/// unsafe { guard.reconstitute(ptr) }.unwrap();
+/// ```
+///
+/// ### Notes
+/// If the target cannot spawn threads, like most `wasm32` targets, no thread is started.
+/// Call [check_watchdog] periodically instead, for example from a timer of the host
+pub fn enable_watchdog(threshold: Duration) {
+    static SPAWN: Once = Once::new();
+
+    registry::enable();
+    let nanos = threshold.as_nanos().clamp(1, u64::MAX as u128) as u64;
+    THRESHOLD_NANOS.store(nanos, Ordering::Relaxed);
+
+    SPAWN.call_once(|| {
+        // Failing to spawn leaves the checks to check_watchdog
+        let _ = std::thread::Builder::new()
+            .name("passable_guard-watchdog".to_owned())
+            .spawn(watch);
+    });
+}
+
+/// Report every guard held longer than the threshold of the watchdog right now, without waiting for the watchdog thread
+///
+/// Does nothing until [enable_watchdog] has been called, or if the target has no clock, like `wasm32-unknown-unknown`
+pub fn check_watchdog() {
+    let threshold = match THRESHOLD_NANOS.load(Ordering::Relaxed) {
+        0 => return,
+        nanos => Duration::from_nanos(nanos)
+    };
+
+    let active = registry::active();
+    let mut stale = Vec::new();
+    {
+        let mut reported = REPORTED.lock().unwrap_or_else(PoisonError::into_inner);
+        let reported = reported.get_or_insert_with(HashSet::new);
+        let active_ids = active.iter()
+            .map(|record| record.id)
+            .collect::<HashSet<_>>();
+        reported.retain(|id| active_ids.contains(id));
+
+        for record in active {
+            let held_for = match record.held_for() {
+                Some(held_for) => held_for,
+                None => continue
+            };
+            if held_for >= threshold && reported.insert(record.id) {
+                stale.push(GuardInfo {
+                    id: record.id,
+                    type_name: record.type_name,
+                    ptr: record.ptr,
+                    label: record.label,
+                    held_for: Some(held_for)
+                });
+            }
+        }
+    }
+
+    // Reported without holding the lock, so a hook may check again itself
+    for guard in stale {
+        reporter::watchdog_alert(&guard);
+        events::emit(GuardEvent::Stale {guard});
+    }
+}
+
+fn watch() {
+    loop {
+        let threshold = Duration::from_nanos(THRESHOLD_NANOS.load(Ordering::Relaxed));
+        std::thread::sleep((threshold / 2).max(Duration::from_millis(1)));
+
+        check_watchdog();
+    }
+}
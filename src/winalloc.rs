@@ -0,0 +1,213 @@
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::c_void;
+
+use crate::{Passable, PassableLen};
+
+const LPTR: u32 = 0x0040;
+const GPTR: u32 = 0x0040;
+
+#[link(name = "ole32")]
+extern "system" {
+    fn CoTaskMemAlloc(size: usize) -> *mut c_void;
+    fn CoTaskMemFree(ptr: *mut c_void);
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn LocalAlloc(flags: u32, size: usize) -> *mut c_void;
+    fn LocalFree(memory: *mut c_void) -> *mut c_void;
+    fn GlobalAlloc(flags: u32, size: usize) -> *mut c_void;
+    fn GlobalFree(memory: *mut c_void) -> *mut c_void;
+}
+
+/// A Windows allocator that a [WinBuffer] can be allocated with
+///
+/// ### Safety
+/// Memory returned by [allocate](WindowsAllocator::allocate) has to be valid for `size` bytes,
+/// zeroed and releasable with [free](WindowsAllocator::free)
+pub unsafe trait WindowsAllocator {
+    /// Allocate `size` zeroed bytes, returning null on failure
+    fn allocate(size: usize) -> *mut u8;
+
+    /// Free memory returned by [allocate](WindowsAllocator::allocate)
+    ///
+    /// ### Safety
+    /// `ptr` has to be returned by [allocate](WindowsAllocator::allocate) and must not be used afterwards
+    unsafe fn free(ptr: *mut u8);
+}
+
+/// Memory from `CoTaskMemAlloc`, freed with `CoTaskMemFree`, as expected by most COM and shell APIs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CoTaskMem;
+
+unsafe impl WindowsAllocator for CoTaskMem {
+    fn allocate(size: usize) -> *mut u8 {
+        let ptr = unsafe { CoTaskMemAlloc(size) } as *mut u8;
+        if !ptr.is_null() {
+            unsafe { std::ptr::write_bytes(ptr, 0, size) }
+        }
+        ptr
+    }
+
+    unsafe fn free(ptr: *mut u8) {
+        CoTaskMemFree(ptr as *mut c_void);
+    }
+}
+
+/// Fixed memory from `LocalAlloc`, freed with `LocalFree`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Local;
+
+unsafe impl WindowsAllocator for Local {
+    fn allocate(size: usize) -> *mut u8 {
+        unsafe { LocalAlloc(LPTR, size) as *mut u8 }
+    }
+
+    unsafe fn free(ptr: *mut u8) {
+        LocalFree(ptr as *mut c_void);
+    }
+}
+
+/// Fixed memory from `GlobalAlloc`, freed with `GlobalFree`, as expected by the clipboard and DDE APIs
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+unsafe impl WindowsAllocator for Global {
+    fn allocate(size: usize) -> *mut u8 {
+        unsafe { GlobalAlloc(GPTR, size) as *mut u8 }
+    }
+
+    unsafe fn free(ptr: *mut u8) {
+        GlobalFree(ptr as *mut c_void);
+    }
+}
+
+/// A byte buffer allocated with the Windows allocator `A`, which is also used to free it
+///
+/// The allocator is part of the type, so it shows up in every report of a guard for the buffer.
+/// Give an API that frees the buffer itself the pointer from [PassableContainer::leak](crate::PassableContainer::leak)
+///
+/// ``` rust
+/// use passable_guard::{CoTaskMem, PassableContainer, WinBuffer};
+///
+/// let buffer = WinBuffer::<CoTaskMem>::from_slice(b"data");
+/// let (guard, ptr) = PassableContainer::new(buffer).pass();
+///
+/// // Hand `ptr` to an API that reads the buffer but does not take ownership
+///
+/// let buffer = unsafe { guard.reconstitute(ptr) }.unwrap().into_inner();
+/// assert_eq!(&*buffer, b"data");
+/// ```
+pub struct WinBuffer<A: WindowsAllocator> {
+    ptr: *mut u8,
+    len: usize,
+    _allocator: PhantomData<A>
+}
+
+impl<A: WindowsAllocator> WinBuffer<A> {
+    /// Allocate a zeroed buffer of `len` bytes
+    ///
+    /// ### Panic
+    /// Panics if the buffer could not be allocated
+    pub fn new(len: usize) -> Self {
+        // Allocating 0 bytes is not guaranteed to return a unique pointer by every allocator
+        let ptr = A::allocate(len.max(1));
+        assert!(!ptr.is_null(), "Could not allocate {} bytes with {}", len, std::any::type_name::<A>());
+        Self {
+            ptr,
+            len,
+            _allocator: PhantomData
+        }
+    }
+
+    /// Allocate a buffer containing a copy of `data`
+    ///
+    /// ### Panic
+    /// Panics if the buffer could not be allocated
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut buffer = Self::new(data.len());
+        buffer.copy_from_slice(data);
+        buffer
+    }
+
+    /// Take ownership of a buffer of `len` bytes, for example one returned by an API
+    ///
+    /// ### Safety
+    /// `ptr` has to be allocated by `A`, valid for `len` bytes and not owned by anything else
+    pub unsafe fn from_raw(ptr: *mut u8, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            _allocator: PhantomData
+        }
+    }
+
+    /// Give up ownership of the buffer, it has to be freed with `A`
+    pub fn into_raw(self) -> *mut u8 {
+        ManuallyDrop::new(self).ptr
+    }
+}
+
+impl<A: WindowsAllocator> Deref for WinBuffer<A> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl<A: WindowsAllocator> DerefMut for WinBuffer<A> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl<A: WindowsAllocator> Drop for WinBuffer<A> {
+    fn drop(&mut self) {
+        unsafe { A::free(self.ptr) }
+    }
+}
+
+impl<A: WindowsAllocator> Debug for WinBuffer<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WinBuffer")
+            .field("allocator", &std::any::type_name::<A>())
+            .field("ptr", &self.ptr)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl<A: WindowsAllocator> Passable for WinBuffer<A> {
+    type Pointee = u8;
+    type ReconstituteError = Infallible;
+    /// The length of the buffer in bytes
+    type Metadata = usize;
+
+    fn pass(self) -> (*mut u8, Self::Metadata) {
+        let len = self.len;
+        (self.into_raw(), len)
+    }
+
+    unsafe fn reconstitute(ptr: *mut u8, metadata: Self::Metadata) -> Result<Self, Self::ReconstituteError> {
+        Ok(WinBuffer::from_raw(ptr, metadata))
+    }
+
+    fn passed_size(metadata: &Self::Metadata) -> Option<usize> {
+        Some(*metadata)
+    }
+
+    fn passed_extent(metadata: &Self::Metadata) -> Option<usize> {
+        Self::passed_size(metadata)
+    }
+}
+
+impl<A: WindowsAllocator> PassableLen for WinBuffer<A> {
+    fn passed_len(metadata: &Self::Metadata) -> usize {
+        *metadata
+    }
+}